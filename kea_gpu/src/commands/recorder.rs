@@ -5,7 +5,7 @@ use crate::{
     pipelines::{Pipeline, PipelineLayout},
 };
 use ash::vk;
-use std::sync::Arc;
+use std::{slice, sync::Arc};
 
 pub struct CommandBufferRecorder<'a> {
     buffer: &'a CommandBuffer,
@@ -51,6 +51,24 @@ impl<'a> CommandBufferRecorder<'a> {
         }
     }
 
+    pub fn push_constants<T: Copy>(
+        &self,
+        layout: &PipelineLayout,
+        stages: vk::ShaderStageFlags,
+        constants: &T,
+    ) {
+        unsafe {
+            let (_, constants, _) = slice::from_ref(constants).align_to::<u8>();
+            self.device().raw().cmd_push_constants(
+                self.buffer.raw(),
+                layout.raw(),
+                stages,
+                0,
+                constants,
+            );
+        }
+    }
+
     pub fn pipeline_barrier(
         &self,
         dependency_flags: vk::DependencyFlags,
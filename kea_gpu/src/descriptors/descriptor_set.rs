@@ -12,9 +12,28 @@ impl DescriptorSetLayout {
         device: Arc<Device>,
         bindings: &[DescriptorSetLayoutBinding<'_>],
     ) -> DescriptorSetLayout {
+        let binding_flags: Vec<vk::DescriptorBindingFlags> =
+            bindings.iter().map(|b| b.flags).collect();
+        let mut binding_flags_info =
+            vk::DescriptorSetLayoutBindingFlagsCreateInfo::default().binding_flags(&binding_flags);
+
+        // Bindings that can be updated after being bound need a layout that
+        // says so.
+        let flags = if binding_flags
+            .iter()
+            .any(|flags| flags.contains(vk::DescriptorBindingFlags::UPDATE_AFTER_BIND))
+        {
+            vk::DescriptorSetLayoutCreateFlags::UPDATE_AFTER_BIND_POOL
+        } else {
+            vk::DescriptorSetLayoutCreateFlags::empty()
+        };
+
         let bindings: Vec<vk::DescriptorSetLayoutBinding<'_>> =
             bindings.iter().map(|b| b.raw).collect();
-        let create_info = vk::DescriptorSetLayoutCreateInfo::default().bindings(&bindings);
+        let create_info = vk::DescriptorSetLayoutCreateInfo::default()
+            .flags(flags)
+            .bindings(&bindings)
+            .push_next(&mut binding_flags_info);
         let raw = unsafe {
             device
                 .raw()
@@ -46,6 +65,7 @@ impl Drop for DescriptorSetLayout {
 
 pub struct DescriptorSetLayoutBinding<'a> {
     raw: vk::DescriptorSetLayoutBinding<'a>,
+    flags: vk::DescriptorBindingFlags,
 }
 
 impl<'a> DescriptorSetLayoutBinding<'a> {
@@ -60,7 +80,16 @@ impl<'a> DescriptorSetLayoutBinding<'a> {
             .descriptor_type(descriptor_type)
             .descriptor_count(descriptor_count)
             .stage_flags(stage_flags);
-        DescriptorSetLayoutBinding { raw }
+        DescriptorSetLayoutBinding {
+            raw,
+            flags: vk::DescriptorBindingFlags::empty(),
+        }
+    }
+
+    /// Set descriptor indexing flags, such as `PARTIALLY_BOUND` for arrays
+    /// that needn't be filled.
+    pub fn with_flags(self, flags: vk::DescriptorBindingFlags) -> Self {
+        Self { flags, ..self }
     }
 }
 
@@ -74,8 +103,10 @@ impl DescriptorPool {
         device: Arc<Device>,
         max_sets: u32,
         pool_sizes: &[vk::DescriptorPoolSize],
+        flags: vk::DescriptorPoolCreateFlags,
     ) -> Arc<DescriptorPool> {
         let create_info = vk::DescriptorPoolCreateInfo::default()
+            .flags(flags)
             .max_sets(max_sets)
             .pool_sizes(pool_sizes);
         let raw = unsafe { device.raw().create_descriptor_pool(&create_info, None) }.unwrap();
@@ -51,9 +51,17 @@ pub fn create_device(
     let mut features_12 = vk::PhysicalDeviceVulkan12Features::default()
         .buffer_device_address(true)
         .vulkan_memory_model(true)
-        .timeline_semaphore(true);
+        .timeline_semaphore(true)
+        .descriptor_indexing(true)
+        .runtime_descriptor_array(true)
+        .descriptor_binding_partially_bound(true)
+        .descriptor_binding_storage_buffer_update_after_bind(true)
+        .descriptor_binding_sampled_image_update_after_bind(true)
+        .shader_storage_buffer_array_non_uniform_indexing(true)
+        .shader_sampled_image_array_non_uniform_indexing(true);
     let mut features_13 = vk::PhysicalDeviceVulkan13Features::default()
         .synchronization2(true)
+        .dynamic_rendering(true)
         .maintenance4(true);
     let mut features_rt =
         vk::PhysicalDeviceRayTracingPipelineFeaturesKHR::default().ray_tracing_pipeline(true);
@@ -39,6 +39,15 @@ impl PhysicalDevice {
         .collect()
     }
 
+    /// What images and buffers of `format` can be used for on this device.
+    pub fn format_properties(&self, format: vk::Format) -> vk::FormatProperties {
+        unsafe {
+            self.instance
+                .raw()
+                .get_physical_device_format_properties(self.raw, format)
+        }
+    }
+
     pub unsafe fn raw(&self) -> vk::PhysicalDevice {
         self.raw
     }
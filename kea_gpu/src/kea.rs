@@ -9,7 +9,7 @@ use std::sync::Arc;
 
 pub struct Kea {
     device: Arc<Device>,
-    presenter: Presenter,
+    presenter: Option<Presenter>,
 }
 
 impl Kea {
@@ -20,7 +20,9 @@ impl Kea {
 
         let vulkan = VulkanInstance::new(&required_features);
         let window_surface = Surface::from_window(vulkan.clone(), &window);
-        let (physical_device, queue_family) = device_supporting_surface(&vulkan, &window_surface);
+        let (physical_device, queue_family) = select_device(&vulkan, |family| {
+            family.supports_graphics() && family.supports_surface(&window_surface)
+        });
         let device = Device::new(
             physical_device.clone(),
             &[(queue_family, 1 as usize)],
@@ -28,7 +30,24 @@ impl Kea {
         );
         let presenter = Presenter::new(&device, window_surface, size);
 
-        Kea { device, presenter }
+        Kea {
+            device,
+            presenter: Some(presenter),
+        }
+    }
+
+    /// Create a Kea instance without a window, surface or swapchain. Used for
+    /// offline rendering on machines with no display.
+    pub fn new_headless(features: Vec<Box<dyn Feature + '_>>) -> Kea {
+        let vulkan = VulkanInstance::new(&features);
+        let (physical_device, queue_family) =
+            select_device(&vulkan, |family| family.supports_graphics());
+        let device = Device::new(physical_device.clone(), &[(queue_family, 1)], &features);
+
+        Kea {
+            device,
+            presenter: None,
+        }
     }
 
     pub fn physical_device(&self) -> &Arc<PhysicalDevice> {
@@ -40,25 +59,34 @@ impl Kea {
     }
 
     pub fn presenter(&self) -> &Presenter {
-        &self.presenter
+        match &self.presenter {
+            Some(presenter) => presenter,
+            None => panic!("Headless Kea instance has no presenter"),
+        }
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.presenter.is_none()
     }
 }
 
-fn device_supporting_surface(
+fn select_device<F>(
     vulkan: &Arc<VulkanInstance>,
-    surface: &Surface,
-) -> (Arc<PhysicalDevice>, QueueFamily) {
+    supports_family: F,
+) -> (Arc<PhysicalDevice>, QueueFamily)
+where
+    F: Fn(&QueueFamily) -> bool,
+{
     let physical_devices = vulkan.physical_devices();
     debug!("All devices: {:?}", physical_devices);
 
     let (physical_device, queue_family) = physical_devices
         .into_iter()
         .filter_map(|physical_device| {
-            let queue_family = physical_device.queue_families().into_iter().find(|family| {
-                family.supports_graphics()
-                    && family.supports_surface(surface)
-                    && family.queue_count() >= 1
-            });
+            let queue_family = physical_device
+                .queue_families()
+                .into_iter()
+                .find(|family| supports_family(family) && family.queue_count() >= 1);
 
             match queue_family {
                 Some(queue_family) => Some((physical_device, queue_family)),
@@ -0,0 +1,154 @@
+use crate::{
+    commands::CommandBufferRecorder,
+    storage::{buffers::Buffer, images::ImageView},
+};
+use ash::vk;
+
+/// An image rendered into between `begin_rendering` and `end_rendering`.
+pub struct RenderingAttachment<'a> {
+    pub image_view: &'a ImageView,
+    /// The layout the image is in while it's rendered into.
+    pub layout: vk::ImageLayout,
+    /// What to clear the image to first, or `None` to draw over its contents.
+    pub clear_value: Option<vk::ClearValue>,
+}
+
+impl RenderingAttachment<'_> {
+    fn info(&self) -> vk::RenderingAttachmentInfo<'static> {
+        let load_op = match self.clear_value {
+            Some(_) => vk::AttachmentLoadOp::CLEAR,
+            None => vk::AttachmentLoadOp::LOAD,
+        };
+
+        vk::RenderingAttachmentInfo::default()
+            .image_view(unsafe { self.image_view.raw() })
+            .image_layout(self.layout)
+            .load_op(load_op)
+            .store_op(vk::AttachmentStoreOp::STORE)
+            .clear_value(self.clear_value.unwrap_or_default())
+    }
+}
+
+impl CommandBufferRecorder<'_> {
+    /// Run the bound compute pipeline over `group_counts` workgroups in each
+    /// dimension.
+    pub fn dispatch(&self, group_counts: (u32, u32, u32)) {
+        unsafe {
+            self.device().raw().cmd_dispatch(
+                self.buffer().raw(),
+                group_counts.0,
+                group_counts.1,
+                group_counts.2,
+            );
+        }
+    }
+
+    /// Start rendering into the attachments, which must all be `size`, with
+    /// the viewport and scissor covering them. Draws up to the matching
+    /// `end_rendering` go to these attachments.
+    pub fn begin_rendering(
+        &self,
+        size: (u32, u32),
+        colour_attachments: &[RenderingAttachment],
+        depth_attachment: Option<&RenderingAttachment>,
+    ) {
+        let colour_attachments: Vec<_> = colour_attachments
+            .iter()
+            .map(|attachment| attachment.info())
+            .collect();
+        let depth_attachment = depth_attachment.map(|attachment| attachment.info());
+
+        let area = vk::Rect2D {
+            offset: vk::Offset2D { x: 0, y: 0 },
+            extent: vk::Extent2D {
+                width: size.0,
+                height: size.1,
+            },
+        };
+        let mut rendering_info = vk::RenderingInfo::default()
+            .render_area(area)
+            .layer_count(1)
+            .color_attachments(&colour_attachments);
+        if let Some(depth_attachment) = depth_attachment.as_ref() {
+            rendering_info = rendering_info.depth_attachment(depth_attachment);
+        }
+
+        let viewport = vk::Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: size.0 as f32,
+            height: size.1 as f32,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        };
+
+        unsafe {
+            let device = self.device().raw();
+            device.cmd_begin_rendering(self.buffer().raw(), &rendering_info);
+            device.cmd_set_viewport(self.buffer().raw(), 0, &[viewport]);
+            device.cmd_set_scissor(self.buffer().raw(), 0, &[area]);
+        }
+    }
+
+    pub fn end_rendering(&self) {
+        unsafe {
+            self.device().raw().cmd_end_rendering(self.buffer().raw());
+        }
+    }
+
+    /// Bind vertex buffers to read vertices from, in the order of the bound
+    /// pipeline's vertex buffer layouts.
+    pub fn bind_vertex_buffers(&self, buffers: &[&Buffer]) {
+        let raw_buffers: Vec<vk::Buffer> = buffers
+            .iter()
+            .map(|buffer| unsafe { buffer.raw() })
+            .collect();
+        let offsets = vec![0; buffers.len()];
+
+        unsafe {
+            self.device().raw().cmd_bind_vertex_buffers(
+                self.buffer().raw(),
+                0,
+                &raw_buffers,
+                &offsets,
+            );
+        }
+    }
+
+    /// Bind a buffer of `u32` indices for `draw_indexed`.
+    pub fn bind_index_buffer(&self, buffer: &Buffer) {
+        unsafe {
+            self.device().raw().cmd_bind_index_buffer(
+                self.buffer().raw(),
+                buffer.raw(),
+                0,
+                vk::IndexType::UINT32,
+            );
+        }
+    }
+
+    /// Draw `vertex_count` vertices from the bound vertex buffers, starting
+    /// at `first_vertex`.
+    pub fn draw(&self, vertex_count: u32, first_vertex: u32) {
+        unsafe {
+            self.device()
+                .raw()
+                .cmd_draw(self.buffer().raw(), vertex_count, 1, first_vertex, 0);
+        }
+    }
+
+    /// Draw `index_count` vertices from the bound vertex buffers, using the
+    /// bound indices starting at `first_index`.
+    pub fn draw_indexed(&self, index_count: u32, first_index: u32) {
+        unsafe {
+            self.device().raw().cmd_draw_indexed(
+                self.buffer().raw(),
+                index_count,
+                1,
+                first_index,
+                0,
+                0,
+            );
+        }
+    }
+}
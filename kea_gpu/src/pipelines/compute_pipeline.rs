@@ -0,0 +1,75 @@
+use super::{Pipeline, PipelineLayout, SlotPipeline};
+use crate::{device::Device, shaders::ShaderModule, slots::SlotLayout};
+use ash::vk;
+use kea_gpu_shaderlib::shaders::Shader;
+use std::{ffi::CString, slice, sync::Arc};
+
+/// A pipeline running a single compute shader, with its resources bound
+/// through slots like a ray tracing pipeline's.
+pub struct ComputePipeline<SlotId> {
+    layout: PipelineLayout,
+    slot_layout: SlotLayout<SlotId>,
+    pipeline: Pipeline,
+}
+
+impl<SlotId> ComputePipeline<SlotId> {
+    /// Create a pipeline running `shader`, an entry point from one of
+    /// `shader_modules`.
+    pub fn new(
+        device: Arc<Device>,
+        shader: Shader,
+        shader_modules: &[(&str, &[u8])],
+        layout: PipelineLayout,
+        slot_layout: SlotLayout<SlotId>,
+    ) -> Self {
+        let Shader(name) = shader;
+        let module = ShaderModule::load_module(&device, shader_modules, name);
+        let entry_point = module.entry_point(name);
+        let entry_point_name = CString::new(entry_point.name()).unwrap();
+
+        let pipeline = unsafe {
+            let stage = vk::PipelineShaderStageCreateInfo::default()
+                .stage(vk::ShaderStageFlags::COMPUTE)
+                .module(entry_point.module().raw())
+                .name(&entry_point_name);
+            let create_info = vk::ComputePipelineCreateInfo::default()
+                .stage(stage)
+                .layout(layout.raw());
+
+            let raw = device
+                .raw()
+                .create_compute_pipelines(
+                    vk::PipelineCache::null(),
+                    slice::from_ref(&create_info),
+                    None,
+                )
+                .map_err(|(_, err)| err)
+                .unwrap()
+                .into_iter()
+                .next()
+                .unwrap();
+
+            Pipeline::new(device.clone(), raw)
+        };
+
+        Self {
+            layout,
+            slot_layout,
+            pipeline,
+        }
+    }
+
+    pub fn pipeline(&self) -> &Pipeline {
+        &self.pipeline
+    }
+}
+
+impl<SlotId> SlotPipeline<SlotId> for ComputePipeline<SlotId> {
+    fn layout(&self) -> &PipelineLayout {
+        &self.layout
+    }
+
+    fn slot_layout(&self) -> &SlotLayout<SlotId> {
+        &self.slot_layout
+    }
+}
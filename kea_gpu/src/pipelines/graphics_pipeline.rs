@@ -0,0 +1,196 @@
+use super::{Pipeline, PipelineLayout, SlotPipeline};
+use crate::{device::Device, shaders::ShaderModule, slots::SlotLayout};
+use ash::vk;
+use kea_gpu_shaderlib::shaders::Shader;
+use std::{ffi::CString, slice, sync::Arc};
+
+/// One input of the vertex shader, read from `offset` bytes into each vertex
+/// of a vertex buffer.
+#[derive(Clone, Copy, Debug)]
+pub struct VertexAttribute {
+    pub location: u32,
+    pub format: vk::Format,
+    pub offset: u32,
+}
+
+/// How vertices are laid out in a vertex buffer. Each layout describes the
+/// buffer bound at its position in `bind_vertex_buffers`.
+#[derive(Clone, Debug)]
+pub struct VertexBufferLayout {
+    pub stride: u32,
+    pub attributes: Vec<VertexAttribute>,
+}
+
+/// Everything about a graphics pipeline besides its resources: its shaders,
+/// the vertices it draws, and the images it renders into with
+/// `begin_rendering`.
+#[derive(Clone)]
+pub struct GraphicsPipelineDescription {
+    pub vertex_shader: Shader,
+    pub fragment_shader: Shader,
+    pub vertex_buffers: Vec<VertexBufferLayout>,
+    pub topology: vk::PrimitiveTopology,
+    pub colour_formats: Vec<vk::Format>,
+    /// The format of the depth attachment, if there is one. Depth testing
+    /// keeps the nearest fragments.
+    pub depth_format: Option<vk::Format>,
+}
+
+/// A pipeline rasterizing primitives with a vertex and fragment shader, using
+/// dynamic rendering rather than render passes. The viewport and scissor are
+/// set by `begin_rendering`, and faces aren't culled.
+pub struct GraphicsPipeline<SlotId> {
+    layout: PipelineLayout,
+    slot_layout: SlotLayout<SlotId>,
+    pipeline: Pipeline,
+}
+
+impl<SlotId> GraphicsPipeline<SlotId> {
+    pub fn new(
+        device: Arc<Device>,
+        description: &GraphicsPipelineDescription,
+        shader_modules: &[(&str, &[u8])],
+        layout: PipelineLayout,
+        slot_layout: SlotLayout<SlotId>,
+    ) -> Self {
+        let shaders = [
+            (&description.vertex_shader, vk::ShaderStageFlags::VERTEX),
+            (&description.fragment_shader, vk::ShaderStageFlags::FRAGMENT),
+        ];
+        let modules: Vec<_> = shaders
+            .iter()
+            .map(|(Shader(name), stage)| {
+                let module = ShaderModule::load_module(&device, shader_modules, name);
+                (module.entry_point(name), *stage)
+            })
+            .collect();
+        let names: Vec<_> = modules
+            .iter()
+            .map(|(entry_point, _)| CString::new(entry_point.name()).unwrap())
+            .collect();
+
+        let vertex_bindings: Vec<_> = description
+            .vertex_buffers
+            .iter()
+            .enumerate()
+            .map(|(binding, buffer)| {
+                vk::VertexInputBindingDescription::default()
+                    .binding(binding as _)
+                    .stride(buffer.stride)
+                    .input_rate(vk::VertexInputRate::VERTEX)
+            })
+            .collect();
+        let vertex_attributes: Vec<_> = description
+            .vertex_buffers
+            .iter()
+            .enumerate()
+            .flat_map(|(binding, buffer)| {
+                buffer.attributes.iter().map(move |attribute| {
+                    vk::VertexInputAttributeDescription::default()
+                        .location(attribute.location)
+                        .binding(binding as _)
+                        .format(attribute.format)
+                        .offset(attribute.offset)
+                })
+            })
+            .collect();
+
+        let colour_blend_attachments: Vec<_> = description
+            .colour_formats
+            .iter()
+            .map(|_| {
+                vk::PipelineColorBlendAttachmentState::default()
+                    .color_write_mask(vk::ColorComponentFlags::RGBA)
+            })
+            .collect();
+        let depth_enabled = description.depth_format.is_some();
+        let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+
+        let pipeline = unsafe {
+            let stages: Vec<_> = modules
+                .iter()
+                .zip(&names)
+                .map(|((entry_point, stage), name)| {
+                    vk::PipelineShaderStageCreateInfo::default()
+                        .stage(*stage)
+                        .module(entry_point.module().raw())
+                        .name(name)
+                })
+                .collect();
+
+            let vertex_input = vk::PipelineVertexInputStateCreateInfo::default()
+                .vertex_binding_descriptions(&vertex_bindings)
+                .vertex_attribute_descriptions(&vertex_attributes);
+            let input_assembly =
+                vk::PipelineInputAssemblyStateCreateInfo::default().topology(description.topology);
+            let viewport = vk::PipelineViewportStateCreateInfo::default()
+                .viewport_count(1)
+                .scissor_count(1);
+            let rasterization = vk::PipelineRasterizationStateCreateInfo::default()
+                .polygon_mode(vk::PolygonMode::FILL)
+                .cull_mode(vk::CullModeFlags::NONE)
+                .line_width(1.0);
+            let multisample = vk::PipelineMultisampleStateCreateInfo::default()
+                .rasterization_samples(vk::SampleCountFlags::TYPE_1);
+            let depth_stencil = vk::PipelineDepthStencilStateCreateInfo::default()
+                .depth_test_enable(depth_enabled)
+                .depth_write_enable(depth_enabled)
+                .depth_compare_op(vk::CompareOp::LESS);
+            let colour_blend = vk::PipelineColorBlendStateCreateInfo::default()
+                .attachments(&colour_blend_attachments);
+            let dynamic_state =
+                vk::PipelineDynamicStateCreateInfo::default().dynamic_states(&dynamic_states);
+            let mut rendering = vk::PipelineRenderingCreateInfo::default()
+                .color_attachment_formats(&description.colour_formats)
+                .depth_attachment_format(description.depth_format.unwrap_or_default());
+
+            let create_info = vk::GraphicsPipelineCreateInfo::default()
+                .stages(&stages)
+                .vertex_input_state(&vertex_input)
+                .input_assembly_state(&input_assembly)
+                .viewport_state(&viewport)
+                .rasterization_state(&rasterization)
+                .multisample_state(&multisample)
+                .depth_stencil_state(&depth_stencil)
+                .color_blend_state(&colour_blend)
+                .dynamic_state(&dynamic_state)
+                .layout(layout.raw())
+                .push_next(&mut rendering);
+
+            let raw = device
+                .raw()
+                .create_graphics_pipelines(
+                    vk::PipelineCache::null(),
+                    slice::from_ref(&create_info),
+                    None,
+                )
+                .map_err(|(_, err)| err)
+                .unwrap()
+                .into_iter()
+                .next()
+                .unwrap();
+
+            Pipeline::new(device.clone(), raw)
+        };
+
+        Self {
+            layout,
+            slot_layout,
+            pipeline,
+        }
+    }
+
+    pub fn pipeline(&self) -> &Pipeline {
+        &self.pipeline
+    }
+}
+
+impl<SlotId> SlotPipeline<SlotId> for GraphicsPipeline<SlotId> {
+    fn layout(&self) -> &PipelineLayout {
+        &self.layout
+    }
+
+    fn slot_layout(&self) -> &SlotLayout<SlotId> {
+        &self.slot_layout
+    }
+}
@@ -1,5 +1,15 @@
+pub mod commands;
+mod compute_pipeline;
+mod graphics_pipeline;
 mod pipeline;
 mod pipeline_layout;
+mod slot_pipeline;
 
+pub use commands::RenderingAttachment;
+pub use compute_pipeline::ComputePipeline;
+pub use graphics_pipeline::{
+    GraphicsPipeline, GraphicsPipelineDescription, VertexAttribute, VertexBufferLayout,
+};
 pub use pipeline::Pipeline;
 pub use pipeline_layout::PipelineLayout;
+pub use slot_pipeline::SlotPipeline;
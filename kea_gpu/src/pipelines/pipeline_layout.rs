@@ -1,28 +1,23 @@
 use crate::{descriptors::DescriptorSetLayout, device::Device};
 use ash::vk;
-use std::{mem, slice, sync::Arc};
+use std::{slice, sync::Arc};
 
 pub struct PipelineLayout {
     device: Arc<Device>,
     raw: vk::PipelineLayout,
     descriptor_set_layout: DescriptorSetLayout,
 }
-#[repr(C)]
-pub struct PushConstants {
-    pub iteration: u64,
-}
 
 impl PipelineLayout {
-    pub fn new(device: Arc<Device>, descriptor_set_layout: DescriptorSetLayout) -> PipelineLayout {
-        let push_constant_range = vk::PushConstantRange::default()
-            .stage_flags(vk::ShaderStageFlags::RAYGEN_KHR)
-            .offset(0)
-            .size(mem::size_of::<PushConstants>() as _);
-
+    pub fn new(
+        device: Arc<Device>,
+        descriptor_set_layout: DescriptorSetLayout,
+        push_constant_ranges: &[vk::PushConstantRange],
+    ) -> PipelineLayout {
         let layout_raw = unsafe { descriptor_set_layout.raw() };
         let create_info = vk::PipelineLayoutCreateInfo::default()
             .set_layouts(slice::from_ref(&layout_raw))
-            .push_constant_ranges(slice::from_ref(&push_constant_range));
+            .push_constant_ranges(push_constant_ranges);
 
         let raw = unsafe { device.raw().create_pipeline_layout(&create_info, None) }.unwrap();
 
@@ -0,0 +1,10 @@
+use super::PipelineLayout;
+use crate::slots::SlotLayout;
+
+/// A pipeline whose resources are bound through the slots of a `SlotLayout`,
+/// so `SlotBindings` can make descriptor sets for it.
+pub trait SlotPipeline<SlotId> {
+    fn layout(&self) -> &PipelineLayout;
+
+    fn slot_layout(&self) -> &SlotLayout<SlotId>;
+}
@@ -6,7 +6,11 @@ use crate::{
     sync::{Semaphore, TimelineSemaphore},
 };
 use ash::vk;
-use std::{cell::Cell, slice, sync::Arc};
+use std::{
+    cell::{Cell, RefCell},
+    slice,
+    sync::Arc,
+};
 
 use super::{swapchain::Swapchain, Surface};
 
@@ -14,15 +18,20 @@ use super::{swapchain::Swapchain, Surface};
 pub const FRAMES_IN_FLIGHT: u64 = 2;
 
 pub struct Presenter {
-    swapchain: Swapchain,
+    swapchain: RefCell<Swapchain>,
     /// One acquire semaphore per in-flight frame slot.
     acquire_semaphores: Vec<Semaphore>,
     /// One present semaphore per swapchain image.
-    present_semaphores: Vec<Semaphore>,
+    present_semaphores: RefCell<Vec<Semaphore>>,
     /// Tracks GPU completion of each submitted frame. Frame N signals value N+1.
     timeline: TimelineSemaphore,
     /// Index of the next frame to record. Incremented after each submit.
     frame_index: Cell<u64>,
+    /// Size the swapchain should have, updated when the window is resized.
+    requested_size: Cell<(u32, u32)>,
+    /// Set when the swapchain no longer matches the surface, and must be
+    /// recreated before the next image is acquired.
+    out_of_date: Cell<bool>,
 }
 
 impl Presenter {
@@ -36,29 +45,33 @@ impl Presenter {
         let acquire_semaphores = (0..FRAMES_IN_FLIGHT)
             .map(|i| Semaphore::new_named(device.clone(), &format!("acquire {}", i)))
             .collect();
-        let present_semaphores = (0..swapchain.image_count())
-            .map(|i| Semaphore::new_named(device.clone(), &format!("present {}", i)))
-            .collect();
+        let present_semaphores = Self::create_present_semaphores(device, &swapchain);
         let timeline = TimelineSemaphore::new_named(device.clone(), 0, "frame timeline");
 
         Presenter {
-            swapchain,
+            swapchain: RefCell::new(swapchain),
             acquire_semaphores,
-            present_semaphores,
+            present_semaphores: RefCell::new(present_semaphores),
             timeline,
             frame_index: Cell::new(0),
+            requested_size: Cell::new(size),
+            out_of_date: Cell::new(false),
         }
     }
 
+    fn create_present_semaphores(device: &Arc<Device>, swapchain: &Swapchain) -> Vec<Semaphore> {
+        (0..swapchain.image_count())
+            .map(|i| Semaphore::new_named(device.clone(), &format!("present {}", i)))
+            .collect()
+    }
+
     pub fn format(&self) -> vk::Format {
-        self.swapchain.format()
+        self.swapchain.borrow().format()
     }
 
     pub fn size(&self) -> (u32, u32) {
-        (
-            self.swapchain.extent().width,
-            self.swapchain.extent().height,
-        )
+        let extent = self.swapchain.borrow().extent();
+        (extent.width, extent.height)
     }
 
     /// Index of the frame currently being recorded. Stable across
@@ -67,7 +80,23 @@ impl Presenter {
         self.frame_index.get()
     }
 
-    pub fn get_swapchain_image(&self) -> (u32, &ImageView) {
+    /// Request a new swapchain size, e.g. when the window is resized. The
+    /// swapchain is recreated when the next image is acquired.
+    pub fn resize(&self, size: (u32, u32)) {
+        self.requested_size.set(size);
+        self.out_of_date.set(true);
+    }
+
+    /// Acquire the next swapchain image to draw to. If the swapchain has to be
+    /// recreated first, `on_recreate` is called with its new size once the
+    /// device is idle, so that size-dependent resources can be replaced.
+    ///
+    /// Returns `None` if there's nothing to draw to, e.g. while the window is
+    /// minimised.
+    pub fn get_swapchain_image<F>(&self, mut on_recreate: F) -> Option<(u32, Arc<ImageView>)>
+    where
+        F: FnMut((u32, u32)),
+    {
         let frame = self.frame_index.get();
         // Wait for frame (frame - FRAMES_IN_FLIGHT) to finish on the GPU before
         // reusing its acquire semaphore slot. Frame N signals timeline = N + 1,
@@ -77,13 +106,56 @@ impl Presenter {
         }
 
         let acquire = &self.acquire_semaphores[(frame % FRAMES_IN_FLIGHT) as usize];
-        self.swapchain.acquire_next_image(acquire)
+        loop {
+            if self.out_of_date.get() {
+                let (width, height) = self.requested_size.get();
+                if width == 0 || height == 0 {
+                    return None;
+                }
+
+                self.recreate_swapchain();
+                on_recreate(self.size());
+            }
+
+            let acquired = self.swapchain.borrow().acquire_next_image(acquire);
+            match acquired {
+                Some((index, suboptimal)) => {
+                    // The acquire semaphore will still be signalled, so draw
+                    // this frame and recreate before the next one.
+                    if suboptimal {
+                        self.out_of_date.set(true);
+                    }
+
+                    return Some((index, self.swapchain.borrow().image(index).clone()));
+                }
+                None => self.out_of_date.set(true),
+            }
+        }
+    }
+
+    fn recreate_swapchain(&self) {
+        let device = self.swapchain.borrow().device().clone();
+        device.wait_until_idle();
+
+        let (width, height) = self.requested_size.get();
+        let mut swapchain = self.swapchain.borrow_mut();
+        swapchain.recreate(vk::Extent2D { width, height });
+        log::info!(
+            "Recreated swapchain at {}x{}",
+            swapchain.extent().width,
+            swapchain.extent().height
+        );
+
+        *self.present_semaphores.borrow_mut() =
+            Self::create_present_semaphores(&device, &swapchain);
+        self.out_of_date.set(false);
     }
 
     pub fn draw(&self, swapchain_index: u32, commands: &[RecordedCommandBuffer]) {
         let frame = self.frame_index.get();
         let acquire = &self.acquire_semaphores[(frame % FRAMES_IN_FLIGHT) as usize];
-        let present = &self.present_semaphores[swapchain_index as usize];
+        let present_semaphores = self.present_semaphores.borrow();
+        let present = &present_semaphores[swapchain_index as usize];
 
         let wait = Wait {
             semaphore: acquire,
@@ -110,16 +182,20 @@ impl Presenter {
             signal: &signals,
         };
 
-        self.swapchain
+        let swapchain = self.swapchain.borrow();
+        swapchain
             .device()
             .graphics_queue()
             .submit(&submission, None);
 
-        self.swapchain.present(
-            &self.swapchain.device().graphics_queue(),
+        let out_of_date = swapchain.present(
+            &swapchain.device().graphics_queue(),
             slice::from_ref(present),
             swapchain_index,
         );
+        if out_of_date {
+            self.out_of_date.set(true);
+        }
 
         self.frame_index.set(frame + 1);
     }
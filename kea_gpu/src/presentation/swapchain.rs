@@ -11,19 +11,62 @@ use std::{slice, sync::Arc};
 
 pub struct Swapchain {
     device: Arc<Device>,
-    _surface: Surface,
+    surface: Surface,
     raw: vk::SwapchainKHR,
     extent: vk::Extent2D,
     format: vk::Format,
-    images: Vec<ImageView>,
+    images: Vec<Arc<ImageView>>,
 }
 
 impl Swapchain {
     pub fn new(device: &Arc<Device>, surface: Surface, extent: vk::Extent2D) -> Swapchain {
+        let (raw, format, extent, images) =
+            Self::create(device, &surface, extent, vk::SwapchainKHR::null());
+
+        Swapchain {
+            raw,
+            surface,
+            format,
+            images,
+            extent,
+            device: device.clone(),
+        }
+    }
+
+    /// Replace the swapchain with one of the given size, e.g. after the window
+    /// is resized. The caller must ensure none of the old images are in use.
+    pub fn recreate(&mut self, extent: vk::Extent2D) {
+        let (raw, format, extent, images) =
+            Self::create(&self.device, &self.surface, extent, self.raw);
+
+        unsafe {
+            self.device
+                .ext()
+                .swapchain()
+                .destroy_swapchain(self.raw, None);
+        }
+
+        self.raw = raw;
+        self.format = format;
+        self.extent = extent;
+        self.images = images;
+    }
+
+    fn create(
+        device: &Arc<Device>,
+        surface: &Surface,
+        extent: vk::Extent2D,
+        old_swapchain: vk::SwapchainKHR,
+    ) -> (
+        vk::SwapchainKHR,
+        vk::Format,
+        vk::Extent2D,
+        Vec<Arc<ImageView>>,
+    ) {
         let surface_capabilities = device
             .instance()
             .ext::<SurfaceExt>()
-            .surface_capabilities(device.physical_device(), &surface);
+            .surface_capabilities(device.physical_device(), surface);
 
         let image_count = surface_capabilities.min_image_count + 1;
         let image_count = if surface_capabilities.max_image_count > 0 {
@@ -32,10 +75,27 @@ impl Swapchain {
             image_count
         };
 
+        // A current extent of u32::MAX means the surface size is determined by
+        // the swapchain, otherwise it must match the surface.
+        let extent = if surface_capabilities.current_extent.width != u32::MAX {
+            surface_capabilities.current_extent
+        } else {
+            vk::Extent2D {
+                width: extent.width.clamp(
+                    surface_capabilities.min_image_extent.width,
+                    surface_capabilities.max_image_extent.width,
+                ),
+                height: extent.height.clamp(
+                    surface_capabilities.min_image_extent.height,
+                    surface_capabilities.max_image_extent.height,
+                ),
+            }
+        };
+
         let available_formats = device
             .instance()
             .ext::<SurfaceExt>()
-            .surface_formats(device.physical_device(), &surface);
+            .surface_formats(device.physical_device(), surface);
         let surface_format = available_formats
             .iter()
             .find(|format| {
@@ -47,7 +107,7 @@ impl Swapchain {
         let present_mode = device
             .instance()
             .ext::<SurfaceExt>()
-            .surface_present_modes(device.physical_device(), &surface)
+            .surface_present_modes(device.physical_device(), surface)
             .iter()
             .cloned()
             .find(|&mode| mode == vk::PresentModeKHR::MAILBOX)
@@ -63,7 +123,8 @@ impl Swapchain {
             .pre_transform(surface_capabilities.current_transform)
             .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
             .image_array_layers(1)
-            .present_mode(present_mode);
+            .present_mode(present_mode)
+            .old_swapchain(old_swapchain);
 
         let raw = unsafe {
             device
@@ -80,14 +141,7 @@ impl Swapchain {
             surface_format.format,
         );
 
-        Swapchain {
-            raw,
-            _surface: surface,
-            format: surface_format.format,
-            images,
-            extent,
-            device: device.clone(),
-        }
+        (raw, surface_format.format, extent, images)
     }
 
     fn create_images(
@@ -95,7 +149,7 @@ impl Swapchain {
         swapchain: vk::SwapchainKHR,
         size: (u32, u32),
         format: vk::Format,
-    ) -> Vec<ImageView> {
+    ) -> Vec<Arc<ImageView>> {
         unsafe { device.ext().swapchain().get_swapchain_images(swapchain) }
             .unwrap()
             .into_iter()
@@ -109,26 +163,34 @@ impl Swapchain {
                     MemoryLocation::GpuOnly,
                     ImageOwnership::ExternallyOwned,
                 );
-                ImageView::new(Arc::new(image))
+                Arc::new(ImageView::new(Arc::new(image)))
             })
             .collect()
     }
 
-    pub fn acquire_next_image(&self, semaphore: &Semaphore) -> (u32, &ImageView) {
-        let (image_index, _) = unsafe {
+    /// Acquire the next image to render to, returning its index and whether
+    /// the swapchain is suboptimal for the surface. Returns `None` if the
+    /// swapchain is out of date and must be recreated before use.
+    pub fn acquire_next_image(&self, semaphore: &Semaphore) -> Option<(u32, bool)> {
+        let result = unsafe {
             self.device.ext().swapchain().acquire_next_image(
                 self.raw,
                 u64::MAX,
                 semaphore.raw(),
                 vk::Fence::null(),
             )
-        }
-        .unwrap();
+        };
 
-        (image_index, &self.images[image_index as usize])
+        match result {
+            Ok(acquired) => Some(acquired),
+            Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => None,
+            Err(err) => panic!("Failed to acquire swapchain image: {}", err),
+        }
     }
 
-    pub fn present(&self, queue: &Queue, wait_semaphores: &[Semaphore], image_index: u32) {
+    /// Queue an image for presentation. Returns true if the swapchain is out
+    /// of date or suboptimal and should be recreated.
+    pub fn present(&self, queue: &Queue, wait_semaphores: &[Semaphore], image_index: u32) -> bool {
         let raw_semaphores: Vec<vk::Semaphore> =
             wait_semaphores.iter().map(|s| unsafe { s.raw() }).collect();
         let present = vk::PresentInfoKHR::default()
@@ -136,15 +198,24 @@ impl Swapchain {
             .swapchains(slice::from_ref(&self.raw))
             .image_indices(slice::from_ref(&image_index));
 
-        unsafe {
+        let result = unsafe {
             self.device()
                 .ext()
                 .swapchain()
                 .queue_present(queue.raw(), &present)
-                .unwrap();
+        };
+
+        match result {
+            Ok(suboptimal) => suboptimal,
+            Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => true,
+            Err(err) => panic!("Failed to present swapchain image: {}", err),
         }
     }
 
+    pub fn image(&self, index: u32) -> &Arc<ImageView> {
+        &self.images[index as usize]
+    }
+
     pub fn format(&self) -> vk::Format {
         self.format
     }
@@ -43,4 +43,20 @@ impl CommandBufferRecorder<'_> {
                 );
         }
     }
+
+    /// Record several acceleration structure builds at once. They may run
+    /// in parallel, so none may depend on another, and each needs its own
+    /// range of scratch memory.
+    pub fn build_acceleration_structures(
+        &self,
+        geometry_infos: &[vk::AccelerationStructureBuildGeometryInfoKHR],
+        ranges: &[&[vk::AccelerationStructureBuildRangeInfoKHR]],
+    ) {
+        unsafe {
+            self.device()
+                .ext()
+                .acceleration_structure()
+                .cmd_build_acceleration_structures(self.buffer().raw(), geometry_infos, ranges);
+        }
+    }
 }
@@ -1,7 +1,7 @@
 use super::RayTracingShaderBindingTables;
 use crate::{
     device::Device,
-    pipelines::{Pipeline, PipelineLayout},
+    pipelines::{Pipeline, PipelineLayout, SlotPipeline},
     shaders::{PipelineShaders, ShaderGroups},
     slots::SlotLayout,
 };
@@ -116,16 +116,18 @@ impl<SlotId> RayTracingPipeline<SlotId> {
         &self.pipeline
     }
 
-    pub fn layout(&self) -> &PipelineLayout {
-        &self.layout
+    pub fn shader_binding_tables(&self) -> &RayTracingShaderBindingTables {
+        &self.shader_binding_tables
     }
+}
 
-    pub fn slot_layout(&self) -> &SlotLayout<SlotId> {
-        &self.slot_layout
+impl<SlotId> SlotPipeline<SlotId> for RayTracingPipeline<SlotId> {
+    fn layout(&self) -> &PipelineLayout {
+        &self.layout
     }
 
-    pub fn shader_binding_tables(&self) -> &RayTracingShaderBindingTables {
-        &self.shader_binding_tables
+    fn slot_layout(&self) -> &SlotLayout<SlotId> {
+        &self.slot_layout
     }
 }
 
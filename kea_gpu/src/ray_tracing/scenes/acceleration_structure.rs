@@ -78,12 +78,14 @@ impl AccelerationStructure {
         let vk::AccelerationStructureBuildSizesInfoKHR {
             acceleration_structure_size,
             build_scratch_size,
+            update_scratch_size,
             ..
         } = size_info;
 
         BuildSizes {
             acceleration_structure: acceleration_structure_size,
             build_scratch: build_scratch_size,
+            update_scratch: update_scratch_size,
         }
     }
 }
@@ -104,4 +106,6 @@ impl Drop for AccelerationStructure {
 pub struct BuildSizes {
     pub acceleration_structure: vk::DeviceSize,
     pub build_scratch: vk::DeviceSize,
+    /// Only meaningful for acceleration structures built with `ALLOW_UPDATE`.
+    pub update_scratch: vk::DeviceSize,
 }
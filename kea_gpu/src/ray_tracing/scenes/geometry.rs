@@ -1,5 +1,9 @@
 use super::{acceleration_structure::AccelerationStructure, scratch_buffer::ScratchBuffer};
-use crate::{commands::CommandBuffer, device::Device, storage::buffers::Buffer};
+use crate::{
+    commands::{CommandBuffer, CommandBufferRecorder},
+    device::Device,
+    storage::buffers::Buffer,
+};
 use ash::vk;
 use gpu_allocator::MemoryLocation;
 use kea_gpu_shaderlib::Aabb;
@@ -16,6 +20,11 @@ pub struct Geometry {
     name: String,
     geometry_type: GeometryType,
     acceleration_structure: Option<Arc<AccelerationStructure>>,
+    /// Whether the acceleration structure can be refitted with `update`.
+    updatable: bool,
+    /// Kept for updates, so only updatable geometries have one. Builds use a
+    /// scratch buffer of their own.
+    scratch_buffer: Option<ScratchBuffer>,
 }
 
 impl Geometry {
@@ -25,6 +34,20 @@ impl Geometry {
             name,
             geometry_type,
             acceleration_structure: None,
+            updatable: false,
+            scratch_buffer: None,
+        }
+    }
+
+    /// A geometry whose vertices or AABBs can move after it's built, with
+    /// `update` or `update_aabbs`. Its vertex or AABB buffer must have been
+    /// created with `TRANSFER_DST` usage. Updatable acceleration structures
+    /// are a little slower to trace, so only geometry that deforms should be
+    /// built this way.
+    pub fn new_updatable(device: Arc<Device>, name: String, geometry_type: GeometryType) -> Self {
+        Self {
+            updatable: true,
+            ..Self::new(device, name, geometry_type)
         }
     }
 
@@ -40,12 +63,223 @@ impl Geometry {
         self.acceleration_structure.as_ref().unwrap()
     }
 
+    /// Build the acceleration structure straight away. `SceneBuilder` builds
+    /// many geometries together, which is much quicker than building them
+    /// one at a time.
     pub fn build(&mut self) {
+        let build_scratch_size = self.allocate();
+        let scratch_buffer = ScratchBuffer::new(self.device().clone(), build_scratch_size);
+
+        CommandBuffer::now(self.device(), "build BLAS".to_string(), |cmd| {
+            let geometry = self.geometry();
+            let range = self.range();
+            let geometry_info = self.build_info(
+                &geometry,
+                vk::BuildAccelerationStructureModeKHR::BUILD,
+                scratch_buffer.device_address(),
+            );
+            cmd.build_acceleration_structure(&geometry_info, &range);
+        });
+    }
+
+    /// Create the acceleration structure, and the scratch buffer it's
+    /// updated with if it's updatable, ready to be built. Returns the size of
+    /// scratch buffer the build needs.
+    pub(super) fn allocate(&mut self) -> vk::DeviceSize {
         if self.acceleration_structure.is_some() {
             log::warn!("Geometry {} has multiple build calls.", self.name);
         }
 
-        self.acceleration_structure = match &self.geometry_type {
+        let geometry = self.geometry();
+        let range = self.range();
+        let geometry_info = vk::AccelerationStructureBuildGeometryInfoKHR::default()
+            .ty(vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL)
+            .flags(self.build_flags())
+            .geometries(slice::from_ref(&geometry));
+
+        let build_sizes = AccelerationStructure::build_sizes(self.device(), &geometry_info, &range);
+
+        let acceleration_structure_buffer = Buffer::new(
+            self.device().clone(),
+            build_sizes.acceleration_structure,
+            vk::BufferUsageFlags::ACCELERATION_STRUCTURE_STORAGE_KHR,
+            format!("{} acceleration structure", self.name),
+            MemoryLocation::GpuOnly,
+            None,
+        );
+
+        let acceleration_structure = AccelerationStructure::new(
+            self.device(),
+            acceleration_structure_buffer,
+            vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL,
+        );
+
+        self.scratch_buffer = (self.updatable && build_sizes.update_scratch > 0)
+            .then(|| ScratchBuffer::new(self.device().clone(), build_sizes.update_scratch));
+        self.acceleration_structure = Some(Arc::new(acceleration_structure));
+
+        build_sizes.build_scratch
+    }
+
+    /// Record moving an updatable triangle geometry's vertices to `vertices`
+    /// and refitting its acceleration structure to them in place. The
+    /// triangles stay the same, so there must be as many vertices as before.
+    ///
+    /// Any scene containing the geometry needs its own acceleration
+    /// structure updating afterwards. As with `Buffer::record_write_range`,
+    /// the returned staging buffer must be kept alive until the command
+    /// buffer has completed.
+    #[must_use]
+    pub fn update(&self, cmd: &CommandBufferRecorder, vertices: &[Vec3A]) -> Buffer {
+        let GeometryType::Triangles {
+            vertices: vertex_buffer,
+            ..
+        } = &self.geometry_type
+        else {
+            panic!("Geometry {} doesn't have vertices to update", self.name);
+        };
+        assert_eq!(
+            vertices.len(),
+            vertex_buffer.count::<Vec3A>(),
+            "Geometry {} can't change its vertex count when updated",
+            self.name
+        );
+
+        self.record_update(cmd, vertex_buffer, vertices)
+    }
+
+    /// Record moving an AABB geometry's boxes to `aabbs` and refitting its
+    /// acceleration structure to them, as `update` does for triangles.
+    #[must_use]
+    pub fn update_aabbs(&self, cmd: &CommandBufferRecorder, aabbs: &[Aabb]) -> Buffer {
+        let GeometryType::Aabbs(aabbs_buffer) = &self.geometry_type else {
+            panic!("Geometry {} doesn't have AABBs to update", self.name);
+        };
+        assert_eq!(
+            aabbs.len(),
+            aabbs_buffer.count::<Aabb>(),
+            "Geometry {} can't change its AABB count when updated",
+            self.name
+        );
+
+        self.record_update(cmd, aabbs_buffer, aabbs)
+    }
+
+    fn record_update<T: Copy>(
+        &self,
+        cmd: &CommandBufferRecorder,
+        input_buffer: &Buffer,
+        data: &[T],
+    ) -> Buffer {
+        assert!(
+            self.updatable,
+            "Geometry {} wasn't created updatable",
+            self.name
+        );
+
+        // Earlier frames may still be tracing rays through the acceleration
+        // structure, or building from the input buffer or with the scratch
+        // buffer.
+        let before = vk::MemoryBarrier2::default()
+            .src_stage_mask(
+                vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR
+                    | vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR,
+            )
+            .src_access_mask(
+                vk::AccessFlags2::ACCELERATION_STRUCTURE_READ_KHR
+                    | vk::AccessFlags2::ACCELERATION_STRUCTURE_WRITE_KHR
+                    | vk::AccessFlags2::SHADER_READ,
+            )
+            .dst_stage_mask(
+                vk::PipelineStageFlags2::TRANSFER
+                    | vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR,
+            )
+            .dst_access_mask(
+                vk::AccessFlags2::TRANSFER_WRITE
+                    | vk::AccessFlags2::ACCELERATION_STRUCTURE_READ_KHR
+                    | vk::AccessFlags2::ACCELERATION_STRUCTURE_WRITE_KHR,
+            );
+        cmd.pipeline_barrier(
+            vk::DependencyFlags::empty(),
+            slice::from_ref(&before),
+            &[],
+            &[],
+        );
+
+        let staging = input_buffer.record_write_range(cmd, 0, data);
+        let geometry = self.geometry();
+        let range = self.range();
+        let geometry_info = self.build_info(
+            &geometry,
+            vk::BuildAccelerationStructureModeKHR::UPDATE,
+            self.scratch_buffer
+                .as_ref()
+                .map_or(0, ScratchBuffer::device_address),
+        );
+        cmd.build_acceleration_structure(&geometry_info, &range);
+
+        // Make the refitted geometry visible to scene builds.
+        let after = vk::MemoryBarrier2::default()
+            .src_stage_mask(vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR)
+            .src_access_mask(vk::AccessFlags2::ACCELERATION_STRUCTURE_WRITE_KHR)
+            .dst_stage_mask(
+                vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR
+                    | vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR,
+            )
+            .dst_access_mask(vk::AccessFlags2::ACCELERATION_STRUCTURE_READ_KHR);
+        cmd.pipeline_barrier(
+            vk::DependencyFlags::empty(),
+            slice::from_ref(&after),
+            &[],
+            &[],
+        );
+
+        staging
+    }
+
+    /// How to build `geometry`, this geometry's description, either from
+    /// scratch or updating it in place.
+    pub(super) fn build_info<'a>(
+        &self,
+        geometry: &'a vk::AccelerationStructureGeometryKHR<'static>,
+        mode: vk::BuildAccelerationStructureModeKHR,
+        scratch_address: vk::DeviceAddress,
+    ) -> vk::AccelerationStructureBuildGeometryInfoKHR<'a> {
+        let acceleration_structure = unsafe { self.acceleration_structure().raw() };
+
+        let geometry_info = vk::AccelerationStructureBuildGeometryInfoKHR::default()
+            .ty(vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL)
+            .flags(self.build_flags())
+            .geometries(slice::from_ref(geometry))
+            .mode(mode)
+            .dst_acceleration_structure(acceleration_structure)
+            .scratch_data(vk::DeviceOrHostAddressKHR {
+                device_address: scratch_address,
+            });
+        if mode == vk::BuildAccelerationStructureModeKHR::UPDATE {
+            geometry_info.src_acceleration_structure(acceleration_structure)
+        } else {
+            geometry_info
+        }
+    }
+
+    fn build_flags(&self) -> vk::BuildAccelerationStructureFlagsKHR {
+        let mut flags = vk::BuildAccelerationStructureFlagsKHR::PREFER_FAST_TRACE;
+        if self.updatable {
+            flags |= vk::BuildAccelerationStructureFlagsKHR::ALLOW_UPDATE;
+        }
+
+        match self.geometry_type {
+            // Hit shaders fetch triangles' vertex positions.
+            GeometryType::Triangles { .. } => {
+                flags | vk::BuildAccelerationStructureFlagsKHR::ALLOW_DATA_ACCESS
+            }
+            GeometryType::Aabbs(_) => flags,
+        }
+    }
+
+    pub(super) fn geometry(&self) -> vk::AccelerationStructureGeometryKHR<'static> {
+        match &self.geometry_type {
             GeometryType::Aabbs(aabbs_buffer) => {
                 let aabbs = vk::AccelerationStructureGeometryAabbsDataKHR::default()
                     .data(vk::DeviceOrHostAddressConstKHR {
@@ -53,53 +287,10 @@ impl Geometry {
                     })
                     .stride(mem::size_of::<Aabb>() as u64);
 
-                let geometry = vk::AccelerationStructureGeometryKHR::default()
+                vk::AccelerationStructureGeometryKHR::default()
                     .geometry_type(vk::GeometryTypeKHR::AABBS)
                     .geometry(vk::AccelerationStructureGeometryDataKHR { aabbs })
-                    .flags(vk::GeometryFlagsKHR::OPAQUE);
-
-                let range = vk::AccelerationStructureBuildRangeInfoKHR::default()
-                    .primitive_count((aabbs_buffer.size() / mem::size_of::<Aabb>()) as u32);
-
-                let geometry_info = vk::AccelerationStructureBuildGeometryInfoKHR::default()
-                    .ty(vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL)
-                    .geometries(slice::from_ref(&geometry));
-
-                let build_sizes =
-                    AccelerationStructure::build_sizes(self.device(), &geometry_info, &range);
-                let scratch_buffer =
-                    ScratchBuffer::new(self.device().clone(), build_sizes.build_scratch);
-
-                let acceleration_structure_buffer = Buffer::new(
-                    self.device().clone(),
-                    build_sizes.acceleration_structure,
-                    vk::BufferUsageFlags::ACCELERATION_STRUCTURE_STORAGE_KHR,
-                    format!("{} acceleration structure", self.name),
-                    MemoryLocation::GpuOnly,
-                    None,
-                );
-
-                let acceleration_structure = AccelerationStructure::new(
-                    self.device(),
-                    acceleration_structure_buffer,
-                    vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL,
-                );
-
-                let geometry_info = vk::AccelerationStructureBuildGeometryInfoKHR::default()
-                    .ty(vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL)
-                    .geometries(slice::from_ref(&geometry))
-                    .mode(vk::BuildAccelerationStructureModeKHR::BUILD)
-                    .flags(vk::BuildAccelerationStructureFlagsKHR::PREFER_FAST_TRACE)
-                    .dst_acceleration_structure(unsafe { acceleration_structure.raw() })
-                    .scratch_data(vk::DeviceOrHostAddressKHR {
-                        device_address: scratch_buffer.device_address(),
-                    });
-
-                CommandBuffer::now(self.device(), "build BLAS".to_string(), |cmd| {
-                    cmd.build_acceleration_structure(&geometry_info, &range);
-                });
-
-                Some(Arc::new(acceleration_structure))
+                    .flags(vk::GeometryFlagsKHR::OPAQUE)
             }
             GeometryType::Triangles { vertices, indices } => {
                 let triangles = vk::AccelerationStructureGeometryTrianglesDataKHR::default()
@@ -114,58 +305,104 @@ impl Geometry {
                     })
                     .max_vertex(indices.count::<u32>() as _);
 
-                let geometry = vk::AccelerationStructureGeometryKHR::default()
+                vk::AccelerationStructureGeometryKHR::default()
                     .geometry_type(vk::GeometryTypeKHR::TRIANGLES)
                     .geometry(vk::AccelerationStructureGeometryDataKHR {
                         triangles,
                     })
-                    .flags(vk::GeometryFlagsKHR::OPAQUE);
-
-                let range = vk::AccelerationStructureBuildRangeInfoKHR::default()
-                    .primitive_count((indices.count::<u32>() / 3) as u32);
-
-                let geometry_info = vk::AccelerationStructureBuildGeometryInfoKHR::default()
-                    .ty(vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL)
-                    .flags(
-                        vk::BuildAccelerationStructureFlagsKHR::PREFER_FAST_TRACE
-                            | vk::BuildAccelerationStructureFlagsKHR::ALLOW_DATA_ACCESS,
-                    )
-                    .geometries(slice::from_ref(&geometry));
-
-                let build_sizes =
-                    AccelerationStructure::build_sizes(self.device(), &geometry_info, &range);
-                let scratch_buffer =
-                    ScratchBuffer::new(self.device().clone(), build_sizes.build_scratch);
-
-                let acceleration_structure_buffer = Buffer::new(
-                    self.device().clone(),
-                    build_sizes.acceleration_structure,
-                    vk::BufferUsageFlags::ACCELERATION_STRUCTURE_STORAGE_KHR,
-                    format!("{} acceleration structure", self.name),
-                    MemoryLocation::GpuOnly,
-                    None,
-                );
-                // self.buffer = Some(acceleration_structure_buffer);
-
-                let acceleration_structure = AccelerationStructure::new(
-                    self.device(),
-                    acceleration_structure_buffer,
-                    vk::AccelerationStructureTypeKHR::BOTTOM_LEVEL,
-                );
-
-                let geometry_info = geometry_info
-                    .mode(vk::BuildAccelerationStructureModeKHR::BUILD)
-                    .dst_acceleration_structure(unsafe { acceleration_structure.raw() })
-                    .scratch_data(vk::DeviceOrHostAddressKHR {
-                        device_address: scratch_buffer.device_address(),
-                    });
-
-                CommandBuffer::now(self.device(), "build BLAS".to_string(), |cmd| {
-                    cmd.build_acceleration_structure(&geometry_info, &range);
-                });
-
-                Some(Arc::new(acceleration_structure))
+                    .flags(vk::GeometryFlagsKHR::OPAQUE)
             }
+        }
+    }
+
+    pub(super) fn range(&self) -> vk::AccelerationStructureBuildRangeInfoKHR {
+        let primitive_count = match &self.geometry_type {
+            GeometryType::Aabbs(aabbs_buffer) => aabbs_buffer.count::<Aabb>(),
+            GeometryType::Triangles { indices, .. } => indices.count::<u32>() / 3,
+        };
+
+        vk::AccelerationStructureBuildRangeInfoKHR::default()
+            .primitive_count(primitive_count as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        debug::DebugFeature,
+        ray_tracing::{
+            scenes::{GeometryInstance, SceneBuilder},
+            RayTracingFeature,
+        },
+        Kea,
+    };
+    use glam::{vec3a, Affine3A};
+
+    #[test]
+    #[ignore = "needs a Vulkan device with ray tracing support"]
+    fn update_moves_vertices_and_refits() {
+        let kea = Kea::new_headless(vec![
+            Box::new(RayTracingFeature::new()),
+            Box::new(DebugFeature::new()),
+        ]);
+        let device = kea.device();
+
+        let vertices = [
+            vec3a(0.0, 0.0, 0.0),
+            vec3a(1.0, 0.0, 0.0),
+            vec3a(0.0, 1.0, 0.0),
+        ];
+        let vertex_buffer = Buffer::new_from_data(
+            device.clone(),
+            &vertices,
+            vk::BufferUsageFlags::ACCELERATION_STRUCTURE_BUILD_INPUT_READ_ONLY_KHR
+                | vk::BufferUsageFlags::TRANSFER_DST
+                | vk::BufferUsageFlags::TRANSFER_SRC,
+            "vertices".to_string(),
+            MemoryLocation::GpuOnly,
+            None,
+        );
+        let index_buffer = Buffer::new_from_data(
+            device.clone(),
+            &[0u32, 1, 2],
+            vk::BufferUsageFlags::ACCELERATION_STRUCTURE_BUILD_INPUT_READ_ONLY_KHR,
+            "indices".to_string(),
+            MemoryLocation::GpuOnly,
+            None,
+        );
+
+        let mut builder = SceneBuilder::new(device.clone(), "deforming".to_string());
+        let geometry = builder.add_geometry(Geometry::new_updatable(
+            device.clone(),
+            "triangle".to_string(),
+            GeometryType::Triangles {
+                vertices: vertex_buffer,
+                indices: index_buffer,
+            },
+        ));
+        assert!(geometry.scratch_buffer.is_some());
+        builder.add_instance(GeometryInstance::new(
+            geometry.clone(),
+            0,
+            Affine3A::IDENTITY,
+            0,
+        ));
+        let mut scene = builder.build();
+
+        let moved = vertices.map(|vertex| vertex + vec3a(0.0, 0.0, -1.0));
+        // The staging buffer must outlive the command buffer, which has
+        // completed by the time `now` returns.
+        let mut staging = None;
+        CommandBuffer::now(device, "update".to_string(), |cmd| {
+            staging = Some(geometry.update(cmd, &moved));
+            scene.update_instances(cmd, 0);
+        });
+        drop(staging);
+
+        let GeometryType::Triangles { vertices, .. } = &geometry.geometry_type else {
+            unreachable!();
         };
+        assert_eq!(vertices.read::<Vec3A>(), moved);
     }
 }
@@ -17,22 +17,7 @@ impl GeometryInstance {
         transform: Affine3A,
         custom_index: u32,
     ) -> Self {
-        let transform = vk::TransformMatrixKHR {
-            matrix: [
-                transform.x_axis.x,
-                transform.y_axis.x,
-                transform.z_axis.x,
-                transform.translation.x,
-                transform.x_axis.y,
-                transform.y_axis.y,
-                transform.z_axis.y,
-                transform.translation.y,
-                transform.x_axis.z,
-                transform.y_axis.z,
-                transform.z_axis.z,
-                transform.translation.z,
-            ],
-        };
+        let transform = transform_matrix(transform);
 
         Self {
             transform,
@@ -42,6 +27,10 @@ impl GeometryInstance {
         }
     }
 
+    pub fn set_transform(&mut self, transform: Affine3A) {
+        self.transform = transform_matrix(transform);
+    }
+
     pub fn geometry(&self) -> &Arc<Geometry> {
         &self.geometry
     }
@@ -66,3 +55,23 @@ impl GeometryInstance {
         }
     }
 }
+
+/// The 3x4 row-major matrix Vulkan expects for an instance's transform.
+fn transform_matrix(transform: Affine3A) -> vk::TransformMatrixKHR {
+    vk::TransformMatrixKHR {
+        matrix: [
+            transform.x_axis.x,
+            transform.y_axis.x,
+            transform.z_axis.x,
+            transform.translation.x,
+            transform.x_axis.y,
+            transform.y_axis.y,
+            transform.z_axis.y,
+            transform.translation.y,
+            transform.x_axis.z,
+            transform.y_axis.z,
+            transform.z_axis.z,
+            transform.translation.z,
+        ],
+    }
+}
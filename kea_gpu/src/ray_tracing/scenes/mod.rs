@@ -2,9 +2,11 @@ mod acceleration_structure;
 mod geometry;
 mod geometry_instance;
 mod scene;
+mod scene_builder;
 mod scratch_buffer;
 
 pub use acceleration_structure::AccelerationStructure;
 pub use geometry::{Geometry, GeometryType};
 pub use geometry_instance::GeometryInstance;
 pub use scene::Scene;
+pub use scene_builder::SceneBuilder;
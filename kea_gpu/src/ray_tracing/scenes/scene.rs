@@ -1,17 +1,38 @@
 use super::{
     acceleration_structure::AccelerationStructure, scratch_buffer::ScratchBuffer, GeometryInstance,
 };
-use crate::{commands::CommandBuffer, device::Device, storage::buffers::Buffer};
+use crate::{
+    commands::{CommandBuffer, CommandBufferRecorder},
+    device::Device,
+    presentation::FRAMES_IN_FLIGHT,
+    storage::buffers::Buffer,
+};
 use ash::vk;
+use glam::Affine3A;
 use gpu_allocator::MemoryLocation;
 use std::{slice, sync::Arc};
 
+/// Refitting an acceleration structure loosens its bounding boxes as the
+/// instances move, so it's rebuilt from scratch after this many updates.
+const REBUILD_INTERVAL: u32 = 64;
+
+const BUILD_FLAGS: vk::BuildAccelerationStructureFlagsKHR =
+    vk::BuildAccelerationStructureFlagsKHR::from_raw(
+        vk::BuildAccelerationStructureFlagsKHR::PREFER_FAST_TRACE.as_raw()
+            | vk::BuildAccelerationStructureFlagsKHR::ALLOW_UPDATE.as_raw(),
+    );
+
 pub struct Scene {
     device: Arc<Device>,
     name: String,
     instances: Vec<GeometryInstance>,
     acceleration_structure: Option<Arc<AccelerationStructure>>,
-    instances_buffer: Option<Buffer>,
+    /// The instances as the GPU reads them, one buffer for each frame in
+    /// flight so they can be rewritten while an earlier frame is running.
+    instances_buffers: Vec<Buffer>,
+    /// Kept for updates, large enough for either a build or an update.
+    scratch_buffer: Option<ScratchBuffer>,
+    updates_since_build: u32,
 }
 
 impl Scene {
@@ -21,7 +42,9 @@ impl Scene {
             name,
             instances: vec![],
             acceleration_structure: None,
-            instances_buffer: None,
+            instances_buffers: vec![],
+            scratch_buffer: None,
+            updates_since_build: 0,
         }
     }
 
@@ -33,6 +56,12 @@ impl Scene {
         self.instances.push(instance);
     }
 
+    /// Move an instance. The acceleration structure doesn't change until
+    /// `update_instances` is next recorded.
+    pub fn set_instance_transform(&mut self, index: usize, transform: Affine3A) {
+        self.instances[index].set_transform(transform);
+    }
+
     pub fn acceleration_structure(&self) -> &Arc<AccelerationStructure> {
         if self.acceleration_structure.is_none() {
             panic!("Scene {} isn't built", self.name);
@@ -41,48 +70,45 @@ impl Scene {
         self.acceleration_structure.as_ref().unwrap()
     }
 
+    /// Build the acceleration structure straight away, from geometries that
+    /// have already been built.
     pub fn build(&mut self) {
-        let instances_data: Vec<vk::AccelerationStructureInstanceKHR> = self
-            .instances
-            .iter()
-            .map(|instance| unsafe { instance.raw() })
-            .collect();
+        self.allocate();
 
-        let instances_buffer = Buffer::new_from_data(
-            self.device.clone(),
-            &instances_data,
-            vk::BufferUsageFlags::ACCELERATION_STRUCTURE_BUILD_INPUT_READ_ONLY_KHR,
-            "scene instances".to_string(),
-            MemoryLocation::CpuToGpu,
-            None,
-        );
-
-        let instances = vk::AccelerationStructureGeometryInstancesDataKHR::default()
-            .data(vk::DeviceOrHostAddressConstKHR {
-                device_address: instances_buffer.device_address(),
-            })
-            .array_of_pointers(false);
+        CommandBuffer::now(&self.device, "build TLAS".to_string(), |cmd| {
+            self.record_initial_build(cmd);
+        });
+    }
 
-        let geometry = vk::AccelerationStructureGeometryKHR::default()
-            .geometry_type(vk::GeometryTypeKHR::INSTANCES)
-            .flags(vk::GeometryFlagsKHR::OPAQUE)
-            .geometry(vk::AccelerationStructureGeometryDataKHR {
-                instances,
-            });
+    /// Create the acceleration structure and the buffers it's built with.
+    pub(super) fn allocate(&mut self) {
+        let instances_data = self.instances_data();
 
-        let range = vk::AccelerationStructureBuildRangeInfoKHR {
-            primitive_count: instances_data.len() as _,
-            primitive_offset: 0,
-            first_vertex: 0,
-            transform_offset: 0,
-        };
+        self.instances_buffers = (0..FRAMES_IN_FLIGHT)
+            .map(|frame| {
+                Buffer::new_from_data(
+                    self.device.clone(),
+                    &instances_data,
+                    vk::BufferUsageFlags::ACCELERATION_STRUCTURE_BUILD_INPUT_READ_ONLY_KHR,
+                    format!("scene instances {}", frame),
+                    MemoryLocation::CpuToGpu,
+                    None,
+                )
+            })
+            .collect();
 
+        let geometry = Self::geometry(&self.instances_buffers[0]);
+        let range = Self::range(instances_data.len());
         let geometry_info = vk::AccelerationStructureBuildGeometryInfoKHR::default()
             .ty(vk::AccelerationStructureTypeKHR::TOP_LEVEL)
+            .flags(BUILD_FLAGS)
             .geometries(slice::from_ref(&geometry));
 
         let build_sizes = AccelerationStructure::build_sizes(&self.device, &geometry_info, &range);
-        let scratch_buffer = ScratchBuffer::new(self.device.clone(), build_sizes.build_scratch);
+        let scratch_buffer = ScratchBuffer::new(
+            self.device.clone(),
+            build_sizes.build_scratch.max(build_sizes.update_scratch),
+        );
 
         let acceleration_structure_buffer = Buffer::new(
             self.device.clone(),
@@ -98,24 +124,148 @@ impl Scene {
             vk::AccelerationStructureTypeKHR::TOP_LEVEL,
         );
 
-        let geometry_info = vk::AccelerationStructureBuildGeometryInfoKHR::default()
+        self.scratch_buffer = Some(scratch_buffer);
+        self.acceleration_structure = Some(Arc::new(acceleration_structure));
+        self.updates_since_build = 0;
+    }
+
+    /// Record the first build of the allocated acceleration structure.
+    pub(super) fn record_initial_build(&self, cmd: &CommandBufferRecorder) {
+        self.record_build(
+            cmd,
+            &self.instances_buffers[0],
+            vk::BuildAccelerationStructureModeKHR::BUILD,
+        );
+    }
+
+    /// Record bringing the acceleration structure up to date with the
+    /// instances' transforms, for drawing `frame`. It's usually refitted in
+    /// place, which is quick, but rebuilt every so often to keep tracing
+    /// fast. The GPU must have finished with the frame `FRAMES_IN_FLIGHT`
+    /// before `frame`.
+    ///
+    /// Instances can be moved but not added once the scene is built. The
+    /// acceleration structure is ready for tracing by the time the following
+    /// commands run.
+    pub fn update_instances(&mut self, cmd: &CommandBufferRecorder, frame: u64) {
+        let instances_data = self.instances_data();
+        let instances_buffer = &mut self.instances_buffers[(frame % FRAMES_IN_FLIGHT) as usize];
+        assert_eq!(
+            instances_buffer.count::<vk::AccelerationStructureInstanceKHR>(),
+            instances_data.len(),
+            "Scene {} has gained instances since it was built",
+            self.name
+        );
+        instances_buffer.fill(&instances_data);
+
+        let mode = if self.updates_since_build >= REBUILD_INTERVAL {
+            self.updates_since_build = 0;
+            vk::BuildAccelerationStructureModeKHR::BUILD
+        } else {
+            self.updates_since_build += 1;
+            vk::BuildAccelerationStructureModeKHR::UPDATE
+        };
+
+        // Earlier frames may still be tracing rays through the acceleration
+        // structure, or building it with the same scratch buffer.
+        let before = vk::MemoryBarrier2::default()
+            .src_stage_mask(
+                vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR
+                    | vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR,
+            )
+            .src_access_mask(
+                vk::AccessFlags2::ACCELERATION_STRUCTURE_READ_KHR
+                    | vk::AccessFlags2::ACCELERATION_STRUCTURE_WRITE_KHR,
+            )
+            .dst_stage_mask(vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR)
+            .dst_access_mask(
+                vk::AccessFlags2::ACCELERATION_STRUCTURE_READ_KHR
+                    | vk::AccessFlags2::ACCELERATION_STRUCTURE_WRITE_KHR,
+            );
+        cmd.pipeline_barrier(
+            vk::DependencyFlags::empty(),
+            slice::from_ref(&before),
+            &[],
+            &[],
+        );
+
+        self.record_build(
+            cmd,
+            &self.instances_buffers[(frame % FRAMES_IN_FLIGHT) as usize],
+            mode,
+        );
+
+        let after = vk::MemoryBarrier2::default()
+            .src_stage_mask(vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR)
+            .src_access_mask(vk::AccessFlags2::ACCELERATION_STRUCTURE_WRITE_KHR)
+            .dst_stage_mask(vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR)
+            .dst_access_mask(vk::AccessFlags2::ACCELERATION_STRUCTURE_READ_KHR);
+        cmd.pipeline_barrier(
+            vk::DependencyFlags::empty(),
+            slice::from_ref(&after),
+            &[],
+            &[],
+        );
+    }
+
+    fn instances_data(&self) -> Vec<vk::AccelerationStructureInstanceKHR> {
+        self.instances
+            .iter()
+            .map(|instance| unsafe { instance.raw() })
+            .collect()
+    }
+
+    /// Record a build of the acceleration structure from `instances_buffer`,
+    /// either from scratch or updating it in place.
+    fn record_build(
+        &self,
+        cmd: &CommandBufferRecorder,
+        instances_buffer: &Buffer,
+        mode: vk::BuildAccelerationStructureModeKHR,
+    ) {
+        let acceleration_structure = unsafe { self.acceleration_structure().raw() };
+        let scratch_buffer = self.scratch_buffer.as_ref().unwrap();
+
+        let geometry = Self::geometry(instances_buffer);
+        let range = Self::range(self.instances.len());
+        let mut geometry_info = vk::AccelerationStructureBuildGeometryInfoKHR::default()
             .ty(vk::AccelerationStructureTypeKHR::TOP_LEVEL)
             .geometries(slice::from_ref(&geometry))
-            .mode(vk::BuildAccelerationStructureModeKHR::BUILD)
-            .flags(vk::BuildAccelerationStructureFlagsKHR::PREFER_FAST_TRACE)
-            .dst_acceleration_structure(unsafe { acceleration_structure.raw() })
+            .mode(mode)
+            .flags(BUILD_FLAGS)
+            .dst_acceleration_structure(acceleration_structure)
             .scratch_data(vk::DeviceOrHostAddressKHR {
                 device_address: scratch_buffer.device_address(),
             });
+        if mode == vk::BuildAccelerationStructureModeKHR::UPDATE {
+            geometry_info = geometry_info.src_acceleration_structure(acceleration_structure);
+        }
 
         log::debug!("geometry info {:?}", geometry_info);
         log::debug!("range {:?}", range);
 
-        CommandBuffer::now(&self.device, "build TLAS".to_string(), |cmd| {
-            cmd.build_acceleration_structure(&geometry_info, &range);
-        });
+        cmd.build_acceleration_structure(&geometry_info, &range);
+    }
 
-        self.instances_buffer = Some(instances_buffer);
-        self.acceleration_structure = Some(Arc::new(acceleration_structure));
+    fn geometry(instances_buffer: &Buffer) -> vk::AccelerationStructureGeometryKHR<'static> {
+        let instances = vk::AccelerationStructureGeometryInstancesDataKHR::default()
+            .data(vk::DeviceOrHostAddressConstKHR {
+                device_address: instances_buffer.device_address(),
+            })
+            .array_of_pointers(false);
+
+        vk::AccelerationStructureGeometryKHR::default()
+            .geometry_type(vk::GeometryTypeKHR::INSTANCES)
+            .flags(vk::GeometryFlagsKHR::OPAQUE)
+            .geometry(vk::AccelerationStructureGeometryDataKHR { instances })
+    }
+
+    fn range(instance_count: usize) -> vk::AccelerationStructureBuildRangeInfoKHR {
+        vk::AccelerationStructureBuildRangeInfoKHR {
+            primitive_count: instance_count as _,
+            primitive_offset: 0,
+            first_vertex: 0,
+            transform_offset: 0,
+        }
     }
 }
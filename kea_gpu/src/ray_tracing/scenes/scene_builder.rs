@@ -0,0 +1,104 @@
+use super::{scratch_buffer::ScratchBuffer, Geometry, GeometryInstance, Scene};
+use crate::{commands::CommandBuffer, device::Device};
+use ash::vk;
+use std::{slice, sync::Arc};
+
+/// Collects the geometries and instances of a scene, then builds all their
+/// acceleration structures in one go. The geometries are built together,
+/// sharing a scratch buffer, and the scene is built after them, all in a
+/// single submission.
+pub struct SceneBuilder {
+    device: Arc<Device>,
+    scene: Scene,
+    /// Geometries waiting to be built, with their scratch memory's offset.
+    geometries: Vec<(Arc<Geometry>, vk::DeviceSize)>,
+    scratch_size: vk::DeviceSize,
+}
+
+impl SceneBuilder {
+    pub fn new(device: Arc<Device>, name: String) -> Self {
+        Self {
+            scene: Scene::new(device.clone(), name),
+            device,
+            geometries: vec![],
+            scratch_size: 0,
+        }
+    }
+
+    /// Add a geometry to be built with the scene. Returns it ready for
+    /// instancing, though its acceleration structure stays empty until the
+    /// scene is built.
+    pub fn add_geometry(&mut self, mut geometry: Geometry) -> Arc<Geometry> {
+        let build_scratch_size = geometry.allocate();
+        let alignment = ScratchBuffer::alignment(&self.device);
+        let offset = self.scratch_size.next_multiple_of(alignment);
+        self.scratch_size = offset + build_scratch_size;
+
+        let geometry = Arc::new(geometry);
+        self.geometries.push((geometry.clone(), offset));
+        geometry
+    }
+
+    pub fn instances(&self) -> &[GeometryInstance] {
+        self.scene.instances()
+    }
+
+    pub fn add_instance(&mut self, instance: GeometryInstance) {
+        self.scene.add_instance(instance);
+    }
+
+    pub fn build(mut self) -> Scene {
+        log::info!(
+            "Building {} geometries and {} instances",
+            self.geometries.len(),
+            self.scene.instances().len()
+        );
+
+        self.scene.allocate();
+        let scratch_buffer = (!self.geometries.is_empty())
+            .then(|| ScratchBuffer::new(self.device.clone(), self.scratch_size));
+
+        CommandBuffer::now(&self.device, "build scene".to_string(), |cmd| {
+            if let Some(scratch_buffer) = &scratch_buffer {
+                let descriptions: Vec<_> = self
+                    .geometries
+                    .iter()
+                    .map(|(geometry, _)| (geometry.geometry(), geometry.range()))
+                    .collect();
+                let geometry_infos: Vec<_> = self
+                    .geometries
+                    .iter()
+                    .zip(&descriptions)
+                    .map(|((geometry, offset), (description, _))| {
+                        geometry.build_info(
+                            description,
+                            vk::BuildAccelerationStructureModeKHR::BUILD,
+                            scratch_buffer.device_address() + offset,
+                        )
+                    })
+                    .collect();
+                let ranges: Vec<_> = descriptions
+                    .iter()
+                    .map(|(_, range)| slice::from_ref(range))
+                    .collect();
+                cmd.build_acceleration_structures(&geometry_infos, &ranges);
+
+                let barrier = vk::MemoryBarrier2::default()
+                    .src_stage_mask(vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR)
+                    .src_access_mask(vk::AccessFlags2::ACCELERATION_STRUCTURE_WRITE_KHR)
+                    .dst_stage_mask(vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR)
+                    .dst_access_mask(vk::AccessFlags2::ACCELERATION_STRUCTURE_READ_KHR);
+                cmd.pipeline_barrier(
+                    vk::DependencyFlags::empty(),
+                    slice::from_ref(&barrier),
+                    &[],
+                    &[],
+                );
+            }
+
+            self.scene.record_initial_build(cmd);
+        });
+
+        self.scene
+    }
+}
@@ -8,10 +8,7 @@ pub struct ScratchBuffer {
 
 impl ScratchBuffer {
     pub fn new(device: Arc<Device>, size: u64) -> ScratchBuffer {
-        let vk::PhysicalDeviceAccelerationStructurePropertiesKHR {
-            min_acceleration_structure_scratch_offset_alignment: alignment,
-            ..
-        } = device.physical_device().acceleration_structure_properties();
+        let alignment = Self::alignment(&device);
 
         let buffer = Buffer::new(
             device,
@@ -19,12 +16,23 @@ impl ScratchBuffer {
             vk::BufferUsageFlags::STORAGE_BUFFER,
             "acceleration structure build scratch".to_string(),
             gpu_allocator::MemoryLocation::GpuOnly,
-            Some(alignment as _),
+            Some(alignment),
         );
 
         ScratchBuffer { buffer }
     }
 
+    /// The alignment each build's scratch memory needs, for sharing one
+    /// buffer between several builds.
+    pub fn alignment(device: &Device) -> u64 {
+        let vk::PhysicalDeviceAccelerationStructurePropertiesKHR {
+            min_acceleration_structure_scratch_offset_alignment: alignment,
+            ..
+        } = device.physical_device().acceleration_structure_properties();
+
+        alignment as _
+    }
+
     pub fn device_address(&self) -> vk::DeviceAddress {
         self.buffer.device_address()
     }
@@ -32,16 +32,27 @@ impl ShaderModule {
             .iter()
             .map(|(entry_point, bytes)| {
                 let entry_point = entry_point.to_string();
-                let module = Self::from_spirv_bytes(
-                    device.clone(),
-                    bytes,
-                    vec![entry_point.clone()],
-                );
+                let module =
+                    Self::from_spirv_bytes(device.clone(), bytes, vec![entry_point.clone()]);
                 (entry_point, module)
             })
             .collect()
     }
 
+    /// Load just the module with `entry_point` out of `modules`.
+    pub fn load_module(
+        device: &Arc<Device>,
+        modules: &[(&str, &[u8])],
+        entry_point: &str,
+    ) -> Arc<ShaderModule> {
+        let (_, bytes) = modules
+            .iter()
+            .find(|(name, _)| *name == entry_point)
+            .unwrap_or_else(|| panic!("No shader module has entry point {}", entry_point));
+
+        Self::from_spirv_bytes(device.clone(), bytes, vec![entry_point.to_string()])
+    }
+
     pub unsafe fn raw(&self) -> vk::ShaderModule {
         self.raw
     }
@@ -1,22 +1,59 @@
 use crate::{
     descriptors::{DescriptorPool, DescriptorSet},
     device::Device,
-    ray_tracing::{scenes::AccelerationStructure, RayTracingPipeline},
-    storage::{buffers::Buffer, images::ImageView},
+    pipelines::SlotPipeline,
+    presentation::FRAMES_IN_FLIGHT,
+    ray_tracing::scenes::AccelerationStructure,
+    storage::{
+        buffers::Buffer,
+        images::{ImageView, Sampler},
+    },
 };
 use ash::vk;
 use kea_gpu_shaderlib::slots::SlotType;
-use std::{collections::HashMap, hash::Hash, slice, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    slice,
+    sync::Arc,
+};
 
-pub struct SlotBindings<SlotId> {
+/// A resource bound to an element of a slot.
+#[derive(Clone)]
+enum Binding {
+    Buffer(Arc<Buffer>),
+    AccelerationStructure(Arc<AccelerationStructure>),
+    Image(Arc<ImageView>),
+    SampledImage(Arc<ImageView>),
+    Sampler(Arc<Sampler>),
+    CombinedImageSampler(Arc<ImageView>, Arc<Sampler>),
+}
+
+/// The descriptor set used by every `FRAMES_IN_FLIGHT`th frame.
+struct FrameSet<SlotId> {
     descriptor_set: DescriptorSet,
-    buffers: HashMap<SlotId, Arc<Buffer>>,
-    acceleration_structures: HashMap<SlotId, Arc<AccelerationStructure>>,
-    images: HashMap<SlotId, Arc<ImageView>>,
+    /// What the set points to, kept alive for as long as it does.
+    bound: HashMap<(SlotId, u32), Binding>,
+    /// Elements that have been rebound since the set was last brought up to
+    /// date.
+    pending: HashSet<(SlotId, u32)>,
+}
+
+/// The resources bound to each slot of a pipeline. Resources are keyed by
+/// their slot and their index in it, which is 0 except in array slots.
+///
+/// There's a descriptor set for each frame in flight, so resources can be
+/// rebound while earlier frames are still running. Each set only picks up
+/// the changes when it's next used, by which point the GPU has finished with
+/// it, and keeps the resources it had alive until then.
+pub struct SlotBindings<SlotId> {
+    frames: Vec<FrameSet<SlotId>>,
+    bindings: HashMap<(SlotId, u32), Binding>,
 }
 
 impl<SlotId: Into<u32> + Hash + Eq + Copy> SlotBindings<SlotId> {
-    pub fn new(device: Arc<Device>, pipeline: &RayTracingPipeline<SlotId>) -> Self {
+    pub fn new(device: Arc<Device>, pipeline: &impl SlotPipeline<SlotId>) -> Self {
+        let frame_count = FRAMES_IN_FLIGHT as u32;
         let pool_sizes: Vec<_> = pipeline
             .slot_layout()
             .slots()
@@ -28,43 +65,47 @@ impl<SlotId: Into<u32> + Hash + Eq + Copy> SlotBindings<SlotId> {
                     }
                     SlotType::Buffer(_) => vk::DescriptorType::STORAGE_BUFFER,
                     SlotType::Image => vk::DescriptorType::STORAGE_IMAGE,
+                    SlotType::SampledImage => vk::DescriptorType::SAMPLED_IMAGE,
+                    SlotType::Sampler => vk::DescriptorType::SAMPLER,
+                    SlotType::CombinedImageSampler => vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
                 },
-                descriptor_count: 1,
+                descriptor_count: slot.descriptor_count() * frame_count,
             })
             .collect();
 
-        let descriptor_pool = DescriptorPool::new(device, 1, &pool_sizes);
-        let descriptor_sets = descriptor_pool
-            .allocate_descriptor_sets(slice::from_ref(pipeline.layout().descriptor_set_layout()));
-        let descriptor_set = descriptor_sets.into_iter().nth(0).unwrap();
+        let flags = if pipeline.slot_layout().has_arrays() {
+            vk::DescriptorPoolCreateFlags::UPDATE_AFTER_BIND
+        } else {
+            vk::DescriptorPoolCreateFlags::empty()
+        };
+        let descriptor_pool = DescriptorPool::new(device, frame_count, &pool_sizes, flags);
+        let frames = (0..frame_count)
+            .map(|_| {
+                let descriptor_sets = descriptor_pool.allocate_descriptor_sets(slice::from_ref(
+                    pipeline.layout().descriptor_set_layout(),
+                ));
+                FrameSet {
+                    descriptor_set: descriptor_sets.into_iter().next().unwrap(),
+                    bound: HashMap::new(),
+                    pending: HashSet::new(),
+                }
+            })
+            .collect();
 
         Self {
-            descriptor_set,
-            buffers: HashMap::new(),
-            acceleration_structures: HashMap::new(),
-            images: HashMap::new(),
+            frames,
+            bindings: HashMap::new(),
         }
     }
 
     pub fn bind_buffer(&mut self, slot_id: SlotId, buffer: Arc<Buffer>) {
-        let buffer_info = vk::DescriptorBufferInfo {
-            buffer: unsafe { buffer.buffer().raw() },
-            offset: 0,
-            range: vk::WHOLE_SIZE,
-        };
-        let write_set = vk::WriteDescriptorSet::default()
-            .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
-            .dst_set(unsafe { self.descriptor_set.raw() })
-            .dst_binding(slot_id.into())
-            .buffer_info(slice::from_ref(&buffer_info));
-
-        unsafe {
-            self.device()
-                .raw()
-                .update_descriptor_sets(slice::from_ref(&write_set), &[])
-        };
+        self.bind_buffer_at(slot_id, 0, buffer);
+    }
 
-        self.buffers.insert(slot_id, buffer);
+    /// Bind a buffer to element `index` of an array slot, replacing whatever
+    /// was there.
+    pub fn bind_buffer_at(&mut self, slot_id: SlotId, index: u32, buffer: Arc<Buffer>) {
+        self.bind(slot_id, index, Binding::Buffer(buffer));
     }
 
     pub fn bind_acceleration_structure(
@@ -72,52 +113,146 @@ impl<SlotId: Into<u32> + Hash + Eq + Copy> SlotBindings<SlotId> {
         slot_id: SlotId,
         acceleration_structure: Arc<AccelerationStructure>,
     ) {
-        let accel_raw = unsafe { acceleration_structure.raw() };
-        let accel_slice = std::slice::from_ref(&accel_raw);
-        let mut write_set_as = vk::WriteDescriptorSetAccelerationStructureKHR::default()
-            .acceleration_structures(accel_slice);
-        let mut write_set = vk::WriteDescriptorSet::default()
-            .descriptor_type(vk::DescriptorType::ACCELERATION_STRUCTURE_KHR)
-            .dst_set(unsafe { self.descriptor_set.raw() })
-            .dst_binding(slot_id.into())
-            .push_next(&mut write_set_as);
-        write_set.descriptor_count = 1;
-
-        unsafe {
-            self.device()
-                .raw()
-                .update_descriptor_sets(slice::from_ref(&write_set), &[])
-        };
-
-        self.acceleration_structures
-            .insert(slot_id, acceleration_structure);
+        self.bind(
+            slot_id,
+            0,
+            Binding::AccelerationStructure(acceleration_structure),
+        );
     }
 
     pub fn bind_image(&mut self, slot_id: SlotId, image: Arc<ImageView>) {
-        let desc_img_info = vk::DescriptorImageInfo::default()
-            .image_view(unsafe { image.raw() })
-            .image_layout(vk::ImageLayout::GENERAL);
-
-        let write_set = vk::WriteDescriptorSet::default()
-            .descriptor_type(vk::DescriptorType::STORAGE_IMAGE)
-            .dst_set(unsafe { self.descriptor_set.raw() })
-            .dst_binding(slot_id.into())
-            .image_info(slice::from_ref(&desc_img_info));
-
-        unsafe {
-            self.device()
-                .raw()
-                .update_descriptor_sets(slice::from_ref(&write_set), &[])
-        };
+        self.bind(slot_id, 0, Binding::Image(image));
+    }
 
-        self.images.insert(slot_id, image);
+    /// Bind an image to be read through a sampler bound to another slot. The
+    /// image must be in `SHADER_READ_ONLY_OPTIMAL` layout whenever the shaders
+    /// run.
+    pub fn bind_sampled_image(&mut self, slot_id: SlotId, image: Arc<ImageView>) {
+        self.bind_sampled_image_at(slot_id, 0, image);
     }
 
-    pub fn device(&self) -> &Arc<Device> {
-        self.descriptor_set.device()
+    /// Bind a sampled image to element `index` of an array slot, as
+    /// `bind_buffer_at` does for buffers.
+    pub fn bind_sampled_image_at(&mut self, slot_id: SlotId, index: u32, image: Arc<ImageView>) {
+        self.bind(slot_id, index, Binding::SampledImage(image));
+    }
+
+    pub fn bind_sampler(&mut self, slot_id: SlotId, sampler: Arc<Sampler>) {
+        self.bind(slot_id, 0, Binding::Sampler(sampler));
+    }
+
+    /// Bind an image to be read through `sampler`. The image must be in
+    /// `SHADER_READ_ONLY_OPTIMAL` layout whenever the shaders run.
+    pub fn bind_combined_image_sampler(
+        &mut self,
+        slot_id: SlotId,
+        image: Arc<ImageView>,
+        sampler: Arc<Sampler>,
+    ) {
+        self.bind(slot_id, 0, Binding::CombinedImageSampler(image, sampler));
+    }
+
+    fn bind(&mut self, slot_id: SlotId, index: u32, binding: Binding) {
+        self.bindings.insert((slot_id, index), binding);
+        for frame in &mut self.frames {
+            frame.pending.insert((slot_id, index));
+        }
+    }
+
+    /// The descriptor set to draw `frame` with, brought up to date with
+    /// everything bound so far. The GPU must have finished with the frame
+    /// `FRAMES_IN_FLIGHT` before it, which last used the same set.
+    pub fn descriptor_set(&mut self, frame: u64) -> &DescriptorSet {
+        let frame = &mut self.frames[(frame % FRAMES_IN_FLIGHT) as usize];
+
+        for key in frame.pending.drain() {
+            let binding = self.bindings[&key].clone();
+            write_descriptor(&frame.descriptor_set, key.0.into(), key.1, &binding);
+            frame.bound.insert(key, binding);
+        }
+
+        &frame.descriptor_set
     }
 
-    pub fn descriptor_set(&self) -> &DescriptorSet {
-        &self.descriptor_set
+    pub fn device(&self) -> &Arc<Device> {
+        self.frames[0].descriptor_set.device()
     }
 }
+
+/// Point element `index` of `binding_index` in `descriptor_set` at `binding`.
+fn write_descriptor(
+    descriptor_set: &DescriptorSet,
+    binding_index: u32,
+    index: u32,
+    binding: &Binding,
+) {
+    let write_set = vk::WriteDescriptorSet::default()
+        .dst_set(unsafe { descriptor_set.raw() })
+        .dst_binding(binding_index)
+        .dst_array_element(index);
+
+    let buffer_info;
+    let image_info;
+    let accel_raw;
+    let mut write_set_as;
+    let write_set = match binding {
+        Binding::Buffer(buffer) => {
+            buffer_info = vk::DescriptorBufferInfo {
+                buffer: unsafe { buffer.buffer().raw() },
+                offset: 0,
+                range: vk::WHOLE_SIZE,
+            };
+            write_set
+                .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+                .buffer_info(slice::from_ref(&buffer_info))
+        }
+        Binding::AccelerationStructure(acceleration_structure) => {
+            accel_raw = unsafe { acceleration_structure.raw() };
+            write_set_as = vk::WriteDescriptorSetAccelerationStructureKHR::default()
+                .acceleration_structures(slice::from_ref(&accel_raw));
+            let mut write_set = write_set
+                .descriptor_type(vk::DescriptorType::ACCELERATION_STRUCTURE_KHR)
+                .push_next(&mut write_set_as);
+            write_set.descriptor_count = 1;
+            write_set
+        }
+        Binding::Image(image) => {
+            image_info = vk::DescriptorImageInfo::default()
+                .image_view(unsafe { image.raw() })
+                .image_layout(vk::ImageLayout::GENERAL);
+            write_set
+                .descriptor_type(vk::DescriptorType::STORAGE_IMAGE)
+                .image_info(slice::from_ref(&image_info))
+        }
+        Binding::SampledImage(image) => {
+            image_info = vk::DescriptorImageInfo::default()
+                .image_view(unsafe { image.raw() })
+                .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+            write_set
+                .descriptor_type(vk::DescriptorType::SAMPLED_IMAGE)
+                .image_info(slice::from_ref(&image_info))
+        }
+        Binding::Sampler(sampler) => {
+            image_info = vk::DescriptorImageInfo::default().sampler(unsafe { sampler.raw() });
+            write_set
+                .descriptor_type(vk::DescriptorType::SAMPLER)
+                .image_info(slice::from_ref(&image_info))
+        }
+        Binding::CombinedImageSampler(image, sampler) => {
+            image_info = vk::DescriptorImageInfo::default()
+                .image_view(unsafe { image.raw() })
+                .sampler(unsafe { sampler.raw() })
+                .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+            write_set
+                .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                .image_info(slice::from_ref(&image_info))
+        }
+    };
+
+    unsafe {
+        descriptor_set
+            .device()
+            .raw()
+            .update_descriptor_sets(slice::from_ref(&write_set), &[])
+    };
+}
@@ -2,6 +2,13 @@ use crate::descriptors::DescriptorSetLayoutBinding;
 use ash::vk;
 use kea_gpu_shaderlib::slots::{Slot, SlotType};
 
+/// Array slots don't have to be filled, and elements the shaders aren't using
+/// can be bound while the shaders are running.
+const ARRAY_BINDING_FLAGS: vk::DescriptorBindingFlags = vk::DescriptorBindingFlags::from_raw(
+    vk::DescriptorBindingFlags::PARTIALLY_BOUND.as_raw()
+        | vk::DescriptorBindingFlags::UPDATE_AFTER_BIND.as_raw(),
+);
+
 pub struct SlotLayout<SlotId> {
     slots: Vec<(SlotId, Slot)>,
 }
@@ -22,6 +29,9 @@ impl<SlotId> SlotLayout<SlotId> {
                     }
                     SlotType::Image => vk::DescriptorType::STORAGE_IMAGE,
                     SlotType::Buffer(_) => vk::DescriptorType::STORAGE_BUFFER,
+                    SlotType::SampledImage => vk::DescriptorType::SAMPLED_IMAGE,
+                    SlotType::Sampler => vk::DescriptorType::SAMPLER,
+                    SlotType::CombinedImageSampler => vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
                 };
 
                 let mut stage_flags = vk::ShaderStageFlags::empty();
@@ -34,12 +44,42 @@ impl<SlotId> SlotLayout<SlotId> {
                 if slot.stages.closest_hit {
                     stage_flags |= vk::ShaderStageFlags::CLOSEST_HIT_KHR
                 }
+                if slot.stages.miss {
+                    stage_flags |= vk::ShaderStageFlags::MISS_KHR
+                }
+                if slot.stages.compute {
+                    stage_flags |= vk::ShaderStageFlags::COMPUTE
+                }
+                if slot.stages.vertex {
+                    stage_flags |= vk::ShaderStageFlags::VERTEX
+                }
+                if slot.stages.fragment {
+                    stage_flags |= vk::ShaderStageFlags::FRAGMENT
+                }
 
-                DescriptorSetLayoutBinding::new(index as _, descriptor_type, 1, stage_flags)
+                let binding = DescriptorSetLayoutBinding::new(
+                    index as _,
+                    descriptor_type,
+                    slot.descriptor_count(),
+                    stage_flags,
+                );
+                if slot.array_capacity.is_some() {
+                    binding.with_flags(ARRAY_BINDING_FLAGS)
+                } else {
+                    binding
+                }
             })
             .collect()
     }
 
+    /// Whether any slot is an array, so descriptor sets for the layout need to
+    /// come from a pool that allows updating them after they're bound.
+    pub fn has_arrays(&self) -> bool {
+        self.slots
+            .iter()
+            .any(|(_, slot)| slot.array_capacity.is_some())
+    }
+
     pub fn slots(&self) -> &[(SlotId, Slot)] {
         &self.slots
     }
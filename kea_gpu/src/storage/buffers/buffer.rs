@@ -1,8 +1,12 @@
 use super::{TransferBuffer, UnallocatedBuffer};
-use crate::{device::Device, storage::memory::Allocation};
+use crate::{
+    commands::{CommandBuffer, CommandBufferRecorder},
+    device::Device,
+    storage::memory::Allocation,
+};
 use ash::vk;
 use gpu_allocator::MemoryLocation;
-use std::{mem, slice, sync::Arc};
+use std::{mem, ptr, slice, sync::Arc};
 
 pub struct Buffer {
     name: String,
@@ -104,6 +108,150 @@ impl Buffer {
         }
     }
 
+    /// Write `data` into the buffer starting `offset` bytes in. GpuOnly
+    /// buffers are written through a staging copy, which blocks until it's
+    /// complete, and must have been created with `TRANSFER_DST` usage.
+    pub fn write_range<T: Copy>(&mut self, offset: u64, data: &[T]) {
+        let size = mem::size_of_val(data);
+        assert!(offset as usize + size <= self.size());
+
+        if self.location == MemoryLocation::GpuOnly {
+            let staging = self.staging_buffer(data);
+            CommandBuffer::now(
+                self.device(),
+                format!("write range of {}", self.name),
+                |cmd| cmd.copy_buffer_region(&staging, self, &Self::region(offset, size)),
+            );
+        } else {
+            let data = unsafe { slice::from_raw_parts(data.as_ptr() as *const u8, size) };
+            let offset = offset as usize;
+            unsafe {
+                let slice = &mut self.allocation.mapped_slice_mut()[offset..offset + size];
+                slice.copy_from_slice(data);
+            }
+        }
+    }
+
+    /// Record a copy of `data` into the buffer starting `offset` bytes in,
+    /// between barriers waiting for earlier commands to finish with the range
+    /// and making the new data visible to later commands. The buffer must
+    /// have been created with `TRANSFER_DST` usage.
+    ///
+    /// The returned staging buffer holds the data until the copy executes, so
+    /// it must be kept alive until the command buffer has completed.
+    #[must_use]
+    pub fn record_write_range<T: Copy>(
+        &self,
+        cmd: &CommandBufferRecorder,
+        offset: u64,
+        data: &[T],
+    ) -> Buffer {
+        let size = mem::size_of_val(data);
+        assert!(offset as usize + size <= self.size());
+
+        let staging = self.staging_buffer(data);
+
+        // Earlier commands, such as the previous frame's, may still be
+        // reading the range being overwritten.
+        let before = vk::BufferMemoryBarrier2::default()
+            .src_stage_mask(vk::PipelineStageFlags2::ALL_COMMANDS)
+            .src_access_mask(vk::AccessFlags2::MEMORY_READ | vk::AccessFlags2::MEMORY_WRITE)
+            .dst_stage_mask(vk::PipelineStageFlags2::TRANSFER)
+            .dst_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+            .buffer(unsafe { self.raw() })
+            .offset(offset)
+            .size(size as u64);
+        cmd.pipeline_barrier(
+            vk::DependencyFlags::empty(),
+            &[],
+            slice::from_ref(&before),
+            &[],
+        );
+
+        cmd.copy_buffer_region(&staging, self, &Self::region(offset, size));
+
+        let after = vk::BufferMemoryBarrier2::default()
+            .src_stage_mask(vk::PipelineStageFlags2::TRANSFER)
+            .src_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+            .dst_stage_mask(vk::PipelineStageFlags2::ALL_COMMANDS)
+            .dst_access_mask(vk::AccessFlags2::MEMORY_READ)
+            .buffer(unsafe { self.raw() })
+            .offset(offset)
+            .size(size as u64);
+        cmd.pipeline_barrier(
+            vk::DependencyFlags::empty(),
+            &[],
+            slice::from_ref(&after),
+            &[],
+        );
+
+        staging
+    }
+
+    /// Copy the buffer's contents back to the host. Any GPU writes to it must
+    /// have completed. GpuOnly buffers are read through a staging copy, and
+    /// must have been created with `TRANSFER_SRC` usage.
+    pub fn read<T: Copy>(&self) -> Vec<T> {
+        if self.location == MemoryLocation::GpuOnly {
+            let staging = Buffer::new(
+                self.device().clone(),
+                self.size() as _,
+                vk::BufferUsageFlags::TRANSFER_DST,
+                format!("{} readback", self.name),
+                MemoryLocation::GpuToCpu,
+                None,
+            );
+
+            CommandBuffer::now(self.device(), format!("read back {}", self.name), |cmd| {
+                cmd.copy_buffer(self, &staging);
+
+                let host_barrier = vk::MemoryBarrier2::default()
+                    .src_stage_mask(vk::PipelineStageFlags2::TRANSFER)
+                    .src_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+                    .dst_stage_mask(vk::PipelineStageFlags2::HOST)
+                    .dst_access_mask(vk::AccessFlags2::HOST_READ);
+                cmd.pipeline_barrier(
+                    vk::DependencyFlags::empty(),
+                    slice::from_ref(&host_barrier),
+                    &[],
+                    &[],
+                );
+            });
+
+            return staging.read();
+        }
+
+        let count = self.count::<T>();
+        let mut data: Vec<T> = Vec::with_capacity(count);
+
+        unsafe {
+            let slice = &self.allocation.mapped_slice()[..count * mem::size_of::<T>()];
+            ptr::copy_nonoverlapping(slice.as_ptr(), data.as_mut_ptr() as *mut u8, slice.len());
+            data.set_len(count);
+        }
+
+        data
+    }
+
+    fn staging_buffer<T: Copy>(&self, data: &[T]) -> Buffer {
+        Buffer::new_from_data(
+            self.device().clone(),
+            data,
+            vk::BufferUsageFlags::TRANSFER_SRC,
+            format!("{} staging", self.name),
+            MemoryLocation::CpuToGpu,
+            None,
+        )
+    }
+
+    fn region(offset: u64, size: usize) -> vk::BufferCopy {
+        vk::BufferCopy {
+            src_offset: 0,
+            dst_offset: offset,
+            size: size as _,
+        }
+    }
+
     pub fn buffer(&self) -> &UnallocatedBuffer {
         &self.buffer
     }
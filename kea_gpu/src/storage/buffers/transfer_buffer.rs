@@ -43,7 +43,8 @@ impl TransferBuffer {
     }
 
     pub fn transfer_to_gpu(&mut self) -> Buffer {
-        let usage = self.usage | vk::BufferUsageFlags::TRANSFER_DST;
+        let usage =
+            self.usage | vk::BufferUsageFlags::TRANSFER_DST | vk::BufferUsageFlags::TRANSFER_SRC;
         let gpu_buffer = Buffer::new(
             self.device.clone(),
             self.cpu_buffer().size() as _,
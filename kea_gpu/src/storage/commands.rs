@@ -13,12 +13,24 @@ impl<'a> CommandBufferRecorder<'a> {
             size: source.size() as _,
         };
 
+        self.copy_buffer_region(source, destination, &copy);
+    }
+
+    pub fn copy_buffer_region(
+        &self,
+        source: &Buffer,
+        destination: &Buffer,
+        region: &vk::BufferCopy,
+    ) {
+        assert!(region.src_offset + region.size <= source.size() as u64);
+        assert!(region.dst_offset + region.size <= destination.size() as u64);
+
         unsafe {
             self.device().raw().cmd_copy_buffer(
                 self.buffer().raw(),
                 source.raw(),
                 destination.raw(),
-                slice::from_ref(&copy),
+                slice::from_ref(region),
             );
         }
     }
@@ -43,8 +55,8 @@ impl<'a> CommandBufferRecorder<'a> {
             .image(unsafe { image.raw() })
             .subresource_range(
                 vk::ImageSubresourceRange::default()
-                    .aspect_mask(vk::ImageAspectFlags::COLOR)
-                    .level_count(1)
+                    .aspect_mask(image.aspect_mask())
+                    .level_count(image.mip_levels())
                     .layer_count(1),
             );
 
@@ -69,6 +81,58 @@ impl<'a> CommandBufferRecorder<'a> {
         };
     }
 
+    pub fn copy_image_to_buffer(&self, image: &Image, mip_level: u32, buffer: &Buffer) {
+        let (width, height) = image.mip_level_size(mip_level);
+        let region = vk::BufferImageCopy::default()
+            .image_subresource(vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level,
+                base_array_layer: 0,
+                layer_count: 1,
+            })
+            .image_extent(vk::Extent3D {
+                width,
+                height,
+                depth: 1,
+            });
+
+        unsafe {
+            self.device().raw().cmd_copy_image_to_buffer(
+                self.buffer().raw(),
+                image.raw(),
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                buffer.raw(),
+                slice::from_ref(&region),
+            )
+        };
+    }
+
+    pub fn copy_buffer_to_image(&self, buffer: &Buffer, image: &Image) {
+        let (width, height) = image.size();
+        let region = vk::BufferImageCopy::default()
+            .image_subresource(vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level: 0,
+                base_array_layer: 0,
+                layer_count: 1,
+            })
+            .image_extent(vk::Extent3D {
+                width,
+                height,
+                depth: 1,
+            });
+
+        unsafe {
+            self.device().raw().cmd_copy_buffer_to_image(
+                self.buffer().raw(),
+                buffer.raw(),
+                image.raw(),
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                slice::from_ref(&region),
+            )
+        };
+    }
+
     pub fn blit_image(
         &self,
         from: &Image,
@@ -1,7 +1,12 @@
-use crate::{device::Device, storage::memory::Allocation};
+use super::Texel;
+use crate::{
+    commands::{CommandBuffer, CommandBufferRecorder},
+    device::Device,
+    storage::{buffers::Buffer, memory::Allocation},
+};
 use ash::vk;
 use gpu_allocator::MemoryLocation;
-use std::sync::Arc;
+use std::{mem, slice, sync::Arc};
 
 pub enum ImageOwnership {
     ExternallyOwned,
@@ -12,8 +17,9 @@ pub struct Image {
     device: Arc<Device>,
     raw: vk::Image,
     name: String,
-    _size: (u32, u32),
+    size: (u32, u32),
     format: vk::Format,
+    mip_levels: u32,
     location: MemoryLocation,
     ownership: ImageOwnership,
 }
@@ -26,6 +32,18 @@ impl Image {
         format: vk::Format,
         usage: vk::ImageUsageFlags,
         location: MemoryLocation,
+    ) -> Self {
+        Self::with_mip_levels(device, name, size, format, usage, location, 1)
+    }
+
+    fn with_mip_levels(
+        device: Arc<Device>,
+        name: String,
+        size: (u32, u32),
+        format: vk::Format,
+        usage: vk::ImageUsageFlags,
+        location: MemoryLocation,
+        mip_levels: u32,
     ) -> Self {
         let image_create_info = vk::ImageCreateInfo::default()
             .image_type(vk::ImageType::TYPE_2D)
@@ -35,7 +53,7 @@ impl Image {
                 height: size.1,
                 depth: 1,
             })
-            .mip_levels(1)
+            .mip_levels(mip_levels)
             .array_layers(1)
             .samples(vk::SampleCountFlags::TYPE_1)
             .tiling(vk::ImageTiling::OPTIMAL)
@@ -48,8 +66,9 @@ impl Image {
             device,
             raw,
             name,
-            _size: size,
+            size,
             format,
+            mip_levels,
             location,
             ownership: ImageOwnership::MemoryManaged(None),
         };
@@ -58,6 +77,192 @@ impl Image {
         image
     }
 
+    /// Create an image in GPU memory holding `pixels`, in row-major order,
+    /// ready to be sampled from. `usage` is added to the `SAMPLED` and
+    /// transfer usage this needs, and the image is left in
+    /// `SHADER_READ_ONLY_OPTIMAL` layout.
+    ///
+    /// If `mipmapped` is set, a full chain of mip levels is generated from the
+    /// pixels by repeatedly halving them with linear filtering. The format
+    /// must support linear filtering and blitting to and from it.
+    ///
+    /// This blocks until the upload is complete.
+    pub fn new_from_data<T: Texel>(
+        device: Arc<Device>,
+        name: String,
+        size: (u32, u32),
+        format: vk::Format,
+        usage: vk::ImageUsageFlags,
+        pixels: &[T],
+        mipmapped: bool,
+    ) -> Self {
+        if !T::supports_format(format) {
+            panic!(
+                "Can't create image {} with format {:?} from {}",
+                name,
+                format,
+                std::any::type_name::<T>()
+            );
+        }
+        assert_eq!(pixels.len(), (size.0 as usize) * (size.1 as usize));
+
+        if mipmapped {
+            let features = device
+                .physical_device()
+                .format_properties(format)
+                .optimal_tiling_features;
+            let needed = vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR
+                | vk::FormatFeatureFlags::BLIT_SRC
+                | vk::FormatFeatureFlags::BLIT_DST;
+            if !features.contains(needed) {
+                panic!(
+                    "Can't generate mip levels for image {} with format {:?}: {:?}",
+                    name, format, features
+                );
+            }
+        }
+
+        let mip_levels = if mipmapped { mip_level_count(size) } else { 1 };
+        let image = Image::with_mip_levels(
+            device.clone(),
+            name,
+            size,
+            format,
+            usage
+                | vk::ImageUsageFlags::SAMPLED
+                | vk::ImageUsageFlags::TRANSFER_SRC
+                | vk::ImageUsageFlags::TRANSFER_DST,
+            MemoryLocation::GpuOnly,
+            mip_levels,
+        );
+
+        let buffer = Buffer::new_from_data(
+            device.clone(),
+            pixels,
+            vk::BufferUsageFlags::TRANSFER_SRC,
+            format!("{} upload", image.name),
+            MemoryLocation::CpuToGpu,
+            None,
+        );
+
+        CommandBuffer::now(&device, format!("upload {}", image.name), |cmd| {
+            cmd.transition_image_layout(
+                &image,
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::AccessFlags2::NONE,
+                vk::AccessFlags2::TRANSFER_WRITE,
+                vk::PipelineStageFlags2::TOP_OF_PIPE,
+                vk::PipelineStageFlags2::TRANSFER,
+            );
+
+            cmd.copy_buffer_to_image(&buffer, &image);
+            image.record_generate_mipmaps(cmd);
+
+            cmd.transition_image_layout(
+                &image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                vk::AccessFlags2::TRANSFER_WRITE,
+                vk::AccessFlags2::SHADER_READ,
+                vk::PipelineStageFlags2::TRANSFER,
+                vk::PipelineStageFlags2::ALL_COMMANDS,
+            );
+        });
+
+        image
+    }
+
+    /// Fill each mip level by blitting the one above it, expecting every level
+    /// to be in `TRANSFER_DST_OPTIMAL` layout and leaving them there.
+    fn record_generate_mipmaps(&self, cmd: &CommandBufferRecorder) {
+        let (mut width, mut height) = (self.size.0 as i32, self.size.1 as i32);
+
+        for level in 1..self.mip_levels {
+            let (next_width, next_height) = ((width / 2).max(1), (height / 2).max(1));
+
+            self.record_level_barrier(
+                cmd,
+                level - 1,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                vk::AccessFlags2::TRANSFER_WRITE,
+                vk::AccessFlags2::TRANSFER_READ,
+            );
+
+            let subresource = |mip_level| vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level,
+                base_array_layer: 0,
+                layer_count: 1,
+            };
+            let blit = vk::ImageBlit::default()
+                .src_subresource(subresource(level - 1))
+                .src_offsets([
+                    vk::Offset3D::default(),
+                    vk::Offset3D {
+                        x: width,
+                        y: height,
+                        z: 1,
+                    },
+                ])
+                .dst_subresource(subresource(level))
+                .dst_offsets([
+                    vk::Offset3D::default(),
+                    vk::Offset3D {
+                        x: next_width,
+                        y: next_height,
+                        z: 1,
+                    },
+                ]);
+            cmd.blit_image(self, self, &blit, vk::Filter::LINEAR);
+
+            self.record_level_barrier(
+                cmd,
+                level - 1,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::AccessFlags2::TRANSFER_READ,
+                vk::AccessFlags2::TRANSFER_WRITE,
+            );
+
+            (width, height) = (next_width, next_height);
+        }
+    }
+
+    fn record_level_barrier(
+        &self,
+        cmd: &CommandBufferRecorder,
+        level: u32,
+        old_layout: vk::ImageLayout,
+        new_layout: vk::ImageLayout,
+        src_access_mask: vk::AccessFlags2,
+        dst_access_mask: vk::AccessFlags2,
+    ) {
+        let barrier = vk::ImageMemoryBarrier2::default()
+            .src_stage_mask(vk::PipelineStageFlags2::TRANSFER)
+            .src_access_mask(src_access_mask)
+            .dst_stage_mask(vk::PipelineStageFlags2::TRANSFER)
+            .dst_access_mask(dst_access_mask)
+            .old_layout(old_layout)
+            .new_layout(new_layout)
+            .image(self.raw)
+            .subresource_range(
+                vk::ImageSubresourceRange::default()
+                    .aspect_mask(vk::ImageAspectFlags::COLOR)
+                    .base_mip_level(level)
+                    .level_count(1)
+                    .layer_count(1),
+            );
+
+        cmd.pipeline_barrier(
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            slice::from_ref(&barrier),
+        );
+    }
+
     fn allocate(&mut self) {
         match &self.ownership {
             ImageOwnership::ExternallyOwned => {
@@ -105,8 +310,9 @@ impl Image {
             device,
             raw,
             name,
-            _size: size,
+            size,
             format,
+            mip_levels: 1,
             location,
             ownership,
         }
@@ -123,6 +329,108 @@ impl Image {
     pub fn format(&self) -> vk::Format {
         self.format
     }
+
+    /// The aspects of the image's format: depth for depth attachments, and
+    /// colour otherwise.
+    pub fn aspect_mask(&self) -> vk::ImageAspectFlags {
+        match self.format {
+            vk::Format::D16_UNORM | vk::Format::X8_D24_UNORM_PACK32 | vk::Format::D32_SFLOAT => {
+                vk::ImageAspectFlags::DEPTH
+            }
+            vk::Format::D16_UNORM_S8_UINT
+            | vk::Format::D24_UNORM_S8_UINT
+            | vk::Format::D32_SFLOAT_S8_UINT => {
+                vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
+            }
+            _ => vk::ImageAspectFlags::COLOR,
+        }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    pub fn mip_levels(&self) -> u32 {
+        self.mip_levels
+    }
+
+    /// The size of mip level `level`, halving the image's size for each level
+    /// down to a single pixel.
+    pub fn mip_level_size(&self, level: u32) -> (u32, u32) {
+        ((self.size.0 >> level).max(1), (self.size.1 >> level).max(1))
+    }
+
+    /// Copy the image's pixels back to the host, in row-major order. `layout`
+    /// is the layout the image is currently in; it is transitioned for the
+    /// copy and then returned to that layout. The image must have been created
+    /// with `TRANSFER_SRC` usage.
+    ///
+    /// This blocks until the copy is complete, and waits for any previously
+    /// submitted writes to the image.
+    pub fn read_to_host<T: Texel>(&self, layout: vk::ImageLayout) -> Vec<T> {
+        self.read_mip_level_to_host(layout, 0)
+    }
+
+    /// Copy mip level `level` back to the host, as `read_to_host` does for
+    /// the full size image.
+    pub fn read_mip_level_to_host<T: Texel>(&self, layout: vk::ImageLayout, level: u32) -> Vec<T> {
+        if !T::supports_format(self.format) {
+            panic!(
+                "Can't read image {} with format {:?} as {}",
+                self.name,
+                self.format,
+                std::any::type_name::<T>()
+            );
+        }
+
+        let (width, height) = self.mip_level_size(level);
+        let buffer = Buffer::new(
+            self.device.clone(),
+            (width as u64) * (height as u64) * mem::size_of::<T>() as u64,
+            vk::BufferUsageFlags::TRANSFER_DST,
+            format!("{} readback", self.name),
+            MemoryLocation::GpuToCpu,
+            None,
+        );
+
+        CommandBuffer::now(&self.device, format!("read back {}", self.name), |cmd| {
+            cmd.transition_image_layout(
+                self,
+                layout,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                vk::AccessFlags2::MEMORY_WRITE,
+                vk::AccessFlags2::TRANSFER_READ,
+                vk::PipelineStageFlags2::ALL_COMMANDS,
+                vk::PipelineStageFlags2::TRANSFER,
+            );
+
+            cmd.copy_image_to_buffer(self, level, &buffer);
+
+            cmd.transition_image_layout(
+                self,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                layout,
+                vk::AccessFlags2::TRANSFER_READ,
+                vk::AccessFlags2::MEMORY_READ | vk::AccessFlags2::MEMORY_WRITE,
+                vk::PipelineStageFlags2::TRANSFER,
+                vk::PipelineStageFlags2::ALL_COMMANDS,
+            );
+
+            let host_barrier = vk::MemoryBarrier2::default()
+                .src_stage_mask(vk::PipelineStageFlags2::TRANSFER)
+                .src_access_mask(vk::AccessFlags2::TRANSFER_WRITE)
+                .dst_stage_mask(vk::PipelineStageFlags2::HOST)
+                .dst_access_mask(vk::AccessFlags2::HOST_READ);
+            cmd.pipeline_barrier(
+                vk::DependencyFlags::empty(),
+                slice::from_ref(&host_barrier),
+                &[],
+                &[],
+            );
+        });
+
+        buffer.read()
+    }
 }
 
 impl Drop for Image {
@@ -137,3 +445,59 @@ impl Drop for Image {
         }
     }
 }
+
+/// The number of mip levels needed to halve an image of `size` down to a
+/// single pixel.
+pub fn mip_level_count(size: (u32, u32)) -> u32 {
+    32 - size.0.max(size.1).max(1).leading_zeros()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{debug::DebugFeature, Kea};
+
+    #[test]
+    #[ignore = "needs a Vulkan device"]
+    fn mipmaps_average_the_level_above() {
+        let kea = Kea::new_headless(vec![Box::new(DebugFeature::new())]);
+
+        // A checkerboard of black and grey texels, which averages to a grey
+        // half as bright at every level below the top.
+        let pixels: Vec<[u8; 4]> = (0..16)
+            .map(|index| {
+                let value = [0, 200][(index % 4 + index / 4) % 2];
+                [value, value, value, 255]
+            })
+            .collect();
+        let image = Image::new_from_data(
+            kea.device().clone(),
+            "checkerboard".to_string(),
+            (4, 4),
+            vk::Format::R8G8B8A8_UNORM,
+            vk::ImageUsageFlags::empty(),
+            &pixels,
+            true,
+        );
+        assert_eq!(image.mip_levels(), 3);
+
+        for level in 1..3 {
+            let texels: Vec<[u8; 4]> =
+                image.read_mip_level_to_host(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL, level);
+            let (width, height) = image.mip_level_size(level);
+            assert_eq!(texels.len(), (width * height) as usize);
+            for texel in texels {
+                assert!(texel[0].abs_diff(100) <= 1, "level {}: {:?}", level, texel);
+                assert_eq!(texel[3], 255);
+            }
+        }
+    }
+
+    #[test]
+    fn mip_levels_halve_down_to_one_pixel() {
+        assert_eq!(mip_level_count((1, 1)), 1);
+        assert_eq!(mip_level_count((4, 4)), 3);
+        assert_eq!(mip_level_count((5, 2)), 3);
+        assert_eq!(mip_level_count((1024, 1)), 11);
+    }
+}
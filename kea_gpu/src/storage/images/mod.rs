@@ -1,5 +1,9 @@
 mod image;
 mod image_view;
+mod sampler;
+mod texel;
 
-pub use image::{Image, ImageOwnership};
+pub use image::{mip_level_count, Image, ImageOwnership};
 pub use image_view::ImageView;
+pub use sampler::Sampler;
+pub use texel::Texel;
@@ -0,0 +1,56 @@
+use crate::device::Device;
+use ash::vk;
+use std::sync::Arc;
+
+pub struct Sampler {
+    device: Arc<Device>,
+    raw: vk::Sampler,
+}
+
+impl Sampler {
+    /// A sampler that filters trilinearly across mip levels, addressing
+    /// outside the image with `address_mode_u` horizontally and
+    /// `address_mode_v` vertically.
+    pub fn new(
+        device: Arc<Device>,
+        name: &str,
+        address_mode_u: vk::SamplerAddressMode,
+        address_mode_v: vk::SamplerAddressMode,
+    ) -> Sampler {
+        let create_info = vk::SamplerCreateInfo::default()
+            .mag_filter(vk::Filter::LINEAR)
+            .min_filter(vk::Filter::LINEAR)
+            .mipmap_mode(vk::SamplerMipmapMode::LINEAR)
+            .address_mode_u(address_mode_u)
+            .address_mode_v(address_mode_v)
+            .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .max_lod(vk::LOD_CLAMP_NONE);
+
+        let raw = unsafe { device.raw().create_sampler(&create_info, None) }.unwrap();
+        device.name_object(raw, name);
+
+        Sampler { device, raw }
+    }
+
+    /// The Vulkan sampler handle, for binding to descriptor sets.
+    ///
+    /// # Safety
+    ///
+    /// The handle is only valid for as long as this `Sampler` is, and is
+    /// destroyed with it, so it must not be destroyed or used after that.
+    pub unsafe fn raw(&self) -> vk::Sampler {
+        self.raw
+    }
+
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.raw().destroy_sampler(self.raw, None);
+        }
+    }
+}
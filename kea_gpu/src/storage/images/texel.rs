@@ -0,0 +1,27 @@
+use ash::vk;
+
+/// Host-side representation of a single texel, used when copying image data
+/// between the GPU and the host.
+pub trait Texel: Copy {
+    fn supports_format(format: vk::Format) -> bool;
+}
+
+impl Texel for [f32; 4] {
+    fn supports_format(format: vk::Format) -> bool {
+        format == vk::Format::R32G32B32A32_SFLOAT
+    }
+}
+
+/// Channels are in the order they're stored in, so a `B8G8R8A8` image reads
+/// back as `[b, g, r, a]`.
+impl Texel for [u8; 4] {
+    fn supports_format(format: vk::Format) -> bool {
+        matches!(
+            format,
+            vk::Format::B8G8R8A8_UNORM
+                | vk::Format::B8G8R8A8_SRGB
+                | vk::Format::R8G8B8A8_UNORM
+                | vk::Format::R8G8B8A8_SRGB
+        )
+    }
+}
@@ -56,6 +56,15 @@ impl Allocation {
         self.allocation.size()
     }
 
+    /// The allocation's memory, as mapped into the host's address space.
+    ///
+    /// # Safety
+    ///
+    /// The GPU must not be writing to the memory while the slice is read.
+    pub unsafe fn mapped_slice(&self) -> &[u8] {
+        self.allocation.mapped_slice().unwrap()
+    }
+
     pub unsafe fn mapped_slice_mut(&mut self) -> &mut [u8] {
         self.allocation.mapped_slice_mut().unwrap()
     }
@@ -5,16 +5,44 @@ pub struct ShaderStages {
     pub raygen: bool,
     pub intersection: bool,
     pub closest_hit: bool,
+    pub miss: bool,
+    pub compute: bool,
+    pub vertex: bool,
+    pub fragment: bool,
 }
 
 #[derive(Clone)]
 pub struct Slot {
     pub slot_type: SlotType,
     pub stages: ShaderStages,
+    /// For slots holding a runtime-sized array of resources, the most the
+    /// array can hold.
+    pub array_capacity: Option<u32>,
 }
 
 impl Slot {
     pub const fn new(slot_type: SlotType, stages: ShaderStages) -> Self {
-        Self { slot_type, stages }
+        Self {
+            slot_type,
+            stages,
+            array_capacity: None,
+        }
+    }
+
+    /// A slot holding a runtime-sized array of up to `capacity` resources,
+    /// which shaders index into, such as a texture per material. Elements
+    /// needn't all be bound, and can be bound while the shaders are running
+    /// as long as those shaders don't use them.
+    pub const fn array(slot_type: SlotType, stages: ShaderStages, capacity: u32) -> Self {
+        Self {
+            slot_type,
+            stages,
+            array_capacity: Some(capacity),
+        }
+    }
+
+    /// The number of descriptors the slot takes up.
+    pub fn descriptor_count(&self) -> u32 {
+        self.array_capacity.unwrap_or(1)
     }
 }
@@ -3,6 +3,13 @@ use core::any::TypeId;
 #[derive(Clone)]
 pub enum SlotType {
     AccelerationStructure,
+    /// A storage image. Shaders declare these with a format, and the image
+    /// views bound to the slot must have the matching Vulkan format.
     Image,
     Buffer(TypeId),
+    /// An image that's read through a separately bound sampler.
+    SampledImage,
+    Sampler,
+    /// A sampled image together with the sampler to read it with.
+    CombinedImageSampler,
 }
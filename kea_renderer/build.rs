@@ -22,11 +22,16 @@ fn main() -> anyhow::Result<()> {
         Capability::RayTracingKHR,
         Capability::Int64,
         Capability::RayTracingPositionFetchKHR,
+        Capability::RuntimeDescriptorArray,
+        Capability::ShaderNonUniform,
+        Capability::StorageBufferArrayNonUniformIndexing,
+        Capability::SampledImageArrayNonUniformIndexing,
     ];
     builder.extensions = vec![
         "SPV_KHR_ray_tracing".into(),
         "SPV_KHR_non_semantic_info".into(),
         "SPV_KHR_ray_tracing_position_fetch".into(),
+        "SPV_EXT_descriptor_indexing".into(),
     ];
     builder.spirv_metadata = SpirvMetadata::Full;
 
@@ -0,0 +1,127 @@
+use glam::{vec3, Vec3, Vec3A};
+use kea_renderer_shaders::cameras::CameraParameters;
+use std::collections::HashSet;
+use winit::{
+    dpi::PhysicalPosition,
+    event::{ElementState, MouseButton, WindowEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+/// Distance moved per second while a movement key is held.
+const MOVE_SPEED: f32 = 1.0;
+/// Multiplier applied to `MOVE_SPEED` while shift is held.
+const FAST_MULTIPLIER: f32 = 4.0;
+/// Radians turned per pixel the mouse moves while looking around.
+const LOOK_SPEED: f32 = 0.003;
+/// Keep the pitch just short of straight up or down, where yaw is undefined.
+const MAX_PITCH: f32 = 1.55;
+
+/// A free-flying camera controlled with WASD to move, Q/E to descend and
+/// ascend, and dragging with the left mouse button to look around.
+pub struct FlyCamera {
+    position: Vec3,
+    yaw: f32,
+    pitch: f32,
+    vertical_field_of_view_radians: f32,
+    held_keys: HashSet<KeyCode>,
+    looking: bool,
+    cursor: Option<PhysicalPosition<f64>>,
+}
+
+impl FlyCamera {
+    pub fn new(parameters: CameraParameters) -> Self {
+        let position = Vec3::from(parameters.position);
+        let forward = (Vec3::from(parameters.target_position) - position).normalize();
+
+        Self {
+            position,
+            yaw: forward.x.atan2(-forward.z),
+            pitch: forward.y.asin(),
+            vertical_field_of_view_radians: parameters.vertical_field_of_view_radians,
+            held_keys: HashSet::new(),
+            looking: false,
+            cursor: None,
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => {
+                if let PhysicalKey::Code(code) = event.physical_key {
+                    match event.state {
+                        ElementState::Pressed => self.held_keys.insert(code),
+                        ElementState::Released => self.held_keys.remove(&code),
+                    };
+                }
+            }
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Left,
+                ..
+            } => {
+                self.looking = *state == ElementState::Pressed;
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                if let (true, Some(last)) = (self.looking, self.cursor) {
+                    self.yaw += (position.x - last.x) as f32 * LOOK_SPEED;
+                    self.pitch = (self.pitch - (position.y - last.y) as f32 * LOOK_SPEED)
+                        .clamp(-MAX_PITCH, MAX_PITCH);
+                }
+                self.cursor = Some(*position);
+            }
+            WindowEvent::CursorLeft { .. } => self.cursor = None,
+            WindowEvent::Focused(false) => {
+                self.held_keys.clear();
+                self.looking = false;
+            }
+            _ => {}
+        }
+    }
+
+    /// Move the camera according to the keys currently held, over a frame
+    /// lasting `delta_seconds`.
+    pub fn update(&mut self, delta_seconds: f32) {
+        let forward = self.forward();
+        let right = forward.cross(Vec3::Y).normalize();
+
+        let mut direction = Vec3::ZERO;
+        for (key, key_direction) in [
+            (KeyCode::KeyW, forward),
+            (KeyCode::KeyS, -forward),
+            (KeyCode::KeyD, right),
+            (KeyCode::KeyA, -right),
+            (KeyCode::KeyE, Vec3::Y),
+            (KeyCode::KeyQ, -Vec3::Y),
+        ] {
+            if self.held_keys.contains(&key) {
+                direction += key_direction;
+            }
+        }
+
+        let mut speed = MOVE_SPEED;
+        if self.held_keys.contains(&KeyCode::ShiftLeft)
+            || self.held_keys.contains(&KeyCode::ShiftRight)
+        {
+            speed *= FAST_MULTIPLIER;
+        }
+
+        self.position += direction.normalize_or_zero() * speed * delta_seconds;
+    }
+
+    pub fn parameters(&self) -> CameraParameters {
+        CameraParameters {
+            position: Vec3A::from(self.position),
+            target_position: Vec3A::from(self.position + self.forward()),
+            vertical_field_of_view_radians: self.vertical_field_of_view_radians,
+            ..Default::default()
+        }
+    }
+
+    fn forward(&self) -> Vec3 {
+        vec3(
+            self.yaw.sin() * self.pitch.cos(),
+            self.pitch.sin(),
+            -self.yaw.cos() * self.pitch.cos(),
+        )
+    }
+}
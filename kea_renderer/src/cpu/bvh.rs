@@ -0,0 +1,160 @@
+use glam::Vec3;
+use kea_gpu_shaderlib::{Aabb, Ray};
+
+/// Primitives per leaf before a node is split.
+const MAX_LEAF_SIZE: usize = 4;
+
+/// A bounding volume hierarchy over primitives identified by their index. The
+/// primitives themselves live with the caller, which tests rays against them
+/// as the hierarchy is traversed.
+pub struct Bvh {
+    nodes: Vec<Node>,
+    /// Primitive indices, ordered so each leaf covers a contiguous range.
+    indices: Vec<usize>,
+}
+
+struct Node {
+    aabb: Aabb,
+    contents: NodeContents,
+}
+
+enum NodeContents {
+    Leaf {
+        start: usize,
+        count: usize,
+    },
+    /// The left child immediately follows its parent.
+    Interior {
+        right: usize,
+    },
+}
+
+impl Bvh {
+    pub fn new(aabbs: &[Aabb]) -> Self {
+        let mut bvh = Self {
+            nodes: Vec::with_capacity(aabbs.len() * 2),
+            indices: (0..aabbs.len()).collect(),
+        };
+
+        if !aabbs.is_empty() {
+            bvh.build(aabbs, 0, aabbs.len());
+        }
+
+        bvh
+    }
+
+    /// Build the node covering `indices[start..end]`, returning its index.
+    fn build(&mut self, aabbs: &[Aabb], start: usize, end: usize) -> usize {
+        let node_index = self.nodes.len();
+        let primitives = &mut self.indices[start..end];
+        let aabb = primitives
+            .iter()
+            .map(|&index| aabbs[index])
+            .reduce(union)
+            .unwrap();
+
+        if primitives.len() <= MAX_LEAF_SIZE {
+            self.nodes.push(Node {
+                aabb,
+                contents: NodeContents::Leaf {
+                    start,
+                    count: primitives.len(),
+                },
+            });
+            return node_index;
+        }
+
+        // Split at the median centroid along the axis the centroids are most
+        // spread out on.
+        let (centroid_min, centroid_max) = primitives.iter().fold(
+            (Vec3::INFINITY, Vec3::NEG_INFINITY),
+            |(min, max), &index| {
+                let centroid = centroid(&aabbs[index]);
+                (min.min(centroid), max.max(centroid))
+            },
+        );
+        let axis = (centroid_max - centroid_min).max_position();
+        let middle = primitives.len() / 2;
+        primitives.select_nth_unstable_by(middle, |&a, &b| {
+            centroid(&aabbs[a])[axis].total_cmp(&centroid(&aabbs[b])[axis])
+        });
+
+        self.nodes.push(Node {
+            aabb,
+            contents: NodeContents::Interior { right: 0 },
+        });
+        self.build(aabbs, start, start + middle);
+        let right = self.build(aabbs, start + middle, end);
+        self.nodes[node_index].contents = NodeContents::Interior { right };
+
+        node_index
+    }
+
+    /// Find the closest primitive hit along `ray` between `t_min` and `t_max`.
+    /// `intersect` tests a primitive, returning the distance to a hit that's
+    /// closer than the `t_max` it's given.
+    pub fn closest_hit<H>(
+        &self,
+        ray: Ray,
+        t_min: f32,
+        t_max: f32,
+        mut intersect: impl FnMut(usize, f32) -> Option<(f32, H)>,
+    ) -> Option<(f32, H)> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let inverse_direction = ray.direction.recip();
+        let mut closest: Option<(f32, H)> = None;
+        let mut stack = vec![0];
+
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            let t_max = closest.as_ref().map_or(t_max, |(distance, _)| *distance);
+            if !hits_aabb(&node.aabb, ray, inverse_direction, t_min, t_max) {
+                continue;
+            }
+
+            match node.contents {
+                NodeContents::Leaf { start, count } => {
+                    for &index in &self.indices[start..start + count] {
+                        let t_max = closest.as_ref().map_or(t_max, |(distance, _)| *distance);
+                        if let Some((distance, hit)) = intersect(index, t_max) {
+                            if distance >= t_min && distance <= t_max {
+                                closest = Some((distance, hit));
+                            }
+                        }
+                    }
+                }
+                NodeContents::Interior { right } => {
+                    stack.push(right);
+                    stack.push(node_index + 1);
+                }
+            }
+        }
+
+        closest
+    }
+}
+
+fn union(a: Aabb, b: Aabb) -> Aabb {
+    Aabb {
+        min: a.min.min(b.min),
+        max: a.max.max(b.max),
+    }
+}
+
+fn centroid(aabb: &Aabb) -> Vec3 {
+    (aabb.min + aabb.max) * 0.5
+}
+
+/// Slab test of a ray against a box.
+fn hits_aabb(aabb: &Aabb, ray: Ray, inverse_direction: Vec3, t_min: f32, t_max: f32) -> bool {
+    let t0 = (aabb.min - ray.origin) * inverse_direction;
+    let t1 = (aabb.max - ray.origin) * inverse_direction;
+
+    let near = t0.min(t1).max_element().max(t_min);
+    let far = t0.max(t1).min_element().min(t_max);
+
+    near <= far
+}
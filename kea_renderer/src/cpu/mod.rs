@@ -0,0 +1,336 @@
+//! A reference path tracer that runs on the CPU. It shares the sampling code
+//! with the ray generation shader, tracing rays against a BVH built on the
+//! host rather than a Vulkan acceleration structure, so it can check the GPU
+//! output on machines without ray tracing support.
+
+mod bvh;
+
+use crate::scenes::{EnvironmentImage, Scene, TextureImage};
+use bvh::Bvh;
+use glam::{UVec2, Vec2, Vec3, Vec3A, Vec4};
+use kea_gpu_shaderlib::{Aabb, Ray};
+use kea_renderer_shaders::{
+    cameras::{Camera, CameraParameters},
+    environments::Environment,
+    lights::Light,
+    materials::{Material, Textures},
+    path_tracer::{
+        sampling::{accumulate, sample_iteration, tone_map, Lighting, WHITE_POINT},
+        Tracer,
+    },
+    payload::RayPayload,
+    primitives,
+    sdfs::{Sdf, SdfNode},
+    spheres::Sphere,
+    triangles::{shade_triangle, Mesh, Vertex},
+};
+use std::{sync::Arc, thread};
+
+enum Primitive {
+    /// A triangle with its vertices in world space.
+    Triangle {
+        points: [Vec3; 3],
+        vertices: [Vertex; 3],
+        mesh: Mesh,
+    },
+    Sphere(Sphere),
+    /// One of the analytic primitives with an intersection shader of its own.
+    Analytic(Box<dyn primitives::Primitive + Send + Sync>),
+    /// A signed distance function, with the nodes of every SDF in the scene.
+    Sdf {
+        sdf: Sdf,
+        nodes: Arc<[SdfNode]>,
+    },
+}
+
+impl Primitive {
+    fn aabb(&self) -> Aabb {
+        match self {
+            Primitive::Triangle { points, .. } => Aabb {
+                min: points[0].min(points[1]).min(points[2]),
+                max: points[0].max(points[1]).max(points[2]),
+            },
+            Primitive::Sphere(sphere) => sphere.aabb(),
+            Primitive::Analytic(primitive) => primitive.aabb(),
+            Primitive::Sdf { sdf, .. } => sdf.aabb(),
+        }
+    }
+
+    /// The distance along the ray to the primitive, and the barycentric
+    /// coordinates of the hit on triangles. Analytic primitives and SDFs
+    /// report the closest hit beyond `t_min`, as their intersection shaders
+    /// do.
+    fn intersect(&self, ray: Ray, t_min: f32) -> Option<(f32, Vec2)> {
+        match self {
+            Primitive::Triangle { points, .. } => intersect_triangle(points, ray),
+            Primitive::Sphere(sphere) => Some((sphere.intersect_ray(ray)?, Vec2::ZERO)),
+            Primitive::Analytic(primitive) => {
+                Some((primitive.intersect_ray(ray, t_min)?, Vec2::ZERO))
+            }
+            Primitive::Sdf { sdf, nodes } => {
+                Some((sdf.intersect_ray(nodes, ray, t_min)?, Vec2::ZERO))
+            }
+        }
+    }
+
+    /// The payload the hit shaders would produce for a hit from `intersect`.
+    fn shade(
+        &self,
+        ray: Ray,
+        distance: f32,
+        barycentrics: Vec2,
+        textures: &CpuTextures,
+    ) -> RayPayload {
+        match self {
+            Primitive::Triangle {
+                points,
+                vertices,
+                mesh,
+            } => shade_triangle(mesh, *points, *vertices, barycentrics, distance, textures),
+            Primitive::Sphere(sphere) => {
+                let normal = sphere.normal(ray);
+
+                RayPayload {
+                    hit: Some(distance),
+                    normal,
+                    shading_normal: normal,
+                    material: sphere.material(),
+                    area: sphere.area(),
+                }
+            }
+            Primitive::Analytic(primitive) => primitives::shade(primitive.as_ref(), ray, distance),
+            Primitive::Sdf { sdf, nodes } => sdf.shade(nodes, ray, distance),
+        }
+    }
+}
+
+fn analytic_primitive<P: primitives::Primitive + Send + Sync + 'static>(primitive: P) -> Primitive {
+    Primitive::Analytic(Box::new(primitive))
+}
+
+/// Möller–Trumbore ray-triangle intersection, returning the distance along the
+/// ray and the barycentric coordinates of the second and third vertices.
+/// Triangles are two-sided.
+fn intersect_triangle(points: &[Vec3; 3], ray: Ray) -> Option<(f32, Vec2)> {
+    let edge1 = points[1] - points[0];
+    let edge2 = points[2] - points[0];
+    let p = ray.direction.cross(edge2);
+    let determinant = edge1.dot(p);
+    if determinant.abs() < f32::EPSILON {
+        return None;
+    }
+
+    let inverse_determinant = 1.0 / determinant;
+    let s = ray.origin - points[0];
+    let u = s.dot(p) * inverse_determinant;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let q = s.cross(edge1);
+    let v = ray.direction.dot(q) * inverse_determinant;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    Some((edge2.dot(q) * inverse_determinant, Vec2::new(u, v)))
+}
+
+/// The scene's textures, sampled on the host.
+struct CpuTextures(Vec<TextureImage>);
+
+impl Textures for CpuTextures {
+    fn sample(&self, texture: u32, uv: Vec2) -> Vec4 {
+        self.0[texture as usize].sample(uv)
+    }
+}
+
+/// The scene flattened into world space primitives, with a BVH over them.
+struct CpuScene {
+    primitives: Vec<Primitive>,
+    bvh: Bvh,
+    lights: Vec<Light>,
+    textures: CpuTextures,
+    environment: Environment,
+    environment_image: EnvironmentImage,
+    environment_distribution: Vec<f32>,
+}
+
+impl CpuScene {
+    fn new(scene: &mut Scene) -> Self {
+        let lights = scene.lights();
+        let mut primitives: Vec<Primitive> =
+            scene.spheres().into_iter().map(Primitive::Sphere).collect();
+
+        let analytic = scene.primitives();
+        primitives.extend(analytic.boxes.into_iter().map(analytic_primitive));
+        primitives.extend(analytic.planes.into_iter().map(analytic_primitive));
+        primitives.extend(analytic.discs.into_iter().map(analytic_primitive));
+        primitives.extend(analytic.cylinders.into_iter().map(analytic_primitive));
+        primitives.extend(analytic.cones.into_iter().map(analytic_primitive));
+        primitives.extend(analytic.tori.into_iter().map(analytic_primitive));
+
+        let (sdfs, nodes) = scene.sdfs();
+        let nodes: Arc<[SdfNode]> = nodes.into();
+        primitives.extend(sdfs.into_iter().map(|sdf| Primitive::Sdf {
+            sdf,
+            nodes: nodes.clone(),
+        }));
+
+        for mesh in scene.meshes() {
+            // Normals are transformed by the inverse transpose, as in the hit
+            // shader.
+            let normal_matrix = mesh.transform.matrix3.inverse().transpose();
+            let shader_mesh = Mesh {
+                material: mesh.material,
+                textures: mesh.textures,
+                asset: mesh.mesh.0,
+            };
+
+            primitives.extend(mesh.indices.iter().map(|triangle| Primitive::Triangle {
+                points: triangle.map(|index| {
+                    mesh.transform
+                        .transform_point3a(mesh.vertices[index as usize])
+                        .into()
+                }),
+                vertices: triangle.map(|index| {
+                    let vertex = mesh.attributes[index as usize];
+                    Vertex {
+                        normal: normal_matrix * vertex.normal,
+                        tangent: mesh.transform.matrix3 * vertex.tangent,
+                        ..vertex
+                    }
+                }),
+                mesh: shader_mesh,
+            }));
+        }
+
+        let aabbs: Vec<Aabb> = primitives.iter().map(Primitive::aabb).collect();
+        let bvh = Bvh::new(&aabbs);
+
+        Self {
+            primitives,
+            bvh,
+            lights,
+            textures: CpuTextures(scene.textures().to_vec()),
+            environment: scene.environment().parameters(),
+            environment_image: scene.environment().image().clone(),
+            environment_distribution: scene.environment().distribution(),
+        }
+    }
+
+    /// Fill in the payload as the miss shader would.
+    fn miss(&self, ray: Ray, payload: &mut RayPayload) {
+        payload.hit = None;
+        payload.material.emit = self
+            .environment
+            .radiance(ray.direction, &self.environment_image)
+            .into();
+    }
+}
+
+impl Tracer for CpuScene {
+    fn trace_ray(&self, ray: Ray, t_min: f32, t_max: f32, payload: &mut RayPayload) {
+        let hit = self.bvh.closest_hit(ray, t_min, t_max, |index, _| {
+            let (distance, barycentrics) = self.primitives[index].intersect(ray, t_min)?;
+            Some((distance, (index, barycentrics)))
+        });
+
+        match hit {
+            Some((distance, (index, barycentrics))) => {
+                *payload = self.primitives[index].shade(ray, distance, barycentrics, &self.textures)
+            }
+            None => self.miss(ray, payload),
+        }
+    }
+
+    fn occluded(&self, ray: Ray, t_min: f32, t_max: f32, payload: &mut RayPayload) -> bool {
+        let hit = self.bvh.closest_hit(ray, t_min, t_max, |index, _| {
+            self.primitives[index].intersect(ray, t_min)
+        });
+
+        match hit {
+            Some((distance, _)) => payload.hit = Some(distance),
+            None => self.miss(ray, payload),
+        }
+
+        payload.hit.is_some()
+    }
+}
+
+pub struct CpuPathTracer {
+    scene: CpuScene,
+    camera: CameraParameters,
+}
+
+impl CpuPathTracer {
+    pub fn new(scene: &mut Scene) -> Self {
+        Self {
+            camera: scene.camera(),
+            scene: CpuScene::new(scene),
+        }
+    }
+
+    /// Render `iterations` iterations, returning the tone-mapped rgba32f
+    /// pixels in row-major order, as `PathTracer::read_output` does.
+    pub fn render(&self, size: (u32, u32), iterations: u64) -> Vec<[f32; 4]> {
+        let camera = Camera::new(CameraParameters {
+            aspect_ratio: size.0 as f32 / size.1 as f32,
+            ..self.camera
+        });
+        let size = UVec2::new(size.0, size.1);
+
+        let mut pixels = vec![[0.0; 4]; (size.x * size.y) as usize];
+        let threads = thread::available_parallelism().map_or(1, |count| count.get());
+        let rows_per_thread = size.y.div_ceil(threads as u32) as usize;
+
+        thread::scope(|scope| {
+            let chunks = pixels.chunks_mut(rows_per_thread * size.x as usize);
+            for (chunk_index, chunk) in chunks.enumerate() {
+                let camera = &camera;
+                scope.spawn(move || {
+                    let first_row = (chunk_index * rows_per_thread) as u32;
+                    for (i, output) in chunk.iter_mut().enumerate() {
+                        let pixel = UVec2::new(i as u32 % size.x, first_row + i as u32 / size.x);
+                        let light = self.render_pixel(camera, pixel, size, iterations);
+                        *output = tone_map(light, WHITE_POINT).extend(1.0).to_array();
+                    }
+                });
+            }
+        });
+
+        pixels
+    }
+
+    /// The accumulated light arriving at a pixel after `iterations`
+    /// iterations.
+    fn render_pixel(&self, camera: &Camera, pixel: UVec2, size: UVec2, iterations: u64) -> Vec3 {
+        let mut payload = RayPayload {
+            hit: None,
+            normal: Vec3::ZERO,
+            shading_normal: Vec3::ZERO,
+            material: Material::lambertian(Vec3A::ZERO),
+            area: 0.0,
+        };
+
+        let lighting = Lighting {
+            lights: &self.scene.lights,
+            environment: &self.scene.environment,
+            environment_distribution: &self.scene.environment_distribution,
+            textures: &self.scene.textures,
+        };
+
+        (0..iterations).fold(Vec3::ZERO, |light, iteration| {
+            let iteration_light = sample_iteration(
+                &self.scene,
+                &lighting,
+                &mut payload,
+                camera,
+                pixel,
+                size,
+                iteration,
+            );
+            accumulate(light, iteration_light, iteration)
+        })
+    }
+}
@@ -0,0 +1,192 @@
+//! Golden image regression tests. Each example scene is rendered at a small
+//! size and a fixed number of iterations, then compared against a reference
+//! image checked in under `tests/golden`. Sampling is seeded from the pixel
+//! and iteration alone, so the same scene always renders the same image.
+//!
+//! The references are rendered by this same CPU renderer, so the tests only
+//! catch changes to its output, not whether it was right in the first place.
+//! Run with `KEA_UPDATE_GOLDEN=1` to write new reference images instead of
+//! comparing against them, after checking a change is intended. When a
+//! comparison fails, the render and a diff image are written to
+//! `tests/golden/failures`.
+
+use crate::{cpu::CpuPathTracer, features, output, path_tracer::PathTracer, scenes, scenes::Scene};
+use image::{Rgba, RgbaImage};
+use kea_gpu::Kea;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+const SIZE: (u32, u32) = (64, 48);
+const ITERATIONS: u64 = 16;
+/// Images are compared after averaging blocks of this many pixels square, so
+/// the comparison is of the picture rather than its exact noise.
+const BLOCK_SIZE: u32 = 4;
+/// Largest RMSE allowed between the block averages of a render and its
+/// reference, with channels in 0-1. The CPU renderer made the references, so
+/// it should match them almost exactly; the GPU takes different floating
+/// point paths through the same code, so its noise differs.
+const CPU_TOLERANCE: f32 = 0.01;
+const GPU_TOLERANCE: f32 = 0.05;
+
+fn golden_directory() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+/// The example scenes, with the name of their reference image: the built-in
+/// Cornell box, and every scene file in `kea_renderer/scenes`.
+fn example_scenes() -> Vec<(String, Scene)> {
+    let mut examples = vec![(
+        "builtin_cornell_box".to_string(),
+        scenes::examples::cornell_box(),
+    )];
+
+    let mut paths: Vec<PathBuf> =
+        fs::read_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes"))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| scenes::is_scene_file(path))
+            .collect();
+    paths.sort();
+
+    for path in paths {
+        let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+        let scene = scenes::load(&path)
+            .unwrap_or_else(|err| panic!("Couldn't load scene {}: {}", path.display(), err));
+        examples.push((name, scene));
+    }
+
+    examples
+}
+
+/// Compare a render against its reference image, writing the render and a
+/// diff image to the failures directory if they don't match.
+fn compare_to_reference(name: &str, pixels: &[[f32; 4]], tolerance: f32) -> Result<(), String> {
+    let actual = output::to_rgba8(SIZE, pixels);
+    let reference_path = golden_directory().join(name).with_extension("png");
+    let reference = image::open(&reference_path)
+        .map_err(|err| {
+            format!(
+                "{}: couldn't open {}: {}",
+                name,
+                reference_path.display(),
+                err
+            )
+        })?
+        .into_rgba8();
+
+    if reference.dimensions() != actual.dimensions() {
+        return Err(format!(
+            "{}: reference is {:?} but the render is {:?}",
+            name,
+            reference.dimensions(),
+            actual.dimensions()
+        ));
+    }
+
+    let error = block_rmse(&actual, &reference);
+    if error <= tolerance {
+        return Ok(());
+    }
+
+    let failures = golden_directory().join("failures");
+    fs::create_dir_all(&failures).unwrap();
+    let actual_path = failures.join(format!("{}.actual.png", name));
+    let diff_path = failures.join(format!("{}.diff.png", name));
+    actual.save(&actual_path).unwrap();
+    diff_image(&actual, &reference).save(&diff_path).unwrap();
+
+    Err(format!(
+        "{}: RMSE {:.4} exceeds {:.4}, wrote {} and {}",
+        name,
+        error,
+        tolerance,
+        actual_path.display(),
+        diff_path.display()
+    ))
+}
+
+/// Root mean squared error between the colour channels of two images,
+/// averaged over blocks of `BLOCK_SIZE` pixels.
+fn block_rmse(a: &RgbaImage, b: &RgbaImage) -> f32 {
+    let (width, height) = a.dimensions();
+    let mut squared_error = 0.0;
+    let mut count = 0;
+
+    for block_y in (0..height).step_by(BLOCK_SIZE as usize) {
+        for block_x in (0..width).step_by(BLOCK_SIZE as usize) {
+            let mut difference = [0.0_f32; 3];
+            let mut pixels = 0;
+            for y in block_y..(block_y + BLOCK_SIZE).min(height) {
+                for x in block_x..(block_x + BLOCK_SIZE).min(width) {
+                    let (a, b) = (a.get_pixel(x, y), b.get_pixel(x, y));
+                    for channel in 0..3 {
+                        difference[channel] += (a[channel] as f32 - b[channel] as f32) / 255.0;
+                    }
+                    pixels += 1;
+                }
+            }
+
+            for channel_difference in difference {
+                squared_error += (channel_difference / pixels as f32).powi(2);
+                count += 1;
+            }
+        }
+    }
+
+    (squared_error / count as f32).sqrt()
+}
+
+/// Per-pixel absolute difference, scaled up so small errors are visible.
+fn diff_image(a: &RgbaImage, b: &RgbaImage) -> RgbaImage {
+    RgbaImage::from_fn(a.width(), a.height(), |x, y| {
+        let (a, b) = (a.get_pixel(x, y), b.get_pixel(x, y));
+        let channel = |i: usize| (a[i].abs_diff(b[i]) as u32 * 4).min(255) as u8;
+        Rgba([channel(0), channel(1), channel(2), 255])
+    })
+}
+
+fn check_all(failures: Vec<String>) {
+    if !failures.is_empty() {
+        panic!("Golden image mismatches:\n{}", failures.join("\n"));
+    }
+}
+
+#[test]
+fn cpu_matches_golden_images() {
+    let update = env::var_os("KEA_UPDATE_GOLDEN").is_some();
+
+    let mut failures = vec![];
+    for (name, mut scene) in example_scenes() {
+        let pixels = CpuPathTracer::new(&mut scene).render(SIZE, ITERATIONS);
+
+        if update {
+            let path = golden_directory().join(&name).with_extension("png");
+            fs::create_dir_all(golden_directory()).unwrap();
+            output::save_image(&path, SIZE, &pixels).unwrap();
+        } else if let Err(failure) = compare_to_reference(&name, &pixels, CPU_TOLERANCE) {
+            failures.push(failure);
+        }
+    }
+
+    check_all(failures);
+}
+
+#[test]
+#[ignore = "needs a Vulkan device with ray tracing support"]
+fn gpu_matches_golden_images() {
+    let mut failures = vec![];
+    for (name, scene) in example_scenes() {
+        let kea = Kea::new_headless(features(false));
+        let path_tracer = PathTracer::new(kea, scene, SIZE);
+        path_tracer.render(ITERATIONS);
+
+        if let Err(failure) = compare_to_reference(&name, &path_tracer.read_output(), GPU_TOLERANCE)
+        {
+            failures.push(failure);
+        }
+    }
+
+    check_all(failures);
+}
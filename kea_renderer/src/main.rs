@@ -1,27 +1,48 @@
+use camera::FlyCamera;
+use cpu::CpuPathTracer;
 use env_logger::Env;
 use kea_gpu::debug::DebugFeature;
+use kea_gpu::features::Feature;
 use kea_gpu::presentation::Window;
 use kea_gpu::ray_tracing::RayTracingFeature;
 use kea_gpu::Kea;
+use options::Options;
 use path_tracer::PathTracer;
+use scenes::Scene;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
 use std::sync::Arc;
+use std::time::Instant;
 use winit::application::ApplicationHandler;
 use winit::dpi::PhysicalSize;
-use winit::event::WindowEvent;
+use winit::event::{ElementState, WindowEvent};
 use winit::event_loop::{ActiveEventLoop, EventLoop};
+use winit::keyboard::{KeyCode, PhysicalKey};
 use winit::window::{Fullscreen, WindowId};
 
+mod camera;
+mod cpu;
+#[cfg(test)]
+mod golden_tests;
+mod options;
+mod output;
 mod path_tracer;
+mod preview;
 mod scenes;
 
 struct InitConfig {
     size: (u32, u32),
     fullscreen: bool,
+    validation: bool,
+    scene: Option<PathBuf>,
 }
 
 struct State {
     window: Arc<winit::window::Window>,
     path_tracer: PathTracer,
+    camera: FlyCamera,
+    last_frame: Instant,
 }
 
 struct App {
@@ -38,7 +59,6 @@ impl ApplicationHandler for App {
         let attributes = winit::window::Window::default_attributes()
             .with_title("kea")
             .with_inner_size(PhysicalSize::new(self.init.size.0, self.init.size.1))
-            .with_resizable(false)
             .with_fullscreen(if self.init.fullscreen {
                 Some(Fullscreen::Borderless(None))
             } else {
@@ -47,18 +67,18 @@ impl ApplicationHandler for App {
 
         let window = Arc::new(event_loop.create_window(attributes).unwrap());
         let kea_window = Window::new(window.clone());
-        let kea = Kea::new(
-            &kea_window,
-            self.init.size,
-            vec![
-                Box::new(RayTracingFeature::new()),
-                Box::new(DebugFeature::new()),
-            ],
-        );
-        let path_tracer = PathTracer::new(kea);
+        let kea = Kea::new(&kea_window, self.init.size, features(self.init.validation));
+        let scene = load_scene(self.init.scene.as_deref());
+        let path_tracer = PathTracer::new(kea, scene, self.init.size);
+        let camera = FlyCamera::new(path_tracer.scene().camera());
 
         window.request_redraw();
-        self.state = Some(State { window, path_tracer });
+        self.state = Some(State {
+            window,
+            path_tracer,
+            camera,
+            last_frame: Instant::now(),
+        });
     }
 
     fn window_event(
@@ -67,13 +87,33 @@ impl ApplicationHandler for App {
         _id: WindowId,
         event: WindowEvent,
     ) {
-        let Some(state) = self.state.as_ref() else {
+        let Some(state) = self.state.as_mut() else {
             return;
         };
 
+        state.camera.handle_event(&event);
+
         match event {
             WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::Resized(size) => state.path_tracer.resize((size.width, size.height)),
+            WindowEvent::KeyboardInput { event, .. }
+                if event.physical_key == PhysicalKey::Code(KeyCode::Tab)
+                    && event.state == ElementState::Pressed
+                    && !event.repeat =>
+            {
+                state.path_tracer.toggle_preview()
+            }
             WindowEvent::RedrawRequested => {
+                let now = Instant::now();
+                let seconds = (now - state.last_frame).as_secs_f32();
+                state.camera.update(seconds);
+                state.last_frame = now;
+
+                state
+                    .path_tracer
+                    .update_scene(|scene| scene.animate(seconds));
+
+                state.path_tracer.set_camera(state.camera.parameters());
                 state.path_tracer.draw();
                 state.window.request_redraw();
             }
@@ -82,14 +122,97 @@ impl ApplicationHandler for App {
     }
 }
 
+fn features(validation: bool) -> Vec<Box<dyn Feature>> {
+    let mut features: Vec<Box<dyn Feature>> = vec![Box::new(RayTracingFeature::new())];
+    if validation {
+        features.push(Box::new(DebugFeature::new()));
+    }
+
+    features
+}
+
+/// Load the scene at `path`, or the built-in Cornell box if there isn't one.
+fn load_scene(path: Option<&Path>) -> Scene {
+    let Some(path) = path else {
+        return scenes::examples::cornell_box();
+    };
+
+    match scenes::load(path) {
+        Ok(scene) => scene,
+        Err(err) => {
+            log::error!("Couldn't load scene {}: {}", path.display(), err);
+            process::exit(1);
+        }
+    }
+}
+
+fn render_offline(options: &Options, scene: Option<&Path>, output: &Path) {
+    let mut scene = load_scene(scene);
+
+    log::info!("Rendering {} iterations", options.iterations);
+    let pixels = if options.cpu {
+        CpuPathTracer::new(&mut scene).render(options.size, options.iterations)
+    } else {
+        let kea = Kea::new_headless(features(options.validation));
+        let path_tracer = PathTracer::new(kea, scene, options.size);
+        path_tracer.render(options.iterations);
+        path_tracer.read_output()
+    };
+
+    output::save_image(output, options.size, &pixels).unwrap();
+    log::info!("Wrote {}", output.display());
+}
+
+/// Render every scene file in `directory`, writing each one to a PNG file of
+/// the same name in `output`.
+fn render_directory(options: &Options, directory: &Path, output: &Path) {
+    let mut scenes: Vec<PathBuf> = fs::read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| scenes::is_scene_file(path))
+        .collect();
+    scenes.sort();
+
+    fs::create_dir_all(output).unwrap();
+    for scene in scenes {
+        log::info!("Rendering scene {}", scene.display());
+        let image = output
+            .join(scene.file_stem().unwrap())
+            .with_extension("png");
+        render_offline(options, Some(&scene), &image);
+    }
+}
+
 fn main() {
     env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
 
+    let options = Options::from_args();
+    if let Some(directory) = options.scene.as_ref().filter(|scene| scene.is_dir()) {
+        let Some(output) = options.output.as_ref() else {
+            log::error!("--output must be given to render a directory of scenes");
+            process::exit(2);
+        };
+        render_directory(&options, directory, output);
+        return;
+    }
+
+    if let Some(output) = options.output.as_ref() {
+        render_offline(&options, options.scene.as_deref(), output);
+        return;
+    }
+
+    if options.cpu {
+        log::error!("--output must be given to render on the CPU");
+        process::exit(2);
+    }
+
     let event_loop = EventLoop::new().unwrap();
     let mut app = App {
         init: InitConfig {
-            size: (1280, 720),
-            fullscreen: false,
+            size: options.size,
+            fullscreen: options.fullscreen,
+            validation: options.validation,
+            scene: options.scene.clone(),
         },
         state: None,
     };
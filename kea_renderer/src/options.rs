@@ -0,0 +1,110 @@
+use std::{path::PathBuf, process};
+
+const USAGE: &str = "\
+Usage: kea_renderer [options]
+
+Options:
+    --size <width>x<height>  Render size in pixels (default 1280x720)
+    --fullscreen             Open a borderless fullscreen window
+    --scene <path>           Scene to render: a scene description (.ron), or
+                             a .gltf, .glb or .obj file. If this is a
+                             directory, every scene in it is rendered headless
+                             to a PNG file in the --output directory. Renders
+                             the built-in Cornell box if not given
+    --output <path>          Render headless and write the result to an image
+                             file. The format is chosen from the extension
+                             (.png or .exr)
+    --iterations <count>     Iterations to accumulate when rendering headless
+                             (default 64)
+    --cpu                    Render headless with the CPU reference path
+                             tracer instead of the GPU. Requires --output
+    --no-validation          Don't enable the Vulkan validation layers
+    --help                   Print this message";
+
+pub struct Options {
+    pub size: (u32, u32),
+    pub fullscreen: bool,
+    pub scene: Option<PathBuf>,
+    pub output: Option<PathBuf>,
+    pub iterations: u64,
+    pub cpu: bool,
+    pub validation: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            size: (1280, 720),
+            fullscreen: false,
+            scene: None,
+            output: None,
+            iterations: 64,
+            cpu: false,
+            validation: true,
+        }
+    }
+}
+
+impl Options {
+    pub fn from_args() -> Options {
+        match Self::parse(std::env::args().skip(1)) {
+            Ok(options) => options,
+            Err(message) => {
+                eprintln!("{}\n\n{}", message, USAGE);
+                process::exit(2);
+            }
+        }
+    }
+
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+        let mut options = Options::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--size" => options.size = parse_size(&value(&arg, args.next())?)?,
+                "--fullscreen" => options.fullscreen = true,
+                "--scene" => options.scene = Some(PathBuf::from(value(&arg, args.next())?)),
+                "--output" => options.output = Some(PathBuf::from(value(&arg, args.next())?)),
+                "--iterations" => {
+                    options.iterations = parse_iterations(&value(&arg, args.next())?)?
+                }
+                "--cpu" => options.cpu = true,
+                "--no-validation" => options.validation = false,
+                "--help" => {
+                    println!("{}", USAGE);
+                    process::exit(0);
+                }
+                _ => return Err(format!("Unknown argument '{}'", arg)),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+fn value(arg: &str, value: Option<String>) -> Result<String, String> {
+    value.ok_or_else(|| format!("Missing value for {}", arg))
+}
+
+fn parse_size(size: &str) -> Result<(u32, u32), String> {
+    let invalid = || format!("Invalid size '{}', expected <width>x<height>", size);
+    let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+    let width: u32 = width.parse().map_err(|_| invalid())?;
+    let height: u32 = height.parse().map_err(|_| invalid())?;
+
+    if width == 0 || height == 0 {
+        return Err(invalid());
+    }
+
+    Ok((width, height))
+}
+
+fn parse_iterations(count: &str) -> Result<u64, String> {
+    match count.parse() {
+        Ok(0) | Err(_) => Err(format!(
+            "Invalid iteration count '{}', expected at least 1",
+            count
+        )),
+        Ok(count) => Ok(count),
+    }
+}
@@ -0,0 +1,30 @@
+use image::{ImageResult, Rgba32FImage, RgbaImage};
+use std::path::Path;
+
+/// Write rgba32f pixels to an image file. EXR files keep the full float
+/// values; every other format is quantized to 8 bits per channel, matching
+/// what is blitted to the swapchain.
+pub fn save_image(path: &Path, size: (u32, u32), pixels: &[[f32; 4]]) -> ImageResult<()> {
+    let is_exr = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("exr"));
+
+    if is_exr {
+        let data = pixels.iter().flatten().copied().collect();
+        Rgba32FImage::from_raw(size.0, size.1, data)
+            .unwrap()
+            .save(path)
+    } else {
+        to_rgba8(size, pixels).save(path)
+    }
+}
+
+/// Quantize rgba32f pixels to 8 bits per channel.
+pub fn to_rgba8(size: (u32, u32), pixels: &[[f32; 4]]) -> RgbaImage {
+    let data = pixels
+        .iter()
+        .flatten()
+        .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+        .collect();
+    RgbaImage::from_raw(size.0, size.1, data).unwrap()
+}
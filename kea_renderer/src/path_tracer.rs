@@ -1,4 +1,4 @@
-use crate::scenes::{self, Scene};
+use crate::{preview::ScenePreview, scenes::Scene};
 
 mod shader_modules {
     include!(concat!(env!("OUT_DIR"), "/shader_modules.rs"));
@@ -6,10 +6,10 @@ mod shader_modules {
 use ash::vk;
 use gpu_allocator::MemoryLocation;
 use kea_gpu::{
-    commands::{CommandBuffer, CommandPool},
+    commands::{CommandBuffer, CommandBufferRecorder, CommandPool},
     descriptors::DescriptorSetLayout,
     device::Device,
-    pipelines::PipelineLayout,
+    pipelines::{ComputePipeline, PipelineLayout, SlotPipeline},
     presentation::FRAMES_IN_FLIGHT,
     ray_tracing::RayTracingPipeline,
     shaders::ShaderGroups,
@@ -17,8 +17,21 @@ use kea_gpu::{
     storage::images::{Image, ImageView},
     Kea,
 };
-use kea_renderer_shaders::{path_tracer::entrypoints::PushConstants, SlotId};
-use std::{cell::RefCell, slice, sync::Arc};
+use kea_renderer_shaders::{
+    cameras::CameraParameters,
+    path_tracer::entrypoints::PushConstants,
+    tone_mapping::{self, ToneMappingSlotId},
+    SlotId,
+};
+use std::{
+    cell::{Cell, Ref, RefCell},
+    mem, slice,
+    sync::Arc,
+};
+
+/// The format of the tone-mapped output and the light accumulation image. The
+/// shaders declare them as `rgba32f` storage images, so the two must match.
+const IMAGE_FORMAT: vk::Format = vk::Format::R32G32B32A32_SFLOAT;
 
 struct FrameSlot {
     pool: Arc<CommandPool>,
@@ -27,37 +40,38 @@ struct FrameSlot {
 
 pub struct PathTracer {
     kea: Kea,
-    _scene: Scene,
+    scene: RefCell<Scene>,
     pipeline: RayTracingPipeline<SlotId>,
-    slot_bindings: SlotBindings<SlotId>,
-    storage_image: Arc<ImageView>,
-    light_image: Arc<ImageView>,
+    slot_bindings: RefCell<SlotBindings<SlotId>>,
+    tone_mapping_pipeline: ComputePipeline<ToneMappingSlotId>,
+    tone_mapping_bindings: RefCell<SlotBindings<ToneMappingSlotId>>,
+    size: Cell<(u32, u32)>,
+    storage_image: RefCell<Arc<ImageView>>,
+    light_image: RefCell<Arc<ImageView>>,
     frame_slots: RefCell<Vec<FrameSlot>>,
+    /// Number of iterations accumulated into the light image so far.
+    iteration: Cell<u64>,
+    camera: Cell<CameraParameters>,
+    /// Made the first time the preview is shown.
+    preview: RefCell<Option<ScenePreview>>,
+    previewing: Cell<bool>,
 }
 
 impl PathTracer {
-    pub fn new(kea: Kea) -> PathTracer {
+    pub fn new(kea: Kea, mut scene: Scene, size: (u32, u32)) -> PathTracer {
+        scene.build_scene(kea.device());
+
         let pipeline = Self::create_pipeline(kea.device());
         let mut slot_bindings = SlotBindings::new(kea.device().clone(), &pipeline);
+        let tone_mapping_pipeline = Self::create_tone_mapping_pipeline(kea.device());
+        let mut tone_mapping_bindings =
+            SlotBindings::new(kea.device().clone(), &tone_mapping_pipeline);
 
-        // The shader writes the tone-mapped output as rgba32f. The swapchain
-        // image is B8G8R8A8_UNORM, so the present path uses cmd_blit_image
-        // (which converts formats) rather than cmd_copy_image.
-        let storage_image = Self::create_storage_image(
-            kea.device(),
-            vk::Format::R32G32B32A32_SFLOAT,
-            kea.presenter().size(),
-        );
-        slot_bindings.bind_image(SlotId::OutputImage, storage_image.clone());
-
-        let light_image = Self::create_storage_image(
-            kea.device(),
-            vk::Format::R32G32B32A32_SFLOAT,
-            kea.presenter().size(),
-        );
+        let (storage_image, light_image) = Self::create_images(kea.device(), size);
         slot_bindings.bind_image(SlotId::LightImage, light_image.clone());
+        tone_mapping_bindings.bind_image(ToneMappingSlotId::LightImage, light_image.clone());
+        tone_mapping_bindings.bind_image(ToneMappingSlotId::OutputImage, storage_image.clone());
 
-        let scene = scenes::examples::cornell_box(kea.device().clone());
         scene.bind_data(&mut slot_bindings);
 
         let frame_slots = (0..FRAMES_IN_FLIGHT)
@@ -73,12 +87,19 @@ impl PathTracer {
 
         PathTracer {
             kea,
-            _scene: scene,
+            camera: Cell::new(scene.camera()),
+            scene: RefCell::new(scene),
             pipeline,
-            slot_bindings,
-            storage_image,
-            light_image,
+            slot_bindings: RefCell::new(slot_bindings),
+            tone_mapping_pipeline,
+            tone_mapping_bindings: RefCell::new(tone_mapping_bindings),
+            size: Cell::new(size),
+            storage_image: RefCell::new(storage_image),
+            light_image: RefCell::new(light_image),
             frame_slots: RefCell::new(frame_slots),
+            iteration: Cell::new(0),
+            preview: RefCell::new(None),
+            previewing: Cell::new(false),
         }
     }
 
@@ -87,7 +108,15 @@ impl PathTracer {
         let bindings = slot_layout.bindings();
 
         let descriptor_set_layout = DescriptorSetLayout::new(device.clone(), &bindings);
-        let pipeline_layout = PipelineLayout::new(device.clone(), descriptor_set_layout);
+        let push_constant_range = vk::PushConstantRange::default()
+            .stage_flags(vk::ShaderStageFlags::RAYGEN_KHR)
+            .offset(0)
+            .size(mem::size_of::<PushConstants>() as _);
+        let pipeline_layout = PipelineLayout::new(
+            device.clone(),
+            descriptor_set_layout,
+            slice::from_ref(&push_constant_range),
+        );
 
         let shader_groups = ShaderGroups::new(kea_renderer_shaders::SHADERS.to_vec());
         let pipeline_shaders = shader_groups.build(device.clone(), shader_modules::SHADER_MODULES);
@@ -102,6 +131,103 @@ impl PathTracer {
         pipeline
     }
 
+    fn create_tone_mapping_pipeline(device: &Arc<Device>) -> ComputePipeline<ToneMappingSlotId> {
+        let slot_layout = SlotLayout::new(tone_mapping::SLOTS.to_vec());
+        let bindings = slot_layout.bindings();
+
+        let descriptor_set_layout = DescriptorSetLayout::new(device.clone(), &bindings);
+        let push_constant_range = vk::PushConstantRange::default()
+            .stage_flags(vk::ShaderStageFlags::COMPUTE)
+            .offset(0)
+            .size(mem::size_of::<tone_mapping::entrypoints::PushConstants>() as _);
+        let pipeline_layout = PipelineLayout::new(
+            device.clone(),
+            descriptor_set_layout,
+            slice::from_ref(&push_constant_range),
+        );
+
+        ComputePipeline::new(
+            device.clone(),
+            tone_mapping::SHADER,
+            shader_modules::SHADER_MODULES,
+            pipeline_layout,
+            slot_layout,
+        )
+    }
+
+    /// Create the tone-mapped output image and the light accumulation image.
+    fn create_images(device: &Arc<Device>, size: (u32, u32)) -> (Arc<ImageView>, Arc<ImageView>) {
+        // The tone mapping pass writes the tone-mapped output as rgba32f. The swapchain
+        // image is B8G8R8A8_UNORM, so the present path uses cmd_blit_image
+        // (which converts formats) rather than cmd_copy_image.
+        let storage_image = Self::create_storage_image(device, IMAGE_FORMAT, size);
+        let light_image = Self::create_storage_image(device, IMAGE_FORMAT, size);
+
+        (storage_image, light_image)
+    }
+
+    /// Replace the size-dependent images after the swapchain has been
+    /// recreated, and restart accumulation. The device must be idle.
+    fn resize_images(&self, size: (u32, u32)) {
+        let (storage_image, light_image) = Self::create_images(self.kea.device(), size);
+
+        let mut slot_bindings = self.slot_bindings.borrow_mut();
+        slot_bindings.bind_image(SlotId::LightImage, light_image.clone());
+        let mut tone_mapping_bindings = self.tone_mapping_bindings.borrow_mut();
+        tone_mapping_bindings.bind_image(ToneMappingSlotId::LightImage, light_image.clone());
+        tone_mapping_bindings.bind_image(ToneMappingSlotId::OutputImage, storage_image.clone());
+
+        *self.storage_image.borrow_mut() = storage_image;
+        *self.light_image.borrow_mut() = light_image;
+        if let Some(preview) = self.preview.borrow_mut().as_mut() {
+            preview.resize(size);
+        }
+        self.size.set(size);
+        self.iteration.set(0);
+    }
+
+    /// Switch between path tracing and a rasterized preview of the scene.
+    /// Path tracing carries on accumulating where it left off, unless
+    /// something has moved.
+    pub fn toggle_preview(&self) {
+        let mut preview = self.preview.borrow_mut();
+        if preview.is_none() {
+            *preview = Some(ScenePreview::new(
+                self.kea.device(),
+                &mut self.scene.borrow_mut(),
+                shader_modules::SHADER_MODULES,
+                IMAGE_FORMAT,
+                self.size.get(),
+            ));
+        }
+
+        self.previewing.set(!self.previewing.get());
+    }
+
+    pub fn scene(&self) -> Ref<'_, Scene> {
+        self.scene.borrow()
+    }
+
+    /// Change the scene between frames, such as by moving its entities.
+    /// Accumulation restarts on the next frame if anything has moved.
+    pub fn update_scene(&self, update: impl FnOnce(&mut Scene)) {
+        update(&mut self.scene.borrow_mut());
+    }
+
+    /// Move the camera, restarting accumulation if it has changed.
+    pub fn set_camera(&self, camera: CameraParameters) {
+        if camera != self.camera.get() {
+            self.camera.set(camera);
+            self.iteration.set(0);
+        }
+    }
+
+    /// Resize the window's swapchain. The output is reallocated at the start
+    /// of the next frame.
+    pub fn resize(&self, size: (u32, u32)) {
+        self.kea.presenter().resize(size);
+    }
+
     fn create_storage_image(
         device: &Arc<Device>,
         format: vk::Format,
@@ -112,7 +238,9 @@ impl PathTracer {
             "rt image output".to_string(),
             size,
             format,
-            vk::ImageUsageFlags::STORAGE | vk::ImageUsageFlags::TRANSFER_SRC,
+            vk::ImageUsageFlags::STORAGE
+                | vk::ImageUsageFlags::TRANSFER_SRC
+                | vk::ImageUsageFlags::COLOR_ATTACHMENT,
             MemoryLocation::GpuOnly,
         );
 
@@ -137,136 +265,244 @@ impl PathTracer {
         Arc::new(image_view)
     }
 
+    /// Record a single path tracing iteration, accumulating into the light
+    /// image. The GPU must have finished with the frame `FRAMES_IN_FLIGHT` before
+    /// `frame`, as they share a descriptor set.
+    fn record_trace_rays(&self, cmd: &CommandBufferRecorder, frame: u64, iteration: u64) {
+        cmd.bind_pipeline(
+            vk::PipelineBindPoint::RAY_TRACING_KHR,
+            self.pipeline.pipeline(),
+        );
+        cmd.bind_descriptor_sets(
+            vk::PipelineBindPoint::RAY_TRACING_KHR,
+            self.pipeline.layout(),
+            slice::from_ref(self.slot_bindings.borrow_mut().descriptor_set(frame)),
+        );
+
+        let (width, height) = self.size.get();
+        let constants = PushConstants {
+            camera: CameraParameters {
+                aspect_ratio: width as f32 / height as f32,
+                ..self.camera.get()
+            },
+            iteration,
+        };
+        cmd.push_constants(
+            self.pipeline.layout(),
+            vk::ShaderStageFlags::RAYGEN_KHR,
+            &constants,
+        );
+
+        // light_image is read-modify-written by trace_rays each frame
+        // (running-average accumulator). With FRAMES_IN_FLIGHT > 1 there
+        // is no implicit ordering between consecutive frames' trace_rays,
+        // so make the read of frame N+1 wait for the write of frame N, and
+        // the write wait for the previous tone mapping pass's read.
+        cmd.transition_image_layout(
+            self.light_image.borrow().image(),
+            vk::ImageLayout::GENERAL,
+            vk::ImageLayout::GENERAL,
+            vk::AccessFlags2::SHADER_STORAGE_WRITE,
+            vk::AccessFlags2::SHADER_STORAGE_READ | vk::AccessFlags2::SHADER_STORAGE_WRITE,
+            vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR
+                | vk::PipelineStageFlags2::COMPUTE_SHADER,
+            vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR,
+        );
+
+        cmd.trace_rays(self.pipeline.shader_binding_tables(), (width, height, 1));
+    }
+
+    /// Record the tone mapping pass, writing the light accumulated so far to
+    /// the storage image. The same descriptor set rules as
+    /// `record_trace_rays` apply.
+    fn record_tone_map(&self, cmd: &CommandBufferRecorder, frame: u64) {
+        cmd.transition_image_layout(
+            self.light_image.borrow().image(),
+            vk::ImageLayout::GENERAL,
+            vk::ImageLayout::GENERAL,
+            vk::AccessFlags2::SHADER_STORAGE_WRITE,
+            vk::AccessFlags2::SHADER_STORAGE_READ,
+            vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR,
+            vk::PipelineStageFlags2::COMPUTE_SHADER,
+        );
+
+        cmd.bind_pipeline(
+            vk::PipelineBindPoint::COMPUTE,
+            self.tone_mapping_pipeline.pipeline(),
+        );
+        cmd.bind_descriptor_sets(
+            vk::PipelineBindPoint::COMPUTE,
+            self.tone_mapping_pipeline.layout(),
+            slice::from_ref(
+                self.tone_mapping_bindings
+                    .borrow_mut()
+                    .descriptor_set(frame),
+            ),
+        );
+
+        let (width, height) = self.size.get();
+        let constants = tone_mapping::entrypoints::PushConstants {
+            size: glam::uvec2(width, height),
+        };
+        cmd.push_constants(
+            self.tone_mapping_pipeline.layout(),
+            vk::ShaderStageFlags::COMPUTE,
+            &constants,
+        );
+
+        let (group_width, group_height) = tone_mapping::WORKGROUP_SIZE;
+        cmd.dispatch((
+            width.div_ceil(group_width),
+            height.div_ceil(group_height),
+            1,
+        ));
+    }
+
     pub fn draw(&self) {
-        let (swapchain_index, swapchain_image) = self.kea.presenter().get_swapchain_image();
-        let frame = self.kea.presenter().frame_index();
+        let presenter = self.kea.presenter();
+        let Some((swapchain_index, swapchain_image)) =
+            presenter.get_swapchain_image(|size| self.resize_images(size))
+        else {
+            return;
+        };
+        let frame = presenter.frame_index();
+        let storage_image = self.storage_image.borrow();
         let slot_index = (frame % FRAMES_IN_FLIGHT) as usize;
 
         let mut slots = self.frame_slots.borrow_mut();
         let slot = &mut slots[slot_index];
         // The presenter's timeline wait at the start of the frame guarantees
-        // the GPU has finished any prior use of this slot's command buffer.
+        // the GPU has finished any prior use of this slot's command buffer
+        // and descriptor set.
         slot.pool.reset();
         let buffer = slot.buffer.take().unwrap();
 
         let cmd = buffer.record(|cmd| {
-                cmd.bind_pipeline(
-                    vk::PipelineBindPoint::RAY_TRACING_KHR,
-                    &self.pipeline.pipeline(),
+            if self.previewing.get() {
+                // Moved instances are picked up once path tracing resumes.
+                self.preview.borrow().as_ref().unwrap().record(
+                    cmd,
+                    &mut self.scene.borrow_mut(),
+                    self.camera.get(),
+                    &storage_image,
                 );
-                cmd.bind_descriptor_sets(
-                    vk::PipelineBindPoint::RAY_TRACING_KHR,
-                    &self.pipeline.layout(),
-                    slice::from_ref(&self.slot_bindings.descriptor_set()),
-                );
-                unsafe {
-                    let constants = PushConstants { iteration: frame };
-                    let (_, constants, _) = slice::from_ref(&constants).align_to::<u8>();
-                    self.kea.device().raw().cmd_push_constants(
-                        cmd.buffer().raw(),
-                        self.pipeline.layout().raw(),
-                        vk::ShaderStageFlags::RAYGEN_KHR,
-                        0,
-                        constants,
-                    );
+            } else {
+                if self.scene.borrow_mut().update_instances(cmd, frame) {
+                    self.iteration.set(0);
                 }
+                self.record_trace_rays(cmd, frame, self.iteration.get());
+                self.record_tone_map(cmd, frame);
+                self.iteration.set(self.iteration.get() + 1);
+            }
 
-                // light_image is read-modify-written by trace_rays each frame
-                // (running-average accumulator). With FRAMES_IN_FLIGHT > 1 there
-                // is no implicit ordering between consecutive frames' trace_rays,
-                // so make the read of frame N+1 wait for the write of frame N.
-                cmd.transition_image_layout(
-                    &self.light_image.image(),
-                    vk::ImageLayout::GENERAL,
-                    vk::ImageLayout::GENERAL,
-                    vk::AccessFlags2::SHADER_STORAGE_WRITE,
-                    vk::AccessFlags2::SHADER_STORAGE_READ | vk::AccessFlags2::SHADER_STORAGE_WRITE,
-                    vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR,
-                    vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR,
-                );
+            cmd.transition_image_layout(
+                &swapchain_image.image(),
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::AccessFlags2::NONE,
+                vk::AccessFlags2::TRANSFER_WRITE,
+                vk::PipelineStageFlags2::TRANSFER,
+                vk::PipelineStageFlags2::TRANSFER,
+            );
 
-                cmd.trace_rays(
-                    self.pipeline.shader_binding_tables(),
-                    (
-                        self.kea.presenter().size().0,
-                        self.kea.presenter().size().1,
-                        1,
-                    ),
-                );
+            cmd.transition_image_layout(
+                storage_image.image(),
+                vk::ImageLayout::GENERAL,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                vk::AccessFlags2::SHADER_STORAGE_WRITE | vk::AccessFlags2::COLOR_ATTACHMENT_WRITE,
+                vk::AccessFlags2::TRANSFER_READ,
+                vk::PipelineStageFlags2::COMPUTE_SHADER
+                    | vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+                vk::PipelineStageFlags2::TRANSFER,
+            );
 
-                cmd.transition_image_layout(
-                    &swapchain_image.image(),
-                    vk::ImageLayout::UNDEFINED,
-                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-                    vk::AccessFlags2::NONE,
-                    vk::AccessFlags2::TRANSFER_WRITE,
-                    vk::PipelineStageFlags2::TRANSFER,
-                    vk::PipelineStageFlags2::TRANSFER,
-                );
+            let layers = vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                base_array_layer: 0,
+                mip_level: 0,
+                layer_count: 1,
+            };
+            let (w, h) = self.size.get();
+            let corners = [
+                vk::Offset3D { x: 0, y: 0, z: 0 },
+                vk::Offset3D {
+                    x: w as i32,
+                    y: h as i32,
+                    z: 1,
+                },
+            ];
+            let blit_region = vk::ImageBlit::default()
+                .src_subresource(layers)
+                .src_offsets(corners)
+                .dst_subresource(layers)
+                .dst_offsets(corners);
 
-                cmd.transition_image_layout(
-                    &self.storage_image.image(),
-                    vk::ImageLayout::GENERAL,
-                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
-                    vk::AccessFlags2::SHADER_STORAGE_WRITE,
-                    vk::AccessFlags2::TRANSFER_READ,
-                    vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR,
-                    vk::PipelineStageFlags2::TRANSFER,
-                );
+            cmd.blit_image(
+                storage_image.image(),
+                &swapchain_image.image(),
+                &blit_region,
+                vk::Filter::NEAREST,
+            );
 
-                let layers = vk::ImageSubresourceLayers {
-                    aspect_mask: vk::ImageAspectFlags::COLOR,
-                    base_array_layer: 0,
-                    mip_level: 0,
-                    layer_count: 1,
-                };
-                let (w, h) = self.kea.presenter().size();
-                let corners = [
-                    vk::Offset3D { x: 0, y: 0, z: 0 },
-                    vk::Offset3D {
-                        x: w as i32,
-                        y: h as i32,
-                        z: 1,
-                    },
-                ];
-                let blit_region = vk::ImageBlit::default()
-                    .src_subresource(layers)
-                    .src_offsets(corners)
-                    .dst_subresource(layers)
-                    .dst_offsets(corners);
-
-                cmd.blit_image(
-                    &self.storage_image.image(),
-                    &swapchain_image.image(),
-                    &blit_region,
-                    vk::Filter::NEAREST,
-                );
+            cmd.transition_image_layout(
+                swapchain_image.image(),
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::ImageLayout::PRESENT_SRC_KHR,
+                vk::AccessFlags2::TRANSFER_WRITE,
+                vk::AccessFlags2::NONE,
+                vk::PipelineStageFlags2::TRANSFER,
+                vk::PipelineStageFlags2::NONE,
+            );
 
-                cmd.transition_image_layout(
-                    swapchain_image.image(),
-                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-                    vk::ImageLayout::PRESENT_SRC_KHR,
-                    vk::AccessFlags2::TRANSFER_WRITE,
-                    vk::AccessFlags2::NONE,
-                    vk::PipelineStageFlags2::TRANSFER,
-                    vk::PipelineStageFlags2::NONE,
-                );
+            cmd.transition_image_layout(
+                storage_image.image(),
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                vk::ImageLayout::GENERAL,
+                vk::AccessFlags2::TRANSFER_READ,
+                vk::AccessFlags2::SHADER_STORAGE_READ
+                    | vk::AccessFlags2::SHADER_STORAGE_WRITE
+                    | vk::AccessFlags2::COLOR_ATTACHMENT_WRITE,
+                vk::PipelineStageFlags2::TRANSFER,
+                vk::PipelineStageFlags2::COMPUTE_SHADER
+                    | vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+            );
+        });
 
-                cmd.transition_image_layout(
-                    &self.storage_image.image(),
-                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
-                    vk::ImageLayout::GENERAL,
-                    vk::AccessFlags2::TRANSFER_READ,
-                    vk::AccessFlags2::SHADER_STORAGE_READ | vk::AccessFlags2::SHADER_STORAGE_WRITE,
-                    vk::PipelineStageFlags2::TRANSFER,
-                    vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR,
-                );
-            });
-
-        self.kea
-            .presenter()
-            .draw(swapchain_index, slice::from_ref(&cmd));
+        presenter.draw(swapchain_index, slice::from_ref(&cmd));
 
         slot.buffer = Some(unsafe { cmd.consume() });
     }
+
+    /// Render `iterations` path tracing iterations without presenting them,
+    /// tone mapping after the last. Each iteration is submitted and waited
+    /// upon before the next is recorded, so each can be treated as its own
+    /// frame.
+    pub fn render(&self, iterations: u64) {
+        let pool = CommandPool::new(self.kea.device().graphics_queue());
+        let mut buffer = pool.allocate_buffer("offline trace rays".to_string());
+
+        for iteration in 0..iterations {
+            let submitted = buffer
+                .record(|cmd| {
+                    self.record_trace_rays(cmd, iteration, iteration);
+                    if iteration + 1 == iterations {
+                        self.record_tone_map(cmd, iteration);
+                    }
+                })
+                .submit();
+            buffer = unsafe { submitted.wait_and_reuse().consume() };
+            pool.reset();
+
+            log::debug!("Rendered iteration {}/{}", iteration + 1, iterations);
+        }
+    }
+
+    /// Copy the tone-mapped output image back to the host as rgba32f pixels,
+    /// in row-major order.
+    pub fn read_output(&self) -> Vec<[f32; 4]> {
+        self.storage_image
+            .borrow()
+            .image()
+            .read_to_host(vk::ImageLayout::GENERAL)
+    }
 }
@@ -0,0 +1,429 @@
+use crate::scenes::{Mesh, Scene};
+use ash::vk;
+use glam::{Mat3, Mat4, Vec3, Vec4};
+use gpu_allocator::MemoryLocation;
+use kea_gpu::{
+    commands::{CommandBuffer, CommandBufferRecorder},
+    descriptors::DescriptorSetLayout,
+    device::Device,
+    pipelines::{
+        GraphicsPipeline, GraphicsPipelineDescription, PipelineLayout, RenderingAttachment,
+        SlotPipeline, VertexAttribute, VertexBufferLayout,
+    },
+    slots::SlotLayout,
+    storage::{
+        buffers::Buffer,
+        images::{Image, ImageView},
+    },
+};
+use kea_gpu_shaderlib::Aabb;
+use kea_renderer_shaders::{
+    cameras::CameraParameters,
+    materials::Material,
+    preview::{self, entrypoints::PushConstants},
+};
+use std::{mem, slice, sync::Arc};
+
+const DEPTH_FORMAT: vk::Format = vk::Format::D32_SFLOAT;
+const NEAR: f32 = 0.01;
+const FAR: f32 = 1000.0;
+
+/// A vertex as the preview shaders take it. A zero normal leaves it unlit.
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct PreviewVertex {
+    position: Vec3,
+    normal: Vec3,
+}
+
+/// A mesh asset's vertices and triangles, ready to draw.
+struct PreviewMesh {
+    vertices: Buffer,
+    indices: Buffer,
+    index_count: u32,
+}
+
+/// A quick rasterized view of a scene, for looking around it without waiting
+/// for the path tracer. Meshes are drawn lit from the camera, and everything
+/// else besides planes as a wireframe of its bounds, in the colour of its
+/// material.
+pub struct ScenePreview {
+    device: Arc<Device>,
+    mesh_pipeline: GraphicsPipeline<()>,
+    wireframe_pipeline: GraphicsPipeline<()>,
+    meshes: Vec<PreviewMesh>,
+    /// Line segments around the bounds of each shape, with the colour to
+    /// draw them in. These are built once, in world space: like the path
+    /// tracer, which bakes shapes into its buffers when the scene is built,
+    /// the preview only follows mesh instances as they move.
+    wireframe: Option<(Buffer, Vec<Vec3>)>,
+    depth_image: ImageView,
+}
+
+impl ScenePreview {
+    pub fn new(
+        device: &Arc<Device>,
+        scene: &mut Scene,
+        shader_modules: &[(&str, &[u8])],
+        colour_format: vk::Format,
+        size: (u32, u32),
+    ) -> Self {
+        let meshes = scene
+            .mesh_assets()
+            .iter()
+            .map(|mesh| {
+                let (vertices, indices) = preview_vertices(mesh);
+                PreviewMesh {
+                    vertices: Buffer::new_from_data(
+                        device.clone(),
+                        &vertices,
+                        vk::BufferUsageFlags::VERTEX_BUFFER,
+                        "preview vertices".to_string(),
+                        MemoryLocation::GpuOnly,
+                        None,
+                    ),
+                    indices: Buffer::new_from_data(
+                        device.clone(),
+                        &indices,
+                        vk::BufferUsageFlags::INDEX_BUFFER,
+                        "preview indices".to_string(),
+                        MemoryLocation::GpuOnly,
+                        None,
+                    ),
+                    index_count: indices.len() as u32,
+                }
+            })
+            .collect();
+
+        let shapes = scene.bounded_shapes();
+        let wireframe = (!shapes.is_empty()).then(|| {
+            let lines: Vec<PreviewVertex> = shapes
+                .iter()
+                .flat_map(|(aabb, _)| box_lines(aabb))
+                .collect();
+            let buffer = Buffer::new_from_data(
+                device.clone(),
+                &lines,
+                vk::BufferUsageFlags::VERTEX_BUFFER,
+                "preview wireframe".to_string(),
+                MemoryLocation::GpuOnly,
+                None,
+            );
+            let colours = shapes
+                .iter()
+                .map(|(_, material)| colour(material))
+                .collect();
+            (buffer, colours)
+        });
+
+        Self {
+            device: device.clone(),
+            mesh_pipeline: Self::create_pipeline(
+                device,
+                shader_modules,
+                colour_format,
+                vk::PrimitiveTopology::TRIANGLE_LIST,
+            ),
+            wireframe_pipeline: Self::create_pipeline(
+                device,
+                shader_modules,
+                colour_format,
+                vk::PrimitiveTopology::LINE_LIST,
+            ),
+            meshes,
+            wireframe,
+            depth_image: Self::create_depth_image(device, size),
+        }
+    }
+
+    fn create_pipeline(
+        device: &Arc<Device>,
+        shader_modules: &[(&str, &[u8])],
+        colour_format: vk::Format,
+        topology: vk::PrimitiveTopology,
+    ) -> GraphicsPipeline<()> {
+        // Everything the shaders need comes in push constants, so there are
+        // no slots.
+        let slot_layout = SlotLayout::new(vec![]);
+        let descriptor_set_layout = DescriptorSetLayout::new(device.clone(), &[]);
+        let push_constant_range = vk::PushConstantRange::default()
+            .stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT)
+            .offset(0)
+            .size(mem::size_of::<PushConstants>() as _);
+        let pipeline_layout = PipelineLayout::new(
+            device.clone(),
+            descriptor_set_layout,
+            slice::from_ref(&push_constant_range),
+        );
+
+        let description = GraphicsPipelineDescription {
+            vertex_shader: preview::VERTEX_SHADER,
+            fragment_shader: preview::FRAGMENT_SHADER,
+            vertex_buffers: vec![VertexBufferLayout {
+                stride: mem::size_of::<PreviewVertex>() as _,
+                attributes: vec![
+                    VertexAttribute {
+                        location: 0,
+                        format: vk::Format::R32G32B32_SFLOAT,
+                        offset: mem::offset_of!(PreviewVertex, position) as _,
+                    },
+                    VertexAttribute {
+                        location: 1,
+                        format: vk::Format::R32G32B32_SFLOAT,
+                        offset: mem::offset_of!(PreviewVertex, normal) as _,
+                    },
+                ],
+            }],
+            topology,
+            colour_formats: vec![colour_format],
+            depth_format: Some(DEPTH_FORMAT),
+        };
+
+        GraphicsPipeline::new(
+            device.clone(),
+            &description,
+            shader_modules,
+            pipeline_layout,
+            slot_layout,
+        )
+    }
+
+    fn create_depth_image(device: &Arc<Device>, size: (u32, u32)) -> ImageView {
+        let image = Image::new(
+            device.clone(),
+            "preview depth".to_string(),
+            size,
+            DEPTH_FORMAT,
+            vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+            MemoryLocation::GpuOnly,
+        );
+        let image_view = ImageView::new(Arc::new(image));
+
+        CommandBuffer::now(
+            device,
+            "Set initial preview depth image layout".to_string(),
+            |cmd| {
+                cmd.transition_image_layout(
+                    image_view.image(),
+                    vk::ImageLayout::UNDEFINED,
+                    vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
+                    vk::AccessFlags2::NONE,
+                    vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE,
+                    vk::PipelineStageFlags2::NONE,
+                    vk::PipelineStageFlags2::EARLY_FRAGMENT_TESTS,
+                )
+            },
+        );
+
+        image_view
+    }
+
+    /// Replace the depth image to match a new output size. The device must
+    /// be idle.
+    pub fn resize(&mut self, size: (u32, u32)) {
+        self.depth_image = Self::create_depth_image(&self.device, size);
+    }
+
+    /// Record drawing the scene from `camera` into `output`, which is in
+    /// `GENERAL` layout and the size of the depth image.
+    pub fn record(
+        &self,
+        cmd: &CommandBufferRecorder,
+        scene: &mut Scene,
+        camera: CameraParameters,
+        output: &ImageView,
+    ) {
+        // The previous frame may still be testing against the depth image.
+        cmd.transition_image_layout(
+            self.depth_image.image(),
+            vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
+            vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
+            vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_READ
+                | vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            vk::PipelineStageFlags2::LATE_FRAGMENT_TESTS,
+            vk::PipelineStageFlags2::EARLY_FRAGMENT_TESTS,
+        );
+
+        let size = output.image().size();
+        cmd.begin_rendering(
+            size,
+            &[RenderingAttachment {
+                image_view: output,
+                layout: vk::ImageLayout::GENERAL,
+                clear_value: Some(vk::ClearValue {
+                    color: vk::ClearColorValue {
+                        float32: [0.0, 0.0, 0.0, 1.0],
+                    },
+                }),
+            }],
+            Some(&RenderingAttachment {
+                image_view: &self.depth_image,
+                layout: vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
+                clear_value: Some(vk::ClearValue {
+                    depth_stencil: vk::ClearDepthStencilValue {
+                        depth: 1.0,
+                        stencil: 0,
+                    },
+                }),
+            }),
+        );
+
+        let view = Mat4::look_at_rh(
+            camera.position.into(),
+            camera.target_position.into(),
+            camera.up_direction.into(),
+        );
+        let mut projection = Mat4::perspective_rh(
+            camera.vertical_field_of_view_radians,
+            size.0 as f32 / size.1 as f32,
+            NEAR,
+            FAR,
+        );
+        // Vulkan's clip space points y down the image.
+        projection.y_axis.y = -projection.y_axis.y;
+        let clip_from_world = projection * view;
+
+        cmd.bind_pipeline(
+            vk::PipelineBindPoint::GRAPHICS,
+            self.mesh_pipeline.pipeline(),
+        );
+        for instance in scene.meshes() {
+            let model = Mat4::from(instance.transform);
+            let normal_transform = Mat3::from_mat4(view * model).inverse().transpose();
+            push_constants(
+                cmd,
+                &self.mesh_pipeline,
+                PushConstants {
+                    clip_from_object: clip_from_world * model,
+                    view_normal_from_object: [
+                        normal_transform.x_axis.extend(0.0),
+                        normal_transform.y_axis.extend(0.0),
+                        normal_transform.z_axis.extend(0.0),
+                    ],
+                    colour: colour(&instance.material).extend(1.0),
+                },
+            );
+
+            let mesh = &self.meshes[instance.mesh.0 as usize];
+            cmd.bind_vertex_buffers(&[&mesh.vertices]);
+            cmd.bind_index_buffer(&mesh.indices);
+            cmd.draw_indexed(mesh.index_count, 0);
+        }
+
+        if let Some((lines, colours)) = self.wireframe.as_ref() {
+            cmd.bind_pipeline(
+                vk::PipelineBindPoint::GRAPHICS,
+                self.wireframe_pipeline.pipeline(),
+            );
+            cmd.bind_vertex_buffers(&[lines]);
+            for (index, colour) in colours.iter().enumerate() {
+                push_constants(
+                    cmd,
+                    &self.wireframe_pipeline,
+                    PushConstants {
+                        clip_from_object: clip_from_world,
+                        view_normal_from_object: [Vec4::ZERO; 3],
+                        colour: colour.extend(1.0),
+                    },
+                );
+                cmd.draw(BOX_LINE_VERTICES, index as u32 * BOX_LINE_VERTICES);
+            }
+        }
+
+        cmd.end_rendering();
+    }
+}
+
+fn push_constants(
+    cmd: &CommandBufferRecorder,
+    pipeline: &GraphicsPipeline<()>,
+    constants: PushConstants,
+) {
+    cmd.push_constants(
+        pipeline.layout(),
+        vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+        &constants,
+    );
+}
+
+/// The colour to preview a material in: its base colour, brightened by any
+/// light it emits.
+fn colour(material: &Material) -> Vec3 {
+    (Vec3::from(material.base_colour) + Vec3::from(material.emit)).min(Vec3::ONE)
+}
+
+/// The vertices and triangles of a mesh asset for the preview shaders.
+/// Flat shaded triangles get vertices of their own, with the triangle's
+/// normal.
+fn preview_vertices(mesh: &Mesh) -> (Vec<PreviewVertex>, Vec<u32>) {
+    let smooth = mesh
+        .attributes()
+        .iter()
+        .all(|vertex| vertex.normal != glam::Vec3A::ZERO);
+    if smooth {
+        let vertices = mesh
+            .vertices()
+            .iter()
+            .zip(mesh.attributes())
+            .map(|(position, vertex)| PreviewVertex {
+                position: (*position).into(),
+                normal: vertex.normal.into(),
+            })
+            .collect();
+        return (vertices, mesh.indices().iter().flatten().copied().collect());
+    }
+
+    let mut vertices = vec![];
+    for triangle in mesh.indices() {
+        let [a, b, c] = triangle.map(|index| Vec3::from(mesh.vertices()[index as usize]));
+        let face_normal = (b - a).cross(c - a);
+        for index in triangle {
+            let normal = Vec3::from(mesh.attributes()[*index as usize].normal);
+            vertices.push(PreviewVertex {
+                position: mesh.vertices()[*index as usize].into(),
+                normal: if normal == Vec3::ZERO {
+                    face_normal
+                } else {
+                    normal
+                },
+            });
+        }
+    }
+    let indices = (0..vertices.len() as u32).collect();
+
+    (vertices, indices)
+}
+
+/// Two vertices for each of a box's twelve edges.
+const BOX_LINE_VERTICES: u32 = 24;
+
+/// The edges of `aabb`, as a line list.
+fn box_lines(aabb: &Aabb) -> Vec<PreviewVertex> {
+    let corner = |index: u32| {
+        let pick = |bit: u32, min: f32, max: f32| if index & bit == 0 { min } else { max };
+        Vec3::new(
+            pick(1, aabb.min.x, aabb.max.x),
+            pick(2, aabb.min.y, aabb.max.y),
+            pick(4, aabb.min.z, aabb.max.z),
+        )
+    };
+
+    // Corners are numbered by which of their coordinates are at the
+    // maximum, so each edge joins corners differing in one bit.
+    let mut lines = vec![];
+    for from in 0..8 {
+        for bit in [1, 2, 4] {
+            if from & bit == 0 {
+                for index in [from, from | bit] {
+                    lines.push(PreviewVertex {
+                        position: corner(index),
+                        normal: Vec3::ZERO,
+                    });
+                }
+            }
+        }
+    }
+
+    lines
+}
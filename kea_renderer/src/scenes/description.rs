@@ -0,0 +1,575 @@
+use super::{Environment, EnvironmentImage, LoadError, Scene, SdfShape};
+use glam::{Affine3A, EulerRot, Quat, Vec3, Vec3A};
+use kea_renderer_shaders::{cameras::CameraParameters, materials::Material, sdfs::MAX_SDF_STACK};
+use ron::extensions::Extensions;
+use serde::Deserialize;
+use std::{collections::HashMap, fs, path::Path};
+
+/// A scene described in a RON file. For example:
+///
+/// ```ron
+/// (
+///     camera: (position: (0.0, 1.0, 1.5), target: (0.0, 1.0, -1.0), fov: 70.0),
+///     environment: Sky(sun_elevation: 30.0, sun_azimuth: 20.0),
+///     materials: {
+///         "grey": (colour: (0.8, 0.8, 0.8)),
+///         "light": (colour: (0.5, 0.5, 0.5), emit: (20.0, 20.0, 14.0)),
+///         "gold": (kind: Metal, colour: (1.0, 0.78, 0.34), roughness: 0.3),
+///         "glass": (kind: Glass, ior: 1.5),
+///     },
+///     objects: [
+///         Sphere(position: (0.4, 0.4, -0.7), radius: 0.4, material: "grey"),
+///         Box(position: (0.0, 2.0, -1.0), scale: (3.0, 0.01, 2.0), material: "light"),
+///         Cylinder(position: (-0.5, 0.0, -1.0), height: 0.6, radius: 0.2, material: "gold"),
+///         Torus(
+///             position: (0.5, 0.1, -1.0),
+///             major_radius: 0.3,
+///             minor_radius: 0.1,
+///             material: "glass",
+///         ),
+///         Sdf(
+///             shape: SmoothUnion(
+///                 Sphere(centre: (0.0, 0.3, -0.5), radius: 0.2),
+///                 Capsule(start: (0.0, 0.0, -0.5), end: (0.0, 0.3, -0.5), radius: 0.1),
+///                 0.1,
+///             ),
+///             material: "grey",
+///         ),
+///         Mesh(path: "bunny.obj", position: (0.0, 0.0, -1.0), rotation: (0.0, 45.0, 0.0)),
+///         PointLight(position: (0.0, 1.8, -1.0), intensity: (2.0, 2.0, 2.0)),
+///     ],
+/// )
+/// ```
+///
+/// Mesh paths are relative to the scene file, and rotations are XYZ Euler
+/// angles in degrees. Boxes can also `spin` in the window, at so many degrees
+/// per second about each of their axes.
+///
+/// Boxes are triangle meshes, but the analytic primitives are traced exactly:
+/// an `AxisAlignedBox` of a given `size`, an infinite `Plane` through its
+/// position, a `Disc`, and a capped `Cylinder`, a `Cone` and a `Torus`. Planes
+/// and discs face along their `normal`, and cylinders, cones and tori are
+/// around their `axis`, both of which default to +Y. Cylinders and cones stand
+/// on their position, and the rest are centred on it.
+///
+/// An `Sdf` is a `shape` traced by its signed distance function: a `Sphere`, a
+/// `Box` of a given `size` or a `Capsule`, or a `Union`, `Intersection` or
+/// `Subtraction` of two shapes, or a `SmoothUnion` blending them over a
+/// radius. Subtractions cut the second shape out of the first. Materials are
+/// Lambertian unless given another `kind`: `Mirror`, `Metal` (with a
+/// `roughness`) or `Glass` (with an `ior`). Their `colour` defaults to white.
+/// Point lights have no material, just an intensity in watts per steradian.
+///
+/// The environment is black unless given as a `Constant(colour: ...)`, a
+/// `Sky` with the sun at the given elevation and azimuth in degrees (clockwise
+/// from -Z, seen from above) and an optional `turbidity`, or a `Map` loading
+/// an equirectangular `.hdr` or `.exr` image from `path`. The sky and maps
+/// take a `strength` to scale them by.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDescription {
+    camera: Option<CameraDescription>,
+    environment: Option<EnvironmentDescription>,
+    #[serde(default)]
+    materials: HashMap<String, MaterialDescription>,
+    #[serde(default)]
+    objects: Vec<ObjectDescription>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDescription {
+    position: [f32; 3],
+    target: [f32; 3],
+    #[serde(default = "default_up")]
+    up: [f32; 3],
+    /// Vertical field of view, in degrees.
+    #[serde(default = "default_fov")]
+    fov: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MaterialDescription {
+    #[serde(default)]
+    kind: MaterialKindDescription,
+    #[serde(alias = "diffuse", default = "default_colour")]
+    colour: [f32; 3],
+    #[serde(default)]
+    emit: [f32; 3],
+    #[serde(default)]
+    roughness: f32,
+    #[serde(default = "default_ior")]
+    ior: f32,
+}
+
+#[derive(Deserialize, Default)]
+enum MaterialKindDescription {
+    #[default]
+    Lambertian,
+    Mirror,
+    Metal,
+    Glass,
+}
+
+impl MaterialDescription {
+    fn material(&self) -> Material {
+        let colour = Vec3A::from(self.colour);
+        let material = match self.kind {
+            MaterialKindDescription::Lambertian => Material::lambertian(colour),
+            MaterialKindDescription::Mirror => Material::mirror(colour),
+            MaterialKindDescription::Metal => Material::metal(colour, self.roughness),
+            MaterialKindDescription::Glass => Material::dielectric(colour, self.ior),
+        };
+
+        material.with_emit(Vec3A::from(self.emit))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+enum EnvironmentDescription {
+    Constant {
+        colour: [f32; 3],
+    },
+    Sky {
+        sun_elevation: f32,
+        #[serde(default)]
+        sun_azimuth: f32,
+        #[serde(default = "default_turbidity")]
+        turbidity: f32,
+        #[serde(default = "default_strength")]
+        strength: f32,
+    },
+    Map {
+        path: String,
+        #[serde(default = "default_strength")]
+        strength: f32,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+enum ObjectDescription {
+    Sphere {
+        position: [f32; 3],
+        radius: f32,
+        material: String,
+    },
+    Box {
+        position: [f32; 3],
+        #[serde(default)]
+        rotation: [f32; 3],
+        scale: [f32; 3],
+        material: String,
+        /// Degrees per second about each axis, in the window.
+        #[serde(default)]
+        spin: [f32; 3],
+    },
+    AxisAlignedBox {
+        position: [f32; 3],
+        size: [f32; 3],
+        material: String,
+    },
+    Plane {
+        position: [f32; 3],
+        #[serde(default = "default_axis")]
+        normal: [f32; 3],
+        material: String,
+    },
+    Disc {
+        position: [f32; 3],
+        #[serde(default = "default_axis")]
+        normal: [f32; 3],
+        radius: f32,
+        material: String,
+    },
+    Cylinder {
+        position: [f32; 3],
+        #[serde(default = "default_axis")]
+        axis: [f32; 3],
+        height: f32,
+        radius: f32,
+        material: String,
+    },
+    Cone {
+        position: [f32; 3],
+        #[serde(default = "default_axis")]
+        axis: [f32; 3],
+        height: f32,
+        radius: f32,
+        material: String,
+    },
+    Torus {
+        position: [f32; 3],
+        #[serde(default = "default_axis")]
+        axis: [f32; 3],
+        major_radius: f32,
+        minor_radius: f32,
+        material: String,
+    },
+    Sdf {
+        shape: SdfDescription,
+        material: String,
+    },
+    Mesh {
+        path: String,
+        #[serde(default)]
+        position: [f32; 3],
+        #[serde(default)]
+        rotation: [f32; 3],
+        #[serde(default = "default_scale")]
+        scale: [f32; 3],
+    },
+    PointLight {
+        position: [f32; 3],
+        intensity: [f32; 3],
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+enum SdfDescription {
+    Sphere {
+        centre: [f32; 3],
+        radius: f32,
+    },
+    Box {
+        centre: [f32; 3],
+        size: [f32; 3],
+    },
+    Capsule {
+        start: [f32; 3],
+        end: [f32; 3],
+        radius: f32,
+    },
+    Union(Box<SdfDescription>, Box<SdfDescription>),
+    Intersection(Box<SdfDescription>, Box<SdfDescription>),
+    Subtraction(Box<SdfDescription>, Box<SdfDescription>),
+    SmoothUnion(Box<SdfDescription>, Box<SdfDescription>, f32),
+}
+
+impl SdfDescription {
+    fn shape(self) -> SdfShape {
+        let pair = |a: Box<Self>, b: Box<Self>| (Box::new(a.shape()), Box::new(b.shape()));
+
+        match self {
+            SdfDescription::Sphere { centre, radius } => SdfShape::Sphere {
+                centre: centre.into(),
+                radius,
+            },
+            SdfDescription::Box { centre, size } => SdfShape::Box {
+                centre: centre.into(),
+                size: size.into(),
+            },
+            SdfDescription::Capsule { start, end, radius } => SdfShape::Capsule {
+                start: start.into(),
+                end: end.into(),
+                radius,
+            },
+            SdfDescription::Union(a, b) => {
+                let (a, b) = pair(a, b);
+                SdfShape::Union(a, b)
+            }
+            SdfDescription::Intersection(a, b) => {
+                let (a, b) = pair(a, b);
+                SdfShape::Intersection(a, b)
+            }
+            SdfDescription::Subtraction(a, b) => {
+                let (a, b) = pair(a, b);
+                SdfShape::Subtraction(a, b)
+            }
+            SdfDescription::SmoothUnion(a, b, radius) => {
+                let (a, b) = pair(a, b);
+                SdfShape::SmoothUnion(a, b, radius)
+            }
+        }
+    }
+}
+
+fn default_up() -> [f32; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+fn default_axis() -> [f32; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+fn default_fov() -> f32 {
+    70.0
+}
+
+fn default_colour() -> [f32; 3] {
+    [1.0; 3]
+}
+
+fn default_ior() -> f32 {
+    1.5
+}
+
+fn default_scale() -> [f32; 3] {
+    [1.0; 3]
+}
+
+fn default_turbidity() -> f32 {
+    3.0
+}
+
+fn default_strength() -> f32 {
+    1.0
+}
+
+/// Convert XYZ Euler angles in degrees to a rotation.
+fn rotation(degrees: [f32; 3]) -> Quat {
+    let [x, y, z] = degrees.map(f32::to_radians);
+    Quat::from_euler(EulerRot::XYZ, x, y, z)
+}
+
+/// Add the contents of a RON scene description to the scene.
+pub fn load(scene: &mut Scene, path: &Path) -> Result<(), LoadError> {
+    let directory = path.parent().unwrap_or(Path::new(""));
+    load_str(scene, &fs::read_to_string(path)?, directory)
+}
+
+/// Add the contents of a RON scene description to the scene, with any paths
+/// in it relative to `directory`.
+fn load_str(scene: &mut Scene, source: &str, directory: &Path) -> Result<(), LoadError> {
+    let description: SceneDescription = ron::Options::default()
+        .with_default_extension(Extensions::IMPLICIT_SOME)
+        .from_str(source)?;
+
+    let materials: HashMap<String, Material> = description
+        .materials
+        .into_iter()
+        .map(|(name, material)| (name, material.material()))
+        .collect();
+    let material = |name: &str| {
+        materials
+            .get(name)
+            .copied()
+            .ok_or_else(|| LoadError::UnknownMaterial(name.to_string()))
+    };
+
+    for object in description.objects {
+        match object {
+            ObjectDescription::Sphere {
+                position,
+                radius,
+                material: name,
+            } => scene.add_sphere(position.into(), radius, material(&name)?),
+            ObjectDescription::Box {
+                position,
+                rotation: degrees,
+                scale,
+                material: name,
+                spin,
+            } => {
+                let entity = scene.add_box(
+                    position.into(),
+                    scale.into(),
+                    rotation(degrees),
+                    material(&name)?,
+                );
+                if spin != [0.0; 3] {
+                    scene.set_spin(entity, Vec3::from(spin.map(f32::to_radians)));
+                }
+            }
+            ObjectDescription::AxisAlignedBox {
+                position,
+                size,
+                material: name,
+            } => scene.add_axis_aligned_box(position.into(), size.into(), material(&name)?),
+            ObjectDescription::Plane {
+                position,
+                normal,
+                material: name,
+            } => scene.add_plane(position.into(), normal.into(), material(&name)?),
+            ObjectDescription::Disc {
+                position,
+                normal,
+                radius,
+                material: name,
+            } => scene.add_disc(position.into(), normal.into(), radius, material(&name)?),
+            ObjectDescription::Cylinder {
+                position,
+                axis,
+                height,
+                radius,
+                material: name,
+            } => scene.add_cylinder(
+                position.into(),
+                axis.into(),
+                height,
+                radius,
+                material(&name)?,
+            ),
+            ObjectDescription::Cone {
+                position,
+                axis,
+                height,
+                radius,
+                material: name,
+            } => scene.add_cone(
+                position.into(),
+                axis.into(),
+                height,
+                radius,
+                material(&name)?,
+            ),
+            ObjectDescription::Torus {
+                position,
+                axis,
+                major_radius,
+                minor_radius,
+                material: name,
+            } => scene.add_torus(
+                position.into(),
+                axis.into(),
+                major_radius,
+                minor_radius,
+                material(&name)?,
+            ),
+            ObjectDescription::Sdf {
+                shape,
+                material: name,
+            } => {
+                let shape = shape.shape();
+                if shape.stack_depth() > MAX_SDF_STACK {
+                    return Err(LoadError::SdfTooDeep(shape.stack_depth()));
+                }
+                scene.add_sdf(shape, material(&name)?);
+            }
+            ObjectDescription::Mesh {
+                path,
+                position,
+                rotation: degrees,
+                scale,
+            } => {
+                let transform = Affine3A::from_scale_rotation_translation(
+                    scale.into(),
+                    rotation(degrees),
+                    position.into(),
+                );
+                super::load_meshes(scene, &directory.join(path), transform)?;
+            }
+            ObjectDescription::PointLight {
+                position,
+                intensity,
+            } => scene.add_point_light(position.into(), intensity.into()),
+        }
+    }
+
+    if let Some(environment) = description.environment {
+        scene.set_environment(match environment {
+            EnvironmentDescription::Constant { colour } => Environment::constant(colour.into()),
+            EnvironmentDescription::Sky {
+                sun_elevation,
+                sun_azimuth,
+                turbidity,
+                strength,
+            } => {
+                let (elevation, azimuth) = (sun_elevation.to_radians(), sun_azimuth.to_radians());
+                let sun_direction = Vec3::new(
+                    elevation.cos() * azimuth.sin(),
+                    elevation.sin(),
+                    -elevation.cos() * azimuth.cos(),
+                );
+                Environment::sky(sun_direction, turbidity, strength)
+            }
+            EnvironmentDescription::Map { path, strength } => {
+                let image = EnvironmentImage::load(&directory.join(path))?;
+                Environment::map(image, strength)
+            }
+        });
+    }
+
+    if let Some(camera) = description.camera {
+        scene.set_camera(CameraParameters {
+            position: Vec3A::from(camera.position),
+            target_position: Vec3A::from(camera.target),
+            up_direction: Vec3::from(camera.up).normalize().into(),
+            vertical_field_of_view_radians: camera.fov.to_radians(),
+            ..Default::default()
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenes;
+    use std::path::PathBuf;
+
+    fn load_source(source: &str) -> Result<Scene, LoadError> {
+        let mut scene = Scene::new();
+        load_str(&mut scene, source, Path::new("")).map(|_| scene)
+    }
+
+    #[test]
+    fn example_scenes_load() {
+        let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes");
+        let paths: Vec<PathBuf> = fs::read_dir(directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "ron"))
+            .collect();
+        assert!(!paths.is_empty());
+
+        for path in paths {
+            if let Err(err) = scenes::load(&path) {
+                panic!("Couldn't load {}: {}", path.display(), err);
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_material_is_an_error() {
+        let result = load_source(
+            r#"(
+                materials: { "grey": (colour: (0.8, 0.8, 0.8)) },
+                objects: [Sphere(position: (0.0, 0.0, 0.0), radius: 1.0, material: "gray")],
+            )"#,
+        );
+
+        assert!(matches!(result, Err(LoadError::UnknownMaterial(name)) if name == "gray"));
+    }
+
+    #[test]
+    fn unknown_object_field_is_an_error() {
+        let result = load_source(
+            r#"(
+                materials: { "grey": () },
+                objects: [
+                    Sphere(
+                        position: (0.0, 0.0, 0.0),
+                        radius: 1.0,
+                        material: "grey",
+                        spin: (0.0, 1.0, 0.0),
+                    ),
+                ],
+            )"#,
+        );
+
+        assert!(matches!(result, Err(LoadError::Ron(_))));
+    }
+
+    #[test]
+    fn too_deep_sdf_is_an_error() {
+        let mut shape = "Sphere(centre: (0.0, 0.0, 0.0), radius: 1.0)".to_string();
+        for _ in 0..MAX_SDF_STACK {
+            shape = format!(
+                "Union(Sphere(centre: (0.0, 0.0, 0.0), radius: 1.0), {})",
+                shape
+            );
+        }
+
+        let result = load_source(&format!(
+            r#"(materials: {{ "grey": () }}, objects: [Sdf(shape: {}, material: "grey")])"#,
+            shape
+        ));
+
+        assert!(matches!(result, Err(LoadError::SdfTooDeep(depth)) if depth == MAX_SDF_STACK + 1));
+    }
+}
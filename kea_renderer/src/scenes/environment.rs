@@ -0,0 +1,196 @@
+use super::LoadError;
+use glam::{uvec2, vec2, Vec2, Vec3, Vec3A};
+use kea_renderer_shaders::environments::{self, uv_to_direction, EnvironmentKind, EnvironmentMap};
+use std::{f32::consts::PI, path::Path};
+
+/// Resolution of the importance sampling table for environments without a
+/// map, which vary smoothly enough for it to be coarse.
+const DISTRIBUTION_SIZE: (u32, u32) = (64, 32);
+
+/// The light surrounding a scene, along with its environment map if it has
+/// one.
+pub struct Environment {
+    parameters: environments::Environment,
+    image: EnvironmentImage,
+}
+
+impl Environment {
+    pub fn constant(colour: Vec3) -> Self {
+        Self {
+            parameters: environments::Environment {
+                colour: colour.into(),
+                distribution_size: uvec2(DISTRIBUTION_SIZE.0, DISTRIBUTION_SIZE.1),
+                ..environments::Environment::BLACK
+            },
+            image: EnvironmentImage::black(),
+        }
+    }
+
+    /// A clear sky lit by the sun, with the brightness of the sky at the
+    /// zenith scaled to `strength`.
+    pub fn sky(sun_direction: Vec3, turbidity: f32, strength: f32) -> Self {
+        Self {
+            parameters: environments::Environment {
+                kind: EnvironmentKind::SKY,
+                colour: Vec3A::splat(strength),
+                sun_direction: sun_direction.normalize().into(),
+                turbidity,
+                distribution_size: uvec2(DISTRIBUTION_SIZE.0, DISTRIBUTION_SIZE.1),
+            },
+            image: EnvironmentImage::black(),
+        }
+    }
+
+    /// An equirectangular environment map, scaled by `strength`.
+    pub fn map(image: EnvironmentImage, strength: f32) -> Self {
+        Self {
+            parameters: environments::Environment {
+                kind: EnvironmentKind::MAP,
+                colour: Vec3A::splat(strength),
+                distribution_size: uvec2(image.size.0, image.size.1),
+                ..environments::Environment::BLACK
+            },
+            image,
+        }
+    }
+
+    /// Whether no light comes from the environment at all, so there's no
+    /// point sampling it.
+    pub fn is_black(&self) -> bool {
+        self.parameters.colour == Vec3A::ZERO
+    }
+
+    /// The environment as the shaders see it.
+    pub fn parameters(&self) -> environments::Environment {
+        self.parameters
+    }
+
+    /// The environment map, or a single black pixel if there isn't one.
+    pub fn image(&self) -> &EnvironmentImage {
+        &self.image
+    }
+
+    /// The table for importance sampling the environment: the cumulative
+    /// distribution over its rows, followed by the one within each row. Each
+    /// cell is weighted by its brightness and the solid angle it covers.
+    pub fn distribution(&self) -> Vec<f32> {
+        let size = self.parameters.distribution_size;
+        let (width, height) = (size.x as usize, size.y as usize);
+
+        let mut row_totals = Vec::with_capacity(height);
+        let mut rows = Vec::with_capacity(height * (width + 1));
+        for y in 0..height {
+            let v = (y as f32 + 0.5) / height as f32;
+            let sin_theta = (v * PI).sin();
+
+            let weights: Vec<f32> = (0..width)
+                .map(|x| {
+                    let uv = vec2((x as f32 + 0.5) / width as f32, v);
+                    let radiance = self.parameters.background(uv_to_direction(uv), &self.image);
+                    luminance(radiance) * sin_theta
+                })
+                .collect();
+
+            row_totals.push(weights.iter().map(|&weight| weight as f64).sum::<f64>() as f32);
+            rows.extend(cumulative_distribution(&weights));
+        }
+
+        let mut table = cumulative_distribution(&row_totals);
+        table.extend(rows);
+        table
+    }
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::constant(Vec3::ZERO)
+    }
+}
+
+/// An equirectangular environment map on the host, filtered the same way as
+/// the sampler on the GPU: bilinearly, wrapping around horizontally and
+/// clamping at the poles.
+#[derive(Clone)]
+pub struct EnvironmentImage {
+    size: (u32, u32),
+    pixels: Vec<[f32; 4]>,
+}
+
+impl EnvironmentImage {
+    /// Load an HDR image, such as a `.hdr` or `.exr` file.
+    pub fn load(path: &Path) -> Result<Self, LoadError> {
+        let image = image::open(path)?.into_rgba32f();
+
+        Ok(Self {
+            size: image.dimensions(),
+            pixels: image.pixels().map(|pixel| pixel.0).collect(),
+        })
+    }
+
+    pub fn black() -> Self {
+        Self {
+            size: (1, 1),
+            pixels: vec![[0.0, 0.0, 0.0, 1.0]],
+        }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// The pixels in row-major order, starting from the top.
+    pub fn pixels(&self) -> &[[f32; 4]] {
+        &self.pixels
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Vec3 {
+        let (width, height) = (self.size.0 as i64, self.size.1 as i64);
+        let x = x.rem_euclid(width);
+        let y = y.clamp(0, height - 1);
+        let [r, g, b, _] = self.pixels[(y * width + x) as usize];
+
+        Vec3::new(r, g, b)
+    }
+}
+
+impl EnvironmentMap for EnvironmentImage {
+    fn radiance(&self, uv: Vec2) -> Vec3 {
+        let x = uv.x * self.size.0 as f32 - 0.5;
+        let y = uv.y * self.size.1 as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = self.texel(x0, y0).lerp(self.texel(x0 + 1, y0), fx);
+        let bottom = self.texel(x0, y0 + 1).lerp(self.texel(x0 + 1, y0 + 1), fx);
+        top.lerp(bottom, fy)
+    }
+}
+
+fn luminance(colour: Vec3) -> f32 {
+    colour.dot(Vec3::new(0.2126, 0.7152, 0.0722))
+}
+
+/// The cumulative distribution of `weights`, from 0 to 1. All zero weights
+/// are treated as uniform instead.
+fn cumulative_distribution(weights: &[f32]) -> Vec<f32> {
+    let total: f64 = weights.iter().map(|&weight| weight as f64).sum();
+
+    let mut cdf = Vec::with_capacity(weights.len() + 1);
+    let mut sum = 0.0;
+    cdf.push(0.0);
+    for (index, &weight) in weights.iter().enumerate() {
+        sum += if total > 0.0 {
+            weight as f64 / total
+        } else {
+            1.0 / weights.len() as f64
+        };
+        cdf.push(if index + 1 == weights.len() {
+            1.0
+        } else {
+            sum as f32
+        });
+    }
+
+    cdf
+}
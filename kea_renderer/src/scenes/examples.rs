@@ -1,11 +1,9 @@
 use super::Scene;
-use kea_gpu::device::Device;
-use kea_renderer_shaders::materials::Material;
+use kea_renderer_shaders::{cameras::CameraParameters, materials::Material};
 use glam::{vec3, vec3a, Quat, Vec3A};
-use std::sync::Arc;
 
-// pub fn basic_shapes(device: Arc<Device>) -> Scene {
-//     let mut scene = Scene::new(device);
+// pub fn basic_shapes() -> Scene {
+//     let mut scene = Scene::new();
 
 //     scene.add_sphere(vec3(0.0, -1000.0, -1.5), 1000.0);
 //     scene.add_sphere(vec3(0.0, 0.8, -2.8), 0.8);
@@ -14,42 +12,25 @@ use std::sync::Arc;
 //     scene.add_box(vec3(1.1, 0.5, -1.8), vec3(1.0, 1.0, 1.0));
 //     scene.add_box(vec3(-0.1, 0.3, -1.7), vec3(0.6, 0.6, 0.6));
 
-//     scene.build_scene();
-
 //     scene
 // }
 
-pub fn cornell_box(device: Arc<Device>) -> Scene {
-    let mut scene = Scene::new(device);
-
-    let red = Material {
-        diffuse: vec3a(0.9, 0.2, 0.2),
-        emit: Vec3A::ZERO,
-    };
-    let green = Material {
-        diffuse: vec3a(0.2, 0.9, 0.2),
-        emit: Vec3A::ZERO,
-    };
-
-    let blue = Material {
-        diffuse: vec3a(0.1, 0.1, 0.9),
-        emit: Vec3A::ZERO,
-    };
+pub fn cornell_box() -> Scene {
+    let mut scene = Scene::new();
 
-    let light_grey = Material {
-        diffuse: Vec3A::splat(0.8),
-        emit: Vec3A::ZERO,
-    };
+    scene.set_camera(CameraParameters {
+        position: vec3a(0.0, 1.0, 1.5),
+        target_position: vec3a(0.0, 1.0, -1.0),
+        vertical_field_of_view_radians: 70.0_f32.to_radians(),
+        ..Default::default()
+    });
 
-    let dark_grey = Material {
-        diffuse: vec3a(0.3, 0.3, 0.3),
-        emit: Vec3A::ZERO,
-    };
-
-    let light = Material {
-        diffuse: Vec3A::splat(0.5),
-        emit: vec3a(1.0, 1.0, 0.7) * 0.1,
-    };
+    let red = Material::lambertian(vec3a(0.9, 0.2, 0.2));
+    let green = Material::lambertian(vec3a(0.2, 0.9, 0.2));
+    let blue = Material::lambertian(vec3a(0.1, 0.1, 0.9));
+    let light_grey = Material::lambertian(Vec3A::splat(0.8));
+    let dark_grey = Material::lambertian(vec3a(0.3, 0.3, 0.3));
+    let light = Material::lambertian(Vec3A::splat(0.5)).with_emit(vec3a(1.0, 1.0, 0.7) * 20.0);
 
     // Walls
     scene.add_box(
@@ -112,21 +93,13 @@ pub fn cornell_box(device: Arc<Device>) -> Scene {
     // scene.add_sphere(
     //     vec3(-0.5, 0.1, -0.5),
     //     0.1,
-    //     Material {
-    //         diffuse: vec3a(0.5, 0.5, 0.5),
-    //         emit: vec3a(0.54, 0.17, 0.89) * 0.02,
-    //     },
+    //     Material::lambertian(vec3a(0.5, 0.5, 0.5)).with_emit(vec3a(0.54, 0.17, 0.89) * 0.02),
     // );
     // scene.add_sphere(
     //     vec3(0.6, 0.15, -0.3),
     //     0.15,
-    //     Material {
-    //         diffuse: vec3a(0.5, 0.5, 0.5),
-    //         emit: vec3a(0.6, 0.3, 0.0) * 0.02,
-    //     },
+    //     Material::lambertian(vec3a(0.5, 0.5, 0.5)).with_emit(vec3a(0.6, 0.3, 0.0) * 0.02),
     // );
 
-    scene.build_scene();
-
     scene
 }
@@ -0,0 +1,259 @@
+use super::{vertices::generate_tangents, MeshHandle, Scene, TextureImage};
+use glam::{Affine3A, Mat4, Quat, Vec2, Vec3, Vec3A};
+use gltf::{buffer, camera::Projection, image, mesh::Mode, Node};
+use kea_renderer_shaders::{
+    cameras::CameraParameters,
+    materials::{Material, MaterialTextures, NO_TEXTURE},
+    triangles::Vertex,
+};
+use std::{collections::HashMap, path::Path};
+
+/// Add the meshes from a glTF 2.0 file (.gltf or .glb) to the scene, along
+/// with its first camera. Everything is placed relative to `transform`.
+///
+/// Only triangle primitives are imported, with their normals, tangents and
+/// first set of texture coordinates. Materials take their colour from the PBR
+/// base colour factor and emission from the emissive factor. Mostly metallic
+/// materials become GGX metals with the same roughness, and mostly
+/// transmissive ones glass with the material's IOR. The base colour, emissive,
+/// metallic roughness and normal textures are applied, all with the first set
+/// of texture coordinates. Meshes used by several nodes are added once and
+/// instanced.
+pub fn load(scene: &mut Scene, path: &Path, transform: Affine3A) -> Result<(), gltf::Error> {
+    let (document, buffers, images) = gltf::import(path)?;
+
+    let gltf_scene = document
+        .default_scene()
+        .or_else(|| document.scenes().next());
+    let Some(gltf_scene) = gltf_scene else {
+        log::warn!("{} contains no scenes", path.display());
+        return Ok(());
+    };
+
+    let mut importer = Importer {
+        scene,
+        buffers: &buffers,
+        images: &images,
+        textures: HashMap::new(),
+        primitives: HashMap::new(),
+        camera: None,
+    };
+    for node in gltf_scene.nodes() {
+        importer.load_node(&node, Mat4::from(transform));
+    }
+
+    if let Some(camera) = importer.camera {
+        scene.set_camera(camera);
+    }
+
+    Ok(())
+}
+
+/// What's needed while walking the nodes of a glTF scene.
+struct Importer<'a> {
+    scene: &'a mut Scene,
+    buffers: &'a [buffer::Data],
+    images: &'a [image::Data],
+    /// The scene's index for each image added to it so far, so images shared
+    /// between materials are only added once.
+    textures: HashMap<usize, u32>,
+    /// The mesh asset and material for each primitive added so far, by mesh
+    /// and primitive index, so meshes shared between nodes are instanced.
+    primitives: HashMap<(usize, usize), (MeshHandle, Material, MaterialTextures)>,
+    camera: Option<CameraParameters>,
+}
+
+impl Importer<'_> {
+    fn load_node(&mut self, node: &Node, parent_transform: Mat4) {
+        let transform = parent_transform * Mat4::from_cols_array_2d(&node.transform().matrix());
+        let (_, rotation, position) =
+            Affine3A::from_mat4(transform).to_scale_rotation_translation();
+
+        if let Some(mesh) = node.mesh() {
+            for primitive in mesh.primitives() {
+                if primitive.mode() != Mode::Triangles {
+                    log::warn!(
+                        "Skipping {:?} primitive in mesh {}",
+                        primitive.mode(),
+                        mesh.name().unwrap_or("unnamed")
+                    );
+                    continue;
+                }
+
+                let key = (mesh.index(), primitive.index());
+                let instance = match self.primitives.get(&key) {
+                    Some(&instance) => Some(instance),
+                    None => self.load_primitive(&primitive),
+                };
+                let Some((asset, material, textures)) = instance else {
+                    continue;
+                };
+                self.primitives.insert(key, (asset, material, textures));
+
+                self.scene.add_mesh_instance(
+                    asset,
+                    Affine3A::from_mat4(transform),
+                    material,
+                    textures,
+                );
+            }
+        }
+
+        if let (None, Some(node_camera)) = (self.camera.as_ref(), node.camera()) {
+            if let Projection::Perspective(perspective) = node_camera.projection() {
+                self.camera = Some(camera_parameters(position, rotation, perspective.yfov()));
+            }
+        }
+
+        for child in node.children() {
+            self.load_node(&child, transform);
+        }
+    }
+
+    /// Add a primitive as a mesh asset, returning it with its material.
+    fn load_primitive(
+        &mut self,
+        primitive: &gltf::Primitive,
+    ) -> Option<(MeshHandle, Material, MaterialTextures)> {
+        let reader = primitive.reader(|buffer| Some(&self.buffers[buffer.index()]));
+        let positions = reader.read_positions()?;
+        let vertices: Vec<Vec3A> = positions.map(Vec3A::from).collect();
+
+        let indices: Vec<u32> = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0..vertices.len() as u32).collect(),
+        };
+        let indices: Vec<[u32; 3]> = indices
+            .chunks_exact(3)
+            .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+            .collect();
+
+        let mut attributes = vec![Vertex::FLAT; vertices.len()];
+        if let Some(normals) = reader.read_normals() {
+            for (vertex, normal) in attributes.iter_mut().zip(normals) {
+                vertex.normal = Vec3A::from(normal).normalize_or_zero();
+            }
+        }
+        if let Some(uvs) = reader.read_tex_coords(0) {
+            for (vertex, uv) in attributes.iter_mut().zip(uvs.into_f32()) {
+                vertex.uv = Vec2::from(uv);
+            }
+        }
+
+        let textures = self.material_textures(&primitive.material());
+        match reader.read_tangents() {
+            Some(tangents) => {
+                for (vertex, [x, y, z, w]) in attributes.iter_mut().zip(tangents) {
+                    vertex.tangent = Vec3A::new(x, y, z);
+                    vertex.bitangent_sign = w;
+                }
+            }
+            None if textures.normal != NO_TEXTURE => {
+                generate_tangents(&vertices, &indices, &mut attributes);
+            }
+            None => {}
+        }
+
+        let mesh = self.scene.add_mesh_asset(vertices, attributes, indices);
+        Some((mesh, material(&primitive.material()), textures))
+    }
+
+    fn material_textures(&mut self, material: &gltf::Material) -> MaterialTextures {
+        let pbr = material.pbr_metallic_roughness();
+
+        MaterialTextures {
+            base_colour: self.texture(pbr.base_color_texture().map(|info| info.texture())),
+            emissive: self.texture(material.emissive_texture().map(|info| info.texture())),
+            roughness: self.texture(pbr.metallic_roughness_texture().map(|info| info.texture())),
+            normal: self.texture(material.normal_texture().map(|info| info.texture())),
+        }
+    }
+
+    /// The scene's index for a texture, adding its image to the scene the
+    /// first time it's used.
+    fn texture(&mut self, texture: Option<gltf::Texture>) -> u32 {
+        let Some(texture) = texture else {
+            return NO_TEXTURE;
+        };
+        let index = texture.source().index();
+
+        *self
+            .textures
+            .entry(index)
+            .or_insert_with(|| self.scene.add_texture(texture_image(&self.images[index])))
+    }
+}
+
+/// Convert an image to 8-bit RGBA. Single channel images are greyscale, and
+/// two channel ones greyscale with alpha.
+fn texture_image(data: &image::Data) -> TextureImage {
+    let (channels, channel_size) = match data.format {
+        image::Format::R8 => (1, 1),
+        image::Format::R8G8 => (2, 1),
+        image::Format::R8G8B8 => (3, 1),
+        image::Format::R8G8B8A8 => (4, 1),
+        image::Format::R16 => (1, 2),
+        image::Format::R16G16 => (2, 2),
+        image::Format::R16G16B16 => (3, 2),
+        image::Format::R16G16B16A16 => (4, 2),
+        image::Format::R32G32B32FLOAT => (3, 4),
+        image::Format::R32G32B32A32FLOAT => (4, 4),
+    };
+
+    let channel = |bytes: &[u8]| match channel_size {
+        1 => bytes[0],
+        2 => (u16::from_ne_bytes([bytes[0], bytes[1]]) >> 8) as u8,
+        _ => {
+            let value = f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            (value.clamp(0.0, 1.0) * 255.0).round() as u8
+        }
+    };
+
+    let bytes: Vec<u8> = data
+        .pixels
+        .chunks_exact(channels * channel_size)
+        .flat_map(|pixel| {
+            let values: Vec<u8> = pixel.chunks_exact(channel_size).map(channel).collect();
+            match values[..] {
+                [grey] => [grey, grey, grey, u8::MAX],
+                [grey, alpha] => [grey, grey, grey, alpha],
+                [r, g, b] => [r, g, b, u8::MAX],
+                [r, g, b, a] => [r, g, b, a],
+                _ => unreachable!(),
+            }
+        })
+        .collect();
+
+    TextureImage::from_rgba8((data.width, data.height), &bytes)
+}
+
+fn material(material: &gltf::Material) -> Material {
+    let pbr = material.pbr_metallic_roughness();
+    let [r, g, b, _] = pbr.base_color_factor();
+    let base_colour = Vec3A::new(r, g, b);
+    let transmission = material
+        .transmission()
+        .map_or(0.0, |transmission| transmission.transmission_factor());
+    let emissive_strength = material.emissive_strength().unwrap_or(1.0);
+
+    let scattering = if transmission >= 0.5 {
+        Material::dielectric(base_colour, material.ior().unwrap_or(1.5))
+    } else if pbr.metallic_factor() >= 0.5 {
+        Material::metal(base_colour, pbr.roughness_factor())
+    } else {
+        Material::lambertian(base_colour)
+    };
+
+    scattering.with_emit(Vec3A::from(material.emissive_factor()) * emissive_strength)
+}
+
+/// glTF cameras look down their local -Z axis, with +Y up.
+fn camera_parameters(position: Vec3, rotation: Quat, yfov: f32) -> CameraParameters {
+    CameraParameters {
+        position: position.into(),
+        target_position: (position + rotation * Vec3::NEG_Z).into(),
+        up_direction: (rotation * Vec3::Y).into(),
+        vertical_field_of_view_radians: yfov,
+        ..Default::default()
+    }
+}
@@ -1,4 +1,125 @@
+mod description;
+mod environment;
 pub mod examples;
+pub mod gltf;
+pub mod obj;
 mod scene;
+mod sdf;
+mod texture;
+mod vertices;
 
-pub use scene::Scene;
+pub use environment::{Environment, EnvironmentImage};
+pub use scene::{Mesh, MeshHandle, Scene};
+pub use sdf::SdfShape;
+pub use texture::TextureImage;
+
+use glam::Affine3A;
+use kea_renderer_shaders::sdfs::MAX_SDF_STACK;
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug)]
+pub enum LoadError {
+    UnsupportedFormat(PathBuf),
+    UnknownMaterial(String),
+    /// An SDF nested too deeply for the shaders' evaluation stack, with the
+    /// stack size it needs.
+    SdfTooDeep(usize),
+    Io(io::Error),
+    Ron(ron::error::SpannedError),
+    Gltf(::gltf::Error),
+    Obj(tobj::LoadError),
+    Image(image::ImageError),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::UnsupportedFormat(path) => {
+                write!(f, "don't know how to load {}", path.display())
+            }
+            LoadError::UnknownMaterial(name) => write!(f, "unknown material '{}'", name),
+            LoadError::SdfTooDeep(depth) => write!(
+                f,
+                "SDF needs a stack of {}, but at most {} is supported",
+                depth, MAX_SDF_STACK
+            ),
+            LoadError::Io(err) => write!(f, "{}", err),
+            LoadError::Ron(err) => write!(f, "invalid scene description: {}", err),
+            LoadError::Gltf(err) => write!(f, "couldn't load glTF: {}", err),
+            LoadError::Obj(err) => write!(f, "couldn't load OBJ: {}", err),
+            LoadError::Image(err) => write!(f, "couldn't load image: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(err: io::Error) -> Self {
+        LoadError::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for LoadError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        LoadError::Ron(err)
+    }
+}
+
+impl From<::gltf::Error> for LoadError {
+    fn from(err: ::gltf::Error) -> Self {
+        LoadError::Gltf(err)
+    }
+}
+
+impl From<tobj::LoadError> for LoadError {
+    fn from(err: tobj::LoadError) -> Self {
+        LoadError::Obj(err)
+    }
+}
+
+impl From<image::ImageError> for LoadError {
+    fn from(err: image::ImageError) -> Self {
+        LoadError::Image(err)
+    }
+}
+
+/// Load a scene from a file, choosing the importer by its extension. RON files
+/// are scene descriptions; glTF and OBJ files are imported as they are.
+pub fn load(path: &Path) -> Result<Scene, LoadError> {
+    let mut scene = Scene::new();
+    match extension(path).as_deref() {
+        Some("ron") => description::load(&mut scene, path)?,
+        _ => load_meshes(&mut scene, path, Affine3A::IDENTITY)?,
+    }
+
+    Ok(scene)
+}
+
+/// Whether `load` knows how to load the file at `path`.
+pub fn is_scene_file(path: &Path) -> bool {
+    matches!(
+        extension(path).as_deref(),
+        Some("ron" | "gltf" | "glb" | "obj")
+    )
+}
+
+/// Add the meshes from a glTF or OBJ file to the scene.
+fn load_meshes(scene: &mut Scene, path: &Path, transform: Affine3A) -> Result<(), LoadError> {
+    match extension(path).as_deref() {
+        Some("gltf" | "glb") => gltf::load(scene, path, transform)?,
+        Some("obj") => obj::load(scene, path, transform)?,
+        _ => return Err(LoadError::UnsupportedFormat(path.to_owned())),
+    }
+
+    Ok(())
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase())
+}
@@ -0,0 +1,175 @@
+use super::{vertices::generate_tangents, Scene, TextureImage};
+use glam::{vec2, vec3a, Affine3A, Vec3A};
+use kea_renderer_shaders::{
+    materials::{Material, MaterialTextures, NO_TEXTURE},
+    triangles::Vertex,
+};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// Add the meshes from a Wavefront OBJ file to the scene, with materials from
+/// the MTL files it references. The meshes are placed at `transform`.
+///
+/// Polygons are triangulated, and each object is split into one mesh per
+/// material. Materials take their colour from `Kd` and emission from `Ke`.
+/// Illumination models with refraction (4, 6, 7 and 9) become glass with an
+/// IOR of `Ni`, model 3 becomes a mirror reflecting `Ks`, and materials with
+/// the PBR extension's `Pm` metallic value become GGX metals with a roughness
+/// of `Pr`.
+///
+/// Vertex normals and texture coordinates are imported, and so are the
+/// `map_Kd`, `map_Ke` and `map_Pr` textures, along with tangent space normal
+/// maps from `norm`. `bump` maps are ignored, as they're often height maps.
+pub fn load(scene: &mut Scene, path: &Path, transform: Affine3A) -> Result<(), tobj::LoadError> {
+    let options = tobj::LoadOptions {
+        single_index: true,
+        triangulate: true,
+        ignore_points: true,
+        ignore_lines: true,
+    };
+    let (models, materials) = tobj::load_obj(path, &options)?;
+
+    let directory = path.parent().unwrap_or(Path::new(""));
+    let mut textures = HashMap::new();
+    let materials: Vec<(Material, MaterialTextures)> = match materials {
+        Ok(materials) => materials
+            .iter()
+            .map(|obj_material| {
+                let textures = material_textures(scene, directory, &mut textures, obj_material);
+                (material(obj_material), textures)
+            })
+            .collect(),
+        Err(err) => {
+            log::warn!("Couldn't load materials for {}: {}", path.display(), err);
+            vec![]
+        }
+    };
+
+    for model in models {
+        let mesh = model.mesh;
+        if mesh.indices.is_empty() {
+            continue;
+        }
+
+        let vertices: Vec<Vec3A> = mesh
+            .positions
+            .chunks_exact(3)
+            .map(|position| vec3a(position[0], position[1], position[2]))
+            .collect();
+        let indices: Vec<[u32; 3]> = mesh
+            .indices
+            .chunks_exact(3)
+            .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+            .collect();
+
+        let (material, textures) = mesh
+            .material_id
+            .and_then(|id| materials.get(id))
+            .copied()
+            .unwrap_or((DEFAULT_MATERIAL, MaterialTextures::NONE));
+
+        let mut attributes = vec![Vertex::FLAT; vertices.len()];
+        for (vertex, normal) in attributes.iter_mut().zip(mesh.normals.chunks_exact(3)) {
+            vertex.normal = vec3a(normal[0], normal[1], normal[2]).normalize_or_zero();
+        }
+        // OBJ texture coordinates start from the bottom of the texture.
+        for (vertex, uv) in attributes.iter_mut().zip(mesh.texcoords.chunks_exact(2)) {
+            vertex.uv = vec2(uv[0], 1.0 - uv[1]);
+        }
+        if textures.normal != NO_TEXTURE {
+            generate_tangents(&vertices, &indices, &mut attributes);
+        }
+
+        scene.add_textured_mesh(vertices, attributes, indices, transform, material, textures);
+    }
+
+    Ok(())
+}
+
+/// Used for faces without a material.
+const DEFAULT_MATERIAL: Material = Material::lambertian(Vec3A::splat(0.8));
+
+fn material(material: &tobj::Material) -> Material {
+    // Ke isn't part of the original MTL spec, so tobj leaves it unparsed.
+    let emit = material
+        .unknown_param
+        .get("Ke")
+        .and_then(|value| parse_colour(value))
+        .unwrap_or(Vec3A::ZERO);
+
+    let parameter = |name: &str| {
+        material
+            .unknown_param
+            .get(name)
+            .and_then(|value| value.trim().parse::<f32>().ok())
+    };
+    let diffuse = material
+        .diffuse
+        .map(Vec3A::from)
+        .unwrap_or(DEFAULT_MATERIAL.base_colour);
+
+    let scattering = match material.illumination_model {
+        Some(4 | 6 | 7 | 9) => {
+            Material::dielectric(Vec3A::ONE, material.optical_density.unwrap_or(1.5))
+        }
+        Some(3) => Material::mirror(material.specular.map(Vec3A::from).unwrap_or(Vec3A::ONE)),
+        _ if parameter("Pm").is_some_and(|metallic| metallic >= 0.5) => {
+            Material::metal(diffuse, parameter("Pr").unwrap_or(0.0))
+        }
+        _ => Material::lambertian(diffuse),
+    };
+
+    scattering.with_emit(emit)
+}
+
+fn material_textures(
+    scene: &mut Scene,
+    directory: &Path,
+    textures: &mut HashMap<PathBuf, u32>,
+    material: &tobj::Material,
+) -> MaterialTextures {
+    let mut texture = |name: Option<&String>| {
+        // Options such as `-bm 1.0` come before the file name.
+        let Some(file_name) = name.and_then(|name| name.split_whitespace().last()) else {
+            return NO_TEXTURE;
+        };
+        let path = directory.join(file_name);
+        if let Some(&index) = textures.get(&path) {
+            return index;
+        }
+
+        match TextureImage::load(&path) {
+            Ok(image) => {
+                let index = scene.add_texture(image);
+                textures.insert(path, index);
+                index
+            }
+            Err(err) => {
+                log::warn!("Couldn't load texture {}: {}", path.display(), err);
+                NO_TEXTURE
+            }
+        }
+    };
+
+    MaterialTextures {
+        base_colour: texture(material.diffuse_texture.as_ref()),
+        emissive: texture(material.unknown_param.get("map_Ke")),
+        roughness: texture(material.unknown_param.get("map_Pr")),
+        normal: texture(material.unknown_param.get("norm")),
+    }
+}
+
+fn parse_colour(value: &str) -> Option<Vec3A> {
+    let channels: Vec<f32> = value
+        .split_whitespace()
+        .map(|channel| channel.parse().ok())
+        .collect::<Option<_>>()?;
+
+    match channels[..] {
+        [r, g, b] => Some(vec3a(r, g, b)),
+        [grey] => Some(Vec3A::splat(grey)),
+        _ => None,
+    }
+}
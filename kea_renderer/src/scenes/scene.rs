@@ -1,23 +1,56 @@
+use super::{Environment, SdfShape, TextureImage};
 use ash::vk;
 use bevy_ecs::prelude::*;
 use gpu_allocator::MemoryLocation;
 use kea_gpu::{
+    commands::CommandBufferRecorder,
     device::Device,
-    ray_tracing::scenes::{Geometry, GeometryInstance, GeometryType},
+    presentation::FRAMES_IN_FLIGHT,
+    ray_tracing::scenes::{Geometry, GeometryInstance, GeometryType, SceneBuilder},
     slots::SlotBindings,
-    storage::buffers::Buffer,
+    storage::{
+        buffers::Buffer,
+        images::{Image, ImageView, Sampler},
+    },
 };
 use kea_gpu_shaderlib::Aabb;
-use kea_renderer_shaders::SlotId;
-use glam::{vec3a, Affine3A, Quat, Vec3, Vec3A};
+use kea_renderer_shaders::{
+    cameras::CameraParameters,
+    lights::Light,
+    materials::{MaterialTextures, MAX_TEXTURES},
+    primitives::Primitive,
+    sdfs::SdfNode,
+    triangles::{Vertex, MAX_MESHES},
+    SlotId,
+};
+use glam::{vec3a, Affine3A, EulerRot, Quat, Vec3, Vec3A};
 use std::sync::Arc;
 
 pub struct Scene {
-    device: Arc<Device>,
     world: World,
     gpu_scene: Option<kea_gpu::ray_tracing::scenes::Scene>,
     spheres: Option<Arc<Buffer>>,
+    /// The storage buffer of each kind of analytic primitive in the scene.
+    primitive_buffers: Vec<(SlotId, Arc<Buffer>)>,
+    /// The SDFs in the scene, and the nodes of their expression trees.
+    sdfs: Option<(Arc<Buffer>, Arc<Buffer>)>,
     meshes: Option<Arc<Buffer>>,
+    /// The vertex and index buffers of each mesh asset, by asset ID.
+    mesh_buffers: Vec<(Arc<Buffer>, Arc<Buffer>)>,
+    texture_images: Vec<Arc<ImageView>>,
+    texture_sampler: Option<Arc<Sampler>>,
+    lights: Option<Arc<Buffer>>,
+    /// The staging buffers of light updates recorded for each frame in
+    /// flight, kept until the frame has finished with them.
+    light_uploads: Vec<Option<Buffer>>,
+    environment_buffers: Option<(Arc<Buffer>, Arc<Buffer>)>,
+    environment_map: Option<(Arc<ImageView>, Arc<Sampler>)>,
+    camera: CameraParameters,
+    environment: Environment,
+    textures: Vec<TextureImage>,
+    mesh_assets: Vec<Mesh>,
+    /// The mesh every box is an instance of, once there's been a box.
+    unit_cube: Option<MeshHandle>,
 }
 
 #[derive(Component)]
@@ -34,26 +67,177 @@ pub struct Sphere {
     radius: f32,
 }
 
+/// A box centred on the entity's position, with its faces along the world
+/// axes.
+#[derive(Component)]
+pub struct AxisAlignedBox {
+    size: Vec3,
+}
+
+/// An infinite plane through the entity's position.
+#[derive(Component)]
+pub struct Plane {
+    normal: Vec3,
+}
+
+/// A flat disc centred on the entity's position.
+#[derive(Component)]
+pub struct Disc {
+    normal: Vec3,
+    radius: f32,
+}
+
+/// A capped cylinder standing on the entity's position.
+#[derive(Component)]
+pub struct Cylinder {
+    axis: Vec3,
+    height: f32,
+    radius: f32,
+}
+
+/// A capped cone with its base on the entity's position, narrowing to a
+/// point along its axis.
+#[derive(Component)]
+pub struct Cone {
+    axis: Vec3,
+    height: f32,
+    radius: f32,
+}
+
+/// A torus centred on the entity's position, around its axis.
+#[derive(Component)]
+pub struct Torus {
+    axis: Vec3,
+    major_radius: f32,
+    minor_radius: f32,
+}
+
+/// A shape defined by a signed distance function, in world space.
+#[derive(Component)]
+pub struct Sdf(pub SdfShape);
+
 #[derive(Component)]
 pub struct Material(pub kea_renderer_shaders::materials::Material);
 
 #[derive(Component)]
+pub struct Textures(pub MaterialTextures);
+
+#[derive(Component)]
+pub struct PointLight {
+    intensity: Vec3,
+}
+
+/// The vertices and triangles of a mesh asset, shared by every entity with a
+/// `MeshHandle` to it.
 pub struct Mesh {
     vertices: Vec<Vec3A>,
+    /// The shading attributes of each vertex.
+    attributes: Vec<Vertex>,
     indices: Vec<[u32; 3]>,
 }
 
+impl Mesh {
+    pub fn vertices(&self) -> &[Vec3A] {
+        &self.vertices
+    }
+
+    pub fn attributes(&self) -> &[Vertex] {
+        &self.attributes
+    }
+
+    pub fn indices(&self) -> &[[u32; 3]] {
+        &self.indices
+    }
+}
+
+/// Makes an entity an instance of one of the scene's mesh assets, by asset
+/// ID. The GPU scene builds one acceleration structure for each asset, however
+/// many entities share it.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MeshHandle(pub u32);
+
+/// Turns an entity about its own origin, in radians per second about each of
+/// its axes.
+#[derive(Component)]
+pub struct Spin(pub Vec3);
+
+/// The index of an entity's instance in the GPU scene, which follows its
+/// transform.
+#[derive(Component)]
+pub struct InstanceIndex(pub usize);
+
+/// The analytic primitives in the scene, as the shaders see them.
+#[derive(Default)]
+pub struct Primitives {
+    pub boxes: Vec<kea_renderer_shaders::primitives::AxisAlignedBox>,
+    pub planes: Vec<kea_renderer_shaders::primitives::Plane>,
+    pub discs: Vec<kea_renderer_shaders::primitives::Disc>,
+    pub cylinders: Vec<kea_renderer_shaders::primitives::Cylinder>,
+    pub cones: Vec<kea_renderer_shaders::primitives::Cone>,
+    pub tori: Vec<kea_renderer_shaders::primitives::Torus>,
+}
+
+/// A triangle mesh entity, gathered from the world.
+pub struct MeshInstance<'a> {
+    pub entity: Entity,
+    pub mesh: MeshHandle,
+    pub vertices: &'a [Vec3A],
+    pub attributes: &'a [Vertex],
+    pub indices: &'a [[u32; 3]],
+    pub transform: Affine3A,
+    pub material: kea_renderer_shaders::materials::Material,
+    pub textures: MaterialTextures,
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Scene {
-    pub fn new(device: Arc<Device>) -> Self {
+    pub fn new() -> Self {
         Self {
-            device,
             world: World::new(),
             gpu_scene: None,
             spheres: None,
+            primitive_buffers: vec![],
+            sdfs: None,
             meshes: None,
+            mesh_buffers: vec![],
+            texture_images: vec![],
+            texture_sampler: None,
+            lights: None,
+            light_uploads: vec![],
+            environment_buffers: None,
+            environment_map: None,
+            camera: CameraParameters::default(),
+            environment: Environment::default(),
+            textures: vec![],
+            mesh_assets: vec![],
+            unit_cube: None,
         }
     }
 
+    /// The camera the scene is initially viewed from. The aspect ratio is
+    /// ignored, as it's taken from the output size.
+    pub fn camera(&self) -> CameraParameters {
+        self.camera
+    }
+
+    pub fn set_camera(&mut self, camera: CameraParameters) {
+        self.camera = camera;
+    }
+
+    /// The light arriving from outside the scene. Black unless set.
+    pub fn environment(&self) -> &Environment {
+        &self.environment
+    }
+
+    pub fn set_environment(&mut self, environment: Environment) {
+        self.environment = environment;
+    }
+
     pub fn add_sphere(
         &mut self,
         position: Vec3,
@@ -64,13 +248,135 @@ impl Scene {
             .spawn((Position(position), Sphere { radius }, Material(material)));
     }
 
+    pub fn add_axis_aligned_box(
+        &mut self,
+        centre: Vec3,
+        size: Vec3,
+        material: kea_renderer_shaders::materials::Material,
+    ) {
+        self.world.spawn((
+            Position(centre),
+            AxisAlignedBox { size },
+            Material(material),
+        ));
+    }
+
+    pub fn add_plane(
+        &mut self,
+        point: Vec3,
+        normal: Vec3,
+        material: kea_renderer_shaders::materials::Material,
+    ) {
+        self.world
+            .spawn((Position(point), Plane { normal }, Material(material)));
+    }
+
+    pub fn add_disc(
+        &mut self,
+        centre: Vec3,
+        normal: Vec3,
+        radius: f32,
+        material: kea_renderer_shaders::materials::Material,
+    ) {
+        self.world.spawn((
+            Position(centre),
+            Disc { normal, radius },
+            Material(material),
+        ));
+    }
+
+    pub fn add_cylinder(
+        &mut self,
+        base: Vec3,
+        axis: Vec3,
+        height: f32,
+        radius: f32,
+        material: kea_renderer_shaders::materials::Material,
+    ) {
+        self.world.spawn((
+            Position(base),
+            Cylinder {
+                axis,
+                height,
+                radius,
+            },
+            Material(material),
+        ));
+    }
+
+    pub fn add_cone(
+        &mut self,
+        base: Vec3,
+        axis: Vec3,
+        height: f32,
+        radius: f32,
+        material: kea_renderer_shaders::materials::Material,
+    ) {
+        self.world.spawn((
+            Position(base),
+            Cone {
+                axis,
+                height,
+                radius,
+            },
+            Material(material),
+        ));
+    }
+
+    pub fn add_torus(
+        &mut self,
+        centre: Vec3,
+        axis: Vec3,
+        major_radius: f32,
+        minor_radius: f32,
+        material: kea_renderer_shaders::materials::Material,
+    ) {
+        self.world.spawn((
+            Position(centre),
+            Torus {
+                axis,
+                major_radius,
+                minor_radius,
+            },
+            Material(material),
+        ));
+    }
+
+    /// Add a shape traced by its signed distance function. Its stack depth
+    /// must be at most `MAX_SDF_STACK`, or the scene will panic when built.
+    pub fn add_sdf(
+        &mut self,
+        shape: SdfShape,
+        material: kea_renderer_shaders::materials::Material,
+    ) {
+        self.world.spawn((Sdf(shape), Material(material)));
+    }
+
     pub fn add_box(
         &mut self,
         position: Vec3,
         scale: Vec3,
         rotation: Quat,
         material: kea_renderer_shaders::materials::Material,
-    ) {
+    ) -> Entity {
+        let cube = self.unit_cube();
+        self.spawn_mesh(
+            cube,
+            position,
+            scale,
+            rotation,
+            material,
+            MaterialTextures::NONE,
+        )
+    }
+
+    /// The unit cube centred on the origin, added as an asset the first time
+    /// it's needed.
+    fn unit_cube(&mut self) -> MeshHandle {
+        if let Some(cube) = self.unit_cube {
+            return cube;
+        }
+
         let vertices = vec![
             vec3a(0.5, -0.5, 0.5),
             vec3a(0.5, -0.5, -0.5),
@@ -96,33 +402,340 @@ impl Scene {
             [0, 4, 5],
         ];
 
-        self.world.spawn((
-            Position(position),
-            Scale(scale),
-            Material(material),
-            Rotation(rotation),
-            Mesh { vertices, indices },
-        ));
+        let attributes = vec![Vertex::FLAT; vertices.len()];
+        let cube = self.add_mesh_asset(vertices, attributes, indices);
+        self.unit_cube = Some(cube);
+        cube
     }
 
-    pub fn build_scene(&mut self) {
-        let mut scene = kea_gpu::ray_tracing::scenes::Scene::new(
-            self.device.clone(),
-            "kea renderer scene".to_string(),
-        );
+    /// Add a mesh with shading attributes for each vertex, and textures from
+    /// `add_texture` varying its material across it.
+    pub fn add_textured_mesh(
+        &mut self,
+        vertices: Vec<Vec3A>,
+        attributes: Vec<Vertex>,
+        indices: Vec<[u32; 3]>,
+        transform: Affine3A,
+        material: kea_renderer_shaders::materials::Material,
+        textures: MaterialTextures,
+    ) {
+        let mesh = self.add_mesh_asset(vertices, attributes, indices);
+        self.add_mesh_instance(mesh, transform, material, textures);
+    }
+
+    /// Add a mesh asset, with shading attributes for each vertex, for any
+    /// number of entities to be instances of.
+    pub fn add_mesh_asset(
+        &mut self,
+        vertices: Vec<Vec3A>,
+        attributes: Vec<Vertex>,
+        indices: Vec<[u32; 3]>,
+    ) -> MeshHandle {
+        assert_eq!(vertices.len(), attributes.len());
+        self.mesh_assets.push(Mesh {
+            vertices,
+            attributes,
+            indices,
+        });
+
+        MeshHandle((self.mesh_assets.len() - 1) as u32)
+    }
 
-        let spheres: Vec<kea_renderer_shaders::spheres::Sphere> = self
-            .world
+    /// Add an instance of a mesh asset, with textures from `add_texture`
+    /// varying its material across it.
+    pub fn add_mesh_instance(
+        &mut self,
+        mesh: MeshHandle,
+        transform: Affine3A,
+        material: kea_renderer_shaders::materials::Material,
+        textures: MaterialTextures,
+    ) -> Entity {
+        let (scale, rotation, position) = transform.to_scale_rotation_translation();
+
+        self.spawn_mesh(mesh, position, scale, rotation, material, textures)
+    }
+
+    fn spawn_mesh(
+        &mut self,
+        mesh: MeshHandle,
+        position: Vec3,
+        scale: Vec3,
+        rotation: Quat,
+        material: kea_renderer_shaders::materials::Material,
+        textures: MaterialTextures,
+    ) -> Entity {
+        self.world
+            .spawn((
+                Position(position),
+                Scale(scale),
+                Material(material),
+                Textures(textures),
+                Rotation(rotation),
+                mesh,
+            ))
+            .id()
+    }
+
+    /// Add a texture for meshes to use, returning its index.
+    pub fn add_texture(&mut self, image: TextureImage) -> u32 {
+        self.textures.push(image);
+        (self.textures.len() - 1) as u32
+    }
+
+    /// Add a light emitting `intensity` watts per steradian in every direction
+    /// from `position`.
+    pub fn add_point_light(&mut self, position: Vec3, intensity: Vec3) {
+        self.world
+            .spawn((Position(position), PointLight { intensity }));
+    }
+
+    /// The spheres in the scene, as the shaders see them.
+    pub fn spheres(&mut self) -> Vec<kea_renderer_shaders::spheres::Sphere> {
+        self.world
             .query::<(&Position, &Sphere, &Material)>()
             .iter(&self.world)
             .map(|(position, sphere, material)| {
                 kea_renderer_shaders::spheres::Sphere::new(position.0, sphere.radius, material.0)
             })
+            .collect()
+    }
+
+    pub fn primitives(&mut self) -> Primitives {
+        use kea_renderer_shaders::primitives;
+
+        let world = &mut self.world;
+        Primitives {
+            boxes: world
+                .query::<(&Position, &AxisAlignedBox, &Material)>()
+                .iter(world)
+                .map(|(position, shape, material)| {
+                    let half_size = shape.size / 2.0;
+                    primitives::AxisAlignedBox::new(
+                        position.0 - half_size,
+                        position.0 + half_size,
+                        material.0,
+                    )
+                })
+                .collect(),
+            planes: world
+                .query::<(&Position, &Plane, &Material)>()
+                .iter(world)
+                .map(|(position, shape, material)| {
+                    primitives::Plane::new(position.0, shape.normal, material.0)
+                })
+                .collect(),
+            discs: world
+                .query::<(&Position, &Disc, &Material)>()
+                .iter(world)
+                .map(|(position, shape, material)| {
+                    primitives::Disc::new(position.0, shape.normal, shape.radius, material.0)
+                })
+                .collect(),
+            cylinders: world
+                .query::<(&Position, &Cylinder, &Material)>()
+                .iter(world)
+                .map(|(position, shape, material)| {
+                    primitives::Cylinder::new(
+                        position.0,
+                        shape.axis,
+                        shape.height,
+                        shape.radius,
+                        material.0,
+                    )
+                })
+                .collect(),
+            cones: world
+                .query::<(&Position, &Cone, &Material)>()
+                .iter(world)
+                .map(|(position, shape, material)| {
+                    primitives::Cone::new(
+                        position.0,
+                        shape.axis,
+                        shape.height,
+                        shape.radius,
+                        material.0,
+                    )
+                })
+                .collect(),
+            tori: world
+                .query::<(&Position, &Torus, &Material)>()
+                .iter(world)
+                .map(|(position, shape, material)| {
+                    primitives::Torus::new(
+                        position.0,
+                        shape.axis,
+                        shape.major_radius,
+                        shape.minor_radius,
+                        material.0,
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// The SDFs in the scene as the shaders see them, and all the nodes of
+    /// their expression trees.
+    pub fn sdfs(&mut self) -> (Vec<kea_renderer_shaders::sdfs::Sdf>, Vec<SdfNode>) {
+        let mut sdfs = vec![];
+        let mut nodes = vec![];
+        for (sdf, material) in self.world.query::<(&Sdf, &Material)>().iter(&self.world) {
+            let shape_nodes = sdf.0.nodes();
+            sdfs.push(kea_renderer_shaders::sdfs::Sdf::new(
+                nodes.len() as u32,
+                shape_nodes.len() as u32,
+                sdf.0.aabb(),
+                material.0,
+            ));
+            nodes.extend(shape_nodes);
+        }
+
+        (sdfs, nodes)
+    }
+
+    pub fn meshes(&mut self) -> Vec<MeshInstance<'_>> {
+        let assets = &self.mesh_assets;
+        self.world
+            .query::<(
+                Entity,
+                &MeshHandle,
+                &Position,
+                &Scale,
+                &Rotation,
+                &Material,
+                &Textures,
+            )>()
+            .iter(&self.world)
+            .map(
+                |(entity, &handle, position, scale, rotation, material, textures)| {
+                    let mesh = &assets[handle.0 as usize];
+                    MeshInstance {
+                        entity,
+                        mesh: handle,
+                        vertices: &mesh.vertices,
+                        attributes: &mesh.attributes,
+                        indices: &mesh.indices,
+                        transform: Affine3A::from_scale_rotation_translation(
+                            scale.0, rotation.0, position.0,
+                        ),
+                        material: material.0,
+                        textures: textures.0,
+                    }
+                },
+            )
+            .collect()
+    }
+
+    /// Every mesh asset in the scene, by asset ID.
+    pub fn mesh_assets(&self) -> &[Mesh] {
+        &self.mesh_assets
+    }
+
+    /// The bounds of everything in the scene besides meshes and planes, which
+    /// have no bounds, with its material.
+    pub fn bounded_shapes(&mut self) -> Vec<(Aabb, kea_renderer_shaders::materials::Material)> {
+        let mut shapes: Vec<_> = self
+            .spheres()
+            .iter()
+            .map(|sphere| (sphere.aabb(), sphere.material()))
+            .collect();
+
+        let primitives = self.primitives();
+        let primitives = primitives
+            .boxes
+            .iter()
+            .map(|shape| shape as &dyn Primitive)
+            .chain(primitives.discs.iter().map(|shape| shape as &dyn Primitive))
+            .chain(
+                primitives
+                    .cylinders
+                    .iter()
+                    .map(|shape| shape as &dyn Primitive),
+            )
+            .chain(primitives.cones.iter().map(|shape| shape as &dyn Primitive))
+            .chain(primitives.tori.iter().map(|shape| shape as &dyn Primitive));
+        shapes.extend(primitives.map(|shape| (shape.aabb(), shape.material())));
+
+        let (sdfs, _) = self.sdfs();
+        shapes.extend(sdfs.iter().map(|sdf| (sdf.aabb(), sdf.material())));
+
+        shapes
+    }
+
+    /// Every texture in the scene, by texture ID.
+    pub fn textures(&self) -> &[TextureImage] {
+        &self.textures
+    }
+
+    /// Everything the path tracer samples lights from: each triangle of the
+    /// emissive meshes, the emissive spheres, the point lights, and the
+    /// environment unless it's black. Never empty, as the shaders always need
+    /// something to sample, so scenes without lights get `Light::NONE`.
+    pub fn lights(&mut self) -> Vec<Light> {
+        let is_emissive = |material: &kea_renderer_shaders::materials::Material| {
+            material.emit.max_element() > 0.0
+        };
+
+        let mut lights: Vec<Light> = self
+            .spheres()
+            .iter()
+            .filter(|sphere| is_emissive(&sphere.material()))
+            .map(|sphere| {
+                Light::sphere(
+                    sphere.center().into(),
+                    sphere.radius(),
+                    sphere.material().emit,
+                )
+            })
             .collect();
 
+        for mesh in self.meshes() {
+            if !is_emissive(&mesh.material) {
+                continue;
+            }
+
+            lights.extend(mesh.indices.iter().map(|triangle| {
+                let points = triangle.map(|index| {
+                    mesh.transform
+                        .transform_point3a(mesh.vertices[index as usize])
+                });
+                let uvs = triangle.map(|index| mesh.attributes[index as usize].uv);
+                Light::triangle(points, mesh.material.emit)
+                    .with_emissive_texture(mesh.textures.emissive, uvs)
+            }));
+        }
+
+        lights.extend(
+            self.world
+                .query::<(&Position, &PointLight)>()
+                .iter(&self.world)
+                .map(|(position, light)| {
+                    Light::from(kea_renderer_shaders::lights::PointLight {
+                        position: position.0.into(),
+                        intensity: light.intensity.into(),
+                    })
+                }),
+        );
+
+        if !self.environment.is_black() {
+            lights.push(Light::ENVIRONMENT);
+        }
+
+        if lights.is_empty() {
+            lights.push(Light::NONE);
+        }
+
+        lights
+    }
+
+    /// Upload the scene to the GPU and build its acceleration structures.
+    pub fn build_scene(&mut self, device: &Arc<Device>) {
+        let mut scene = SceneBuilder::new(device.clone(), "kea renderer scene".to_string());
+
+        let spheres = self.spheres();
+
         if spheres.len() > 0 {
             let spheres_buffer = Buffer::new_from_data(
-                self.device.clone(),
+                device.clone(),
                 &spheres,
                 vk::BufferUsageFlags::STORAGE_BUFFER,
                 "spheres".to_string(),
@@ -137,7 +750,7 @@ impl Scene {
                 .collect();
             log::debug!("Aabbs: {:?}", aabbs);
             let aabbs_buffer = Buffer::new_from_data(
-                self.device.clone(),
+                device.clone(),
                 &aabbs,
                 vk::BufferUsageFlags::ACCELERATION_STRUCTURE_BUILD_INPUT_READ_ONLY_KHR,
                 "aabbs".to_string(),
@@ -147,26 +760,122 @@ impl Scene {
 
             self.spheres = Some(Arc::new(spheres_buffer));
 
-            let mut geometry = Geometry::new(
-                self.device.clone(),
+            let geometry = scene.add_geometry(Geometry::new(
+                device.clone(),
                 "spheres".to_string(),
                 GeometryType::Aabbs(aabbs_buffer),
-            );
-            geometry.build();
-            let geometry_instance =
-                GeometryInstance::new(Arc::new(geometry), 1, Affine3A::IDENTITY, 0);
+            ));
+            let geometry_instance = GeometryInstance::new(geometry, 1, Affine3A::IDENTITY, 0);
             scene.add_instance(geometry_instance);
         }
 
-        let mut meshes: Vec<kea_renderer_shaders::triangles::Mesh> = vec![];
+        // Each kind of primitive uses the hit group after the spheres', in
+        // the order they're listed in the shaders.
+        let primitives = self.primitives();
+        self.primitive_buffers = [
+            add_primitives(
+                &mut scene,
+                device,
+                "boxes",
+                SlotId::Boxes,
+                &primitives.boxes,
+                2,
+            ),
+            add_primitives(
+                &mut scene,
+                device,
+                "planes",
+                SlotId::Planes,
+                &primitives.planes,
+                3,
+            ),
+            add_primitives(
+                &mut scene,
+                device,
+                "discs",
+                SlotId::Discs,
+                &primitives.discs,
+                4,
+            ),
+            add_primitives(
+                &mut scene,
+                device,
+                "cylinders",
+                SlotId::Cylinders,
+                &primitives.cylinders,
+                5,
+            ),
+            add_primitives(
+                &mut scene,
+                device,
+                "cones",
+                SlotId::Cones,
+                &primitives.cones,
+                6,
+            ),
+            add_primitives(
+                &mut scene,
+                device,
+                "tori",
+                SlotId::Tori,
+                &primitives.tori,
+                7,
+            ),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
 
-        for (mesh, position, scale, rotation, material) in self
-            .world
-            .query::<(&Mesh, &Position, &Scale, &Rotation, &Material)>()
-            .iter(&self.world)
-        {
+        let (sdfs, nodes) = self.sdfs();
+        if !sdfs.is_empty() {
+            let sdfs_buffer = Buffer::new_from_data(
+                device.clone(),
+                &sdfs,
+                vk::BufferUsageFlags::STORAGE_BUFFER,
+                "sdfs".to_string(),
+                MemoryLocation::GpuOnly,
+                None,
+            );
+            let nodes_buffer = Buffer::new_from_data(
+                device.clone(),
+                &nodes,
+                vk::BufferUsageFlags::STORAGE_BUFFER,
+                "sdf nodes".to_string(),
+                MemoryLocation::GpuOnly,
+                None,
+            );
+            self.sdfs = Some((Arc::new(sdfs_buffer), Arc::new(nodes_buffer)));
+
+            let aabbs: Vec<Aabb> = sdfs.iter().map(|sdf| sdf.aabb()).collect();
+            let aabbs_buffer = Buffer::new_from_data(
+                device.clone(),
+                &aabbs,
+                vk::BufferUsageFlags::ACCELERATION_STRUCTURE_BUILD_INPUT_READ_ONLY_KHR,
+                "sdf aabbs".to_string(),
+                MemoryLocation::GpuOnly,
+                None,
+            );
+
+            // SDFs use the hit group after the analytic primitives'.
+            let geometry = scene.add_geometry(Geometry::new(
+                device.clone(),
+                "sdfs".to_string(),
+                GeometryType::Aabbs(aabbs_buffer),
+            ));
+            scene.add_instance(GeometryInstance::new(geometry, 8, Affine3A::IDENTITY, 0));
+        }
+
+        assert!(
+            self.mesh_assets.len() <= MAX_MESHES as usize,
+            "Scene has {} mesh assets, but at most {} are supported",
+            self.mesh_assets.len(),
+            MAX_MESHES
+        );
+        let mut geometries = vec![];
+        let mut mesh_buffers = vec![];
+        for mesh in &self.mesh_assets {
             let vertices = Buffer::new_from_data(
-                self.device.clone(),
+                device.clone(),
                 &mesh.vertices,
                 vk::BufferUsageFlags::ACCELERATION_STRUCTURE_BUILD_INPUT_READ_ONLY_KHR,
                 "vertices".to_string(),
@@ -175,7 +884,7 @@ impl Scene {
             );
 
             let indices = Buffer::new_from_data(
-                self.device.clone(),
+                device.clone(),
                 &mesh.indices,
                 vk::BufferUsageFlags::ACCELERATION_STRUCTURE_BUILD_INPUT_READ_ONLY_KHR,
                 "indices".to_string(),
@@ -183,28 +892,58 @@ impl Scene {
                 None,
             );
 
-            let mut geometry = Geometry::new(
-                self.device.clone(),
+            geometries.push(scene.add_geometry(Geometry::new(
+                device.clone(),
                 "triangle mesh".to_string(),
                 GeometryType::Triangles { vertices, indices },
-            );
+            )));
 
-            geometry.build();
+            let vertices = Buffer::new_from_data(
+                device.clone(),
+                &mesh.attributes,
+                vk::BufferUsageFlags::STORAGE_BUFFER,
+                "mesh vertices".to_string(),
+                MemoryLocation::GpuOnly,
+                None,
+            );
+            let indices = Buffer::new_from_data(
+                device.clone(),
+                &mesh.indices,
+                vk::BufferUsageFlags::STORAGE_BUFFER,
+                "mesh indices".to_string(),
+                MemoryLocation::GpuOnly,
+                None,
+            );
+            mesh_buffers.push((Arc::new(vertices), Arc::new(indices)));
+        }
 
-            let transform =
-                Affine3A::from_scale_rotation_translation(scale.0, rotation.0, position.0);
-            let geometry_instance =
-                GeometryInstance::new(Arc::new(geometry), 0, transform, meshes.len() as _);
+        let mut meshes: Vec<kea_renderer_shaders::triangles::Mesh> = vec![];
+        let mut instance_indices = vec![];
+        for mesh in self.meshes() {
+            let geometry_instance = GeometryInstance::new(
+                geometries[mesh.mesh.0 as usize].clone(),
+                0,
+                mesh.transform,
+                meshes.len() as _,
+            );
+            instance_indices.push((mesh.entity, InstanceIndex(scene.instances().len())));
             scene.add_instance(geometry_instance);
 
             meshes.push(kea_renderer_shaders::triangles::Mesh {
-                material: material.0,
+                material: mesh.material,
+                textures: mesh.textures,
+                asset: mesh.mesh.0,
             });
         }
+        self.mesh_buffers = mesh_buffers;
+        self.world.insert_batch(instance_indices);
+        // Only pick up transforms that change from here on.
+        self.world.run_system_cached(changed_transforms).unwrap();
+        self.world.run_system_cached(moved_emitters).unwrap();
 
         if !meshes.is_empty() {
             self.meshes = Some(Arc::new(Buffer::new_from_data(
-                self.device.clone(),
+                device.clone(),
                 &meshes,
                 vk::BufferUsageFlags::STORAGE_BUFFER,
                 "meshes".to_string(),
@@ -213,8 +952,148 @@ impl Scene {
             )));
         }
 
-        scene.build();
-        self.gpu_scene = Some(scene);
+        self.upload_textures(device);
+
+        let lights = self.lights();
+        log::info!("{} lights", lights.len());
+        self.lights = Some(Arc::new(Buffer::new_from_data(
+            device.clone(),
+            &lights,
+            // Rewritten as emissive meshes move.
+            vk::BufferUsageFlags::STORAGE_BUFFER | vk::BufferUsageFlags::TRANSFER_DST,
+            "lights".to_string(),
+            MemoryLocation::GpuOnly,
+            None,
+        )));
+
+        self.upload_environment(device);
+
+        self.gpu_scene = Some(scene.build());
+    }
+
+    /// Move the GPU scene's instances to follow any entities whose position,
+    /// rotation or scale has changed since the last call, and record an
+    /// update of its acceleration structure for drawing `frame`. Returns
+    /// whether anything moved.
+    ///
+    /// Only meshes can move. When emissive ones do, their light triangles are
+    /// rewritten to follow them too.
+    pub fn update_instances(&mut self, cmd: &CommandBufferRecorder, frame: u64) -> bool {
+        let changed = self.world.run_system_cached(changed_transforms).unwrap();
+        let emitters_moved = self.world.run_system_cached(moved_emitters).unwrap();
+        if changed.is_empty() {
+            return false;
+        }
+
+        let scene = self.gpu_scene.as_mut().unwrap();
+        for (index, transform) in changed {
+            scene.set_instance_transform(index, transform);
+        }
+        scene.update_instances(cmd, frame);
+
+        if emitters_moved {
+            // Moving doesn't change how many lights there are, so they can be
+            // rewritten in place.
+            let lights = self.lights();
+            let staging = self
+                .lights
+                .as_ref()
+                .unwrap()
+                .record_write_range(cmd, 0, &lights);
+            self.light_uploads
+                .resize_with(FRAMES_IN_FLIGHT as usize, || None);
+            self.light_uploads[(frame % FRAMES_IN_FLIGHT) as usize] = Some(staging);
+        }
+
+        true
+    }
+
+    /// Set an entity spinning, as a `Spin` of `radians_per_second`.
+    pub fn set_spin(&mut self, entity: Entity, radians_per_second: Vec3) {
+        self.world
+            .entity_mut(entity)
+            .insert(Spin(radians_per_second));
+    }
+
+    /// Move everything that's animated on by `seconds`.
+    pub fn animate(&mut self, seconds: f32) {
+        self.world.run_system_cached_with(spin, seconds).unwrap();
+    }
+
+    fn upload_textures(&mut self, device: &Arc<Device>) {
+        log::info!("{} textures", self.textures.len());
+        assert!(
+            self.textures.len() <= MAX_TEXTURES as usize,
+            "Scene has {} textures, but at most {} are supported",
+            self.textures.len(),
+            MAX_TEXTURES
+        );
+
+        // Colour textures are sRGB encoded, but normal and roughness textures
+        // aren't, so the shaders decode them as needed. They always sample
+        // the top mip level.
+        self.texture_images = self
+            .textures
+            .iter()
+            .map(|texture| {
+                let image = Image::new_from_data(
+                    device.clone(),
+                    "texture".to_string(),
+                    texture.size(),
+                    vk::Format::R8G8B8A8_UNORM,
+                    vk::ImageUsageFlags::empty(),
+                    texture.texels(),
+                    false,
+                );
+                Arc::new(ImageView::new(Arc::new(image)))
+            })
+            .collect();
+
+        self.texture_sampler = Some(Arc::new(Sampler::new(
+            device.clone(),
+            "textures",
+            vk::SamplerAddressMode::REPEAT,
+            vk::SamplerAddressMode::REPEAT,
+        )));
+    }
+
+    fn upload_environment(&mut self, device: &Arc<Device>) {
+        let parameters = Buffer::new_from_data(
+            device.clone(),
+            &[self.environment.parameters()],
+            vk::BufferUsageFlags::STORAGE_BUFFER,
+            "environment".to_string(),
+            MemoryLocation::GpuOnly,
+            None,
+        );
+        let distribution = Buffer::new_from_data(
+            device.clone(),
+            &self.environment.distribution(),
+            vk::BufferUsageFlags::STORAGE_BUFFER,
+            "environment distribution".to_string(),
+            MemoryLocation::GpuOnly,
+            None,
+        );
+        self.environment_buffers = Some((Arc::new(parameters), Arc::new(distribution)));
+
+        let image = self.environment.image();
+        let image = Image::new_from_data(
+            device.clone(),
+            "environment map".to_string(),
+            image.size(),
+            vk::Format::R32G32B32A32_SFLOAT,
+            vk::ImageUsageFlags::empty(),
+            image.pixels(),
+            false,
+        );
+        // Wrap around horizontally, but not over the poles.
+        let sampler = Sampler::new(
+            device.clone(),
+            "environment map",
+            vk::SamplerAddressMode::REPEAT,
+            vk::SamplerAddressMode::CLAMP_TO_EDGE,
+        );
+        self.environment_map = Some((Arc::new(ImageView::new(Arc::new(image))), Arc::new(sampler)));
     }
 
     pub fn bind_data(&self, slot_bindings: &mut SlotBindings<SlotId>) {
@@ -231,8 +1110,183 @@ impl Scene {
             slot_bindings.bind_buffer(SlotId::Spheres, spheres.clone());
         }
 
+        for (slot_id, primitives) in &self.primitive_buffers {
+            slot_bindings.bind_buffer(*slot_id, primitives.clone());
+        }
+
+        if let Some((sdfs, nodes)) = self.sdfs.as_ref() {
+            slot_bindings.bind_buffer(SlotId::Sdfs, sdfs.clone());
+            slot_bindings.bind_buffer(SlotId::SdfNodes, nodes.clone());
+        }
+
         if let Some(meshes) = self.meshes.as_ref() {
             slot_bindings.bind_buffer(SlotId::Meshes, meshes.clone());
         }
+
+        for (asset_id, (vertices, indices)) in self.mesh_buffers.iter().enumerate() {
+            slot_bindings.bind_buffer_at(SlotId::MeshVertices, asset_id as u32, vertices.clone());
+            slot_bindings.bind_buffer_at(SlotId::MeshIndices, asset_id as u32, indices.clone());
+        }
+
+        for (texture_id, image) in self.texture_images.iter().enumerate() {
+            slot_bindings.bind_sampled_image_at(SlotId::Textures, texture_id as u32, image.clone());
+        }
+        slot_bindings.bind_sampler(
+            SlotId::TextureSampler,
+            self.texture_sampler.as_ref().unwrap().clone(),
+        );
+
+        slot_bindings.bind_buffer(SlotId::Lights, self.lights.as_ref().unwrap().clone());
+
+        let (parameters, distribution) = self.environment_buffers.as_ref().unwrap();
+        slot_bindings.bind_buffer(SlotId::Environment, parameters.clone());
+        slot_bindings.bind_buffer(SlotId::EnvironmentDistribution, distribution.clone());
+
+        let (map, sampler) = self.environment_map.as_ref().unwrap();
+        slot_bindings.bind_combined_image_sampler(
+            SlotId::EnvironmentMap,
+            map.clone(),
+            sampler.clone(),
+        );
+    }
+}
+
+/// Upload a kind of analytic primitive, if there are any, and add them to the
+/// scene as a geometry of their AABBs using `hit_group`. Returns their storage
+/// buffer, for binding to `slot_id`.
+fn add_primitives<P: Primitive + Copy>(
+    scene: &mut SceneBuilder,
+    device: &Arc<Device>,
+    name: &str,
+    slot_id: SlotId,
+    primitives: &[P],
+    hit_group: u32,
+) -> Option<(SlotId, Arc<Buffer>)> {
+    if primitives.is_empty() {
+        return None;
+    }
+
+    let primitives_buffer = Buffer::new_from_data(
+        device.clone(),
+        primitives,
+        vk::BufferUsageFlags::STORAGE_BUFFER,
+        name.to_string(),
+        MemoryLocation::GpuOnly,
+        None,
+    );
+
+    let aabbs: Vec<Aabb> = primitives.iter().map(Primitive::aabb).collect();
+    let aabbs_buffer = Buffer::new_from_data(
+        device.clone(),
+        &aabbs,
+        vk::BufferUsageFlags::ACCELERATION_STRUCTURE_BUILD_INPUT_READ_ONLY_KHR,
+        format!("{} aabbs", name),
+        MemoryLocation::GpuOnly,
+        None,
+    );
+
+    let geometry = scene.add_geometry(Geometry::new(
+        device.clone(),
+        name.to_string(),
+        GeometryType::Aabbs(aabbs_buffer),
+    ));
+    scene.add_instance(GeometryInstance::new(
+        geometry,
+        hit_group,
+        Affine3A::IDENTITY,
+        0,
+    ));
+
+    Some((slot_id, Arc::new(primitives_buffer)))
+}
+
+/// Entities whose position, rotation or scale has changed.
+type Moved = Or<(Changed<Position>, Changed<Rotation>, Changed<Scale>)>;
+
+/// Gather the transforms of the instanced entities that have moved since the
+/// system last ran.
+fn changed_transforms(
+    query: Query<(&InstanceIndex, &Position, &Rotation, &Scale), Moved>,
+) -> Vec<(usize, Affine3A)> {
+    query
+        .iter()
+        .map(|(index, position, rotation, scale)| {
+            (
+                index.0,
+                Affine3A::from_scale_rotation_translation(scale.0, rotation.0, position.0),
+            )
+        })
+        .collect()
+}
+
+/// Whether any emissive mesh has moved since the system last ran, leaving its
+/// lights behind.
+fn moved_emitters(query: Query<&Material, (With<InstanceIndex>, Moved)>) -> bool {
+    query
+        .iter()
+        .any(|material| material.0.emit.max_element() > 0.0)
+}
+
+fn spin(In(seconds): In<f32>, mut query: Query<(&Spin, &mut Rotation)>) {
+    for (spin, mut rotation) in &mut query {
+        let angles = spin.0 * seconds;
+        let step = Quat::from_euler(EulerRot::XYZ, angles.x, angles.y, angles.z);
+        rotation.0 = (rotation.0 * step).normalize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn_instance(world: &mut World, index: usize, emit: Vec3A) -> Entity {
+        let material = kea_renderer_shaders::materials::Material::lambertian(Vec3A::ONE);
+        world
+            .spawn((
+                InstanceIndex(index),
+                Position(Vec3::ZERO),
+                Rotation(Quat::IDENTITY),
+                Scale(Vec3::ONE),
+                Material(material.with_emit(emit)),
+            ))
+            .id()
+    }
+
+    #[test]
+    fn changed_transforms_finds_moved_instances_once() {
+        let mut world = World::new();
+        spawn_instance(&mut world, 0, Vec3A::ZERO);
+        let moved = spawn_instance(&mut world, 1, Vec3A::ZERO);
+        // Everything is new the first time.
+        assert_eq!(
+            world.run_system_cached(changed_transforms).unwrap().len(),
+            2
+        );
+
+        world.get_mut::<Position>(moved).unwrap().0 = Vec3::X;
+        let changed = world.run_system_cached(changed_transforms).unwrap();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].0, 1);
+        assert_eq!(changed[0].1.translation, Vec3A::X);
+
+        assert!(world
+            .run_system_cached(changed_transforms)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn moved_emitters_ignores_unlit_meshes() {
+        let mut world = World::new();
+        let unlit = spawn_instance(&mut world, 0, Vec3A::ZERO);
+        let light = spawn_instance(&mut world, 1, Vec3A::ONE);
+        world.run_system_cached(moved_emitters).unwrap();
+
+        world.get_mut::<Rotation>(unlit).unwrap().0 = Quat::from_rotation_y(1.0);
+        assert!(!world.run_system_cached(moved_emitters).unwrap());
+
+        world.get_mut::<Scale>(light).unwrap().0 = Vec3::splat(2.0);
+        assert!(world.run_system_cached(moved_emitters).unwrap());
+        assert!(!world.run_system_cached(moved_emitters).unwrap());
     }
 }
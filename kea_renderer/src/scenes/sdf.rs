@@ -0,0 +1,193 @@
+use glam::Vec3;
+use kea_gpu_shaderlib::Aabb;
+use kea_renderer_shaders::sdfs::{SdfNode, SdfNodeKind, MAX_SDF_STACK};
+
+/// A shape defined by a signed distance function, as a tree of spheres, boxes
+/// and capsules combined with CSG operations.
+#[derive(Clone, Debug)]
+pub enum SdfShape {
+    Sphere {
+        centre: Vec3,
+        radius: f32,
+    },
+    Box {
+        centre: Vec3,
+        size: Vec3,
+    },
+    /// A line segment from `start` to `end`, thickened by `radius`.
+    Capsule {
+        start: Vec3,
+        end: Vec3,
+        radius: f32,
+    },
+    Union(Box<SdfShape>, Box<SdfShape>),
+    Intersection(Box<SdfShape>, Box<SdfShape>),
+    /// The first shape with the second cut out of it.
+    Subtraction(Box<SdfShape>, Box<SdfShape>),
+    /// A union with the seams filled in, blending over `radius`.
+    SmoothUnion(Box<SdfShape>, Box<SdfShape>, f32),
+}
+
+impl SdfShape {
+    /// Bounds containing the whole shape.
+    pub fn aabb(&self) -> Aabb {
+        match self {
+            SdfShape::Sphere { centre, radius } => Aabb {
+                min: *centre - Vec3::splat(*radius),
+                max: *centre + Vec3::splat(*radius),
+            },
+            SdfShape::Box { centre, size } => Aabb {
+                min: *centre - *size / 2.0,
+                max: *centre + *size / 2.0,
+            },
+            SdfShape::Capsule { start, end, radius } => Aabb {
+                min: start.min(*end) - Vec3::splat(*radius),
+                max: start.max(*end) + Vec3::splat(*radius),
+            },
+            SdfShape::Union(a, b) => union(a.aabb(), b.aabb()),
+            SdfShape::Intersection(a, b) => {
+                // Shapes that don't overlap leave an empty box, which still
+                // mustn't have its minimum above its maximum.
+                let (a, b) = (a.aabb(), b.aabb());
+                let min = a.min.max(b.min);
+                Aabb {
+                    min,
+                    max: a.max.min(b.max).max(min),
+                }
+            }
+            SdfShape::Subtraction(a, _) => a.aabb(),
+            SdfShape::SmoothUnion(a, b, radius) => {
+                // The blend bulges out by at most a quarter of its radius.
+                let aabb = union(a.aabb(), b.aabb());
+                Aabb {
+                    min: aabb.min - Vec3::splat(radius / 4.0),
+                    max: aabb.max + Vec3::splat(radius / 4.0),
+                }
+            }
+        }
+    }
+
+    /// The shape's expression tree in postfix order, as the shaders evaluate
+    /// it. Its `stack_depth` must be at most `MAX_SDF_STACK`.
+    pub fn nodes(&self) -> Vec<SdfNode> {
+        assert!(
+            self.stack_depth() <= MAX_SDF_STACK,
+            "SDF needs a stack of {}, but at most {} is supported",
+            self.stack_depth(),
+            MAX_SDF_STACK
+        );
+
+        let mut nodes = vec![];
+        self.push_nodes(&mut nodes);
+        nodes
+    }
+
+    fn push_nodes(&self, nodes: &mut Vec<SdfNode>) {
+        let (a, b, operation) = match self {
+            SdfShape::Sphere { centre, radius } => {
+                nodes.push(SdfNode::sphere(*centre, *radius));
+                return;
+            }
+            SdfShape::Box { centre, size } => {
+                nodes.push(SdfNode::cuboid(*centre, *size / 2.0));
+                return;
+            }
+            SdfShape::Capsule { start, end, radius } => {
+                nodes.push(SdfNode::capsule(*start, *end, *radius));
+                return;
+            }
+            SdfShape::Union(a, b) => (a, b, SdfNode::operation(SdfNodeKind::UNION)),
+            SdfShape::Intersection(a, b) => (a, b, SdfNode::operation(SdfNodeKind::INTERSECTION)),
+            SdfShape::Subtraction(a, b) => (a, b, SdfNode::operation(SdfNodeKind::SUBTRACTION)),
+            SdfShape::SmoothUnion(a, b, radius) => (a, b, SdfNode::smooth_union(*radius)),
+        };
+
+        a.push_nodes(nodes);
+        b.push_nodes(nodes);
+        nodes.push(operation);
+    }
+
+    /// How deep the evaluation stack gets: the first operand's values stay on
+    /// it while the second is evaluated.
+    pub fn stack_depth(&self) -> usize {
+        match self {
+            SdfShape::Sphere { .. } | SdfShape::Box { .. } | SdfShape::Capsule { .. } => 1,
+            SdfShape::Union(a, b)
+            | SdfShape::Intersection(a, b)
+            | SdfShape::Subtraction(a, b)
+            | SdfShape::SmoothUnion(a, b, _) => a.stack_depth().max(b.stack_depth() + 1),
+        }
+    }
+}
+
+fn union(a: Aabb, b: Aabb) -> Aabb {
+    Aabb {
+        min: a.min.min(b.min),
+        max: a.max.max(b.max),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sphere(x: f32) -> Box<SdfShape> {
+        Box::new(SdfShape::Sphere {
+            centre: Vec3::new(x, 0.0, 0.0),
+            radius: 1.0,
+        })
+    }
+
+    #[test]
+    fn stack_depth_grows_down_second_operands() {
+        assert_eq!(sphere(0.0).stack_depth(), 1);
+
+        // Left-leaning trees reuse the same stack slot.
+        let left = SdfShape::Union(
+            Box::new(SdfShape::Union(
+                Box::new(SdfShape::Union(sphere(0.0), sphere(1.0))),
+                sphere(2.0),
+            )),
+            sphere(3.0),
+        );
+        assert_eq!(left.stack_depth(), 2);
+
+        let right = SdfShape::Union(
+            sphere(0.0),
+            Box::new(SdfShape::Union(
+                sphere(1.0),
+                Box::new(SdfShape::Union(sphere(2.0), sphere(3.0))),
+            )),
+        );
+        assert_eq!(right.stack_depth(), 4);
+        assert_eq!(right.nodes().len(), 7);
+    }
+
+    #[test]
+    fn smooth_union_aabb_is_padded() {
+        let shape = SdfShape::SmoothUnion(sphere(0.0), sphere(1.0), 0.4);
+        let aabb = shape.aabb();
+
+        // The blend can reach a quarter of its radius past the spheres.
+        assert!(aabb.min.abs_diff_eq(Vec3::new(-1.1, -1.1, -1.1), 1e-6));
+        assert!(aabb.max.abs_diff_eq(Vec3::new(2.1, 1.1, 1.1), 1e-6));
+    }
+
+    #[test]
+    fn intersection_aabb_is_the_overlap() {
+        let shape = SdfShape::Intersection(sphere(0.0), sphere(1.0));
+        let aabb = shape.aabb();
+
+        assert_eq!(aabb.min, Vec3::new(0.0, -1.0, -1.0));
+        assert_eq!(aabb.max, Vec3::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn empty_intersection_aabb_is_valid() {
+        let shape = SdfShape::Intersection(sphere(0.0), sphere(5.0));
+        let aabb = shape.aabb();
+
+        assert!(aabb.min.cmple(aabb.max).all());
+        assert_eq!(aabb.min.x, aabb.max.x);
+    }
+}
@@ -0,0 +1,64 @@
+use super::LoadError;
+use glam::{Vec2, Vec4};
+use std::path::Path;
+
+/// An 8-bit RGBA image for texturing meshes.
+#[derive(Clone)]
+pub struct TextureImage {
+    size: (u32, u32),
+    texels: Vec<[u8; 4]>,
+}
+
+impl TextureImage {
+    /// Load an image in any format the `image` crate supports. Colour
+    /// textures are expected to be sRGB encoded.
+    pub fn load(path: &Path) -> Result<Self, LoadError> {
+        let image = image::open(path)?.into_rgba8();
+
+        Ok(Self::from_rgba8(image.dimensions(), image.as_raw()))
+    }
+
+    /// An image from RGBA bytes in row-major order, starting from the top.
+    pub fn from_rgba8(size: (u32, u32), bytes: &[u8]) -> Self {
+        assert_eq!(bytes.len(), (size.0 as usize) * (size.1 as usize) * 4);
+
+        Self {
+            size,
+            texels: bytes
+                .chunks_exact(4)
+                .map(|texel| [texel[0], texel[1], texel[2], texel[3]])
+                .collect(),
+        }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// The texels in row-major order, starting from the top.
+    pub fn texels(&self) -> &[[u8; 4]] {
+        &self.texels
+    }
+
+    /// The bilinearly filtered texel values at `uv`, from 0 to 1, repeating
+    /// outside 0-1 as the texture sampler on the GPU does.
+    pub fn sample(&self, uv: Vec2) -> Vec4 {
+        let x = uv.x * self.size.0 as f32 - 0.5;
+        let y = uv.y * self.size.1 as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = self.texel(x0, y0).lerp(self.texel(x0 + 1, y0), fx);
+        let bottom = self.texel(x0, y0 + 1).lerp(self.texel(x0 + 1, y0 + 1), fx);
+        top.lerp(bottom, fy)
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Vec4 {
+        let (width, height) = (self.size.0 as i64, self.size.1 as i64);
+        let x = x.rem_euclid(width);
+        let y = y.rem_euclid(height);
+
+        Vec4::from_array(self.texels[(y * width + x) as usize].map(f32::from)) / 255.0
+    }
+}
@@ -0,0 +1,41 @@
+use glam::{Vec2, Vec3A};
+use kea_renderer_shaders::triangles::Vertex;
+
+/// Fill in the tangents of vertices with normals and texture coordinates, so
+/// normal maps can be applied to meshes that don't come with tangents. Each
+/// vertex gets the average of the tangents of the triangles sharing it.
+pub fn generate_tangents(positions: &[Vec3A], indices: &[[u32; 3]], vertices: &mut [Vertex]) {
+    let mut tangents = vec![Vec3A::ZERO; vertices.len()];
+    // Point along increasing V, which is down the texture.
+    let mut bitangents = vec![Vec3A::ZERO; vertices.len()];
+
+    for triangle in indices {
+        let [a, b, c] = triangle.map(|index| index as usize);
+        let edge1 = positions[b] - positions[a];
+        let edge2 = positions[c] - positions[a];
+        let delta_uv1: Vec2 = vertices[b].uv - vertices[a].uv;
+        let delta_uv2: Vec2 = vertices[c].uv - vertices[a].uv;
+
+        let determinant = delta_uv1.x * delta_uv2.y - delta_uv2.x * delta_uv1.y;
+        if determinant.abs() < f32::EPSILON {
+            continue;
+        }
+
+        let tangent = (edge1 * delta_uv2.y - edge2 * delta_uv1.y) / determinant;
+        let bitangent = (edge2 * delta_uv1.x - edge1 * delta_uv2.x) / determinant;
+        for index in [a, b, c] {
+            tangents[index] += tangent;
+            bitangents[index] += bitangent;
+        }
+    }
+
+    for ((vertex, tangent), bitangent) in vertices.iter_mut().zip(tangents).zip(bitangents) {
+        let normal = vertex.normal;
+        vertex.tangent = (tangent - normal * normal.dot(tangent)).normalize_or_zero();
+        vertex.bitangent_sign = if normal.cross(vertex.tangent).dot(bitangent) > 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+    }
+}
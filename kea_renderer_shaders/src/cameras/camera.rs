@@ -1,26 +1,30 @@
 use kea_gpu_shaderlib::Ray;
-use spirv_std::glam::{vec3, Vec3};
+use spirv_std::glam::{vec3a, Vec3, Vec3A};
 
 // Needed for .tan()
 #[allow(unused_imports)]
 use spirv_std::num_traits::Float;
 
+/// Camera placement, supplied by the host through push constants.
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug, PartialEq))]
+#[derive(Clone, Copy)]
+#[repr(C)]
 pub struct CameraParameters {
+    pub position: Vec3A,
+    pub target_position: Vec3A,
+    pub up_direction: Vec3A,
     pub aspect_ratio: f32,
     pub vertical_field_of_view_radians: f32,
-    pub position: Vec3,
-    pub target_position: Vec3,
-    pub up_direction: Vec3,
 }
 
 impl Default for CameraParameters {
     fn default() -> Self {
         Self {
+            position: vec3a(0.0, 0.0, 0.0),
+            target_position: vec3a(0.0, 0.0, -1.0),
+            up_direction: vec3a(0.0, 1.0, 0.0),
             aspect_ratio: 4.0 / 3.0,
             vertical_field_of_view_radians: 90.0_f32.to_radians(),
-            position: vec3(0.0, 0.0, 0.0),
-            target_position: vec3(0.0, 0.0, -1.0),
-            up_direction: vec3(0.0, 1.0, 0.0),
         }
     }
 }
@@ -38,18 +42,19 @@ impl Camera {
         let width = height * params.aspect_ratio;
 
         // https://raytracing.github.io/books/RayTracingInOneWeekend.html#positionablecamera
-        let w = (params.position - params.target_position).normalize();
-        let u = params.up_direction.cross(w).normalize();
+        let position = Vec3::from(params.position);
+        let w = (position - Vec3::from(params.target_position)).normalize();
+        let u = Vec3::from(params.up_direction).cross(w).normalize();
         let v = w.cross(u);
 
         let horizontal = width * u;
         let vertical = height * v;
 
         Self {
-            position: params.position,
+            position,
             horizontal,
             vertical,
-            lower_left: params.position - horizontal / 2.0 - vertical / 2.0 - w,
+            lower_left: position - horizontal / 2.0 - vertical / 2.0 - w,
         }
     }
 
@@ -0,0 +1,59 @@
+//! Importance sampling of a piecewise constant distribution over `[0, 1)²`,
+//! tabulated on a `size.x` by `size.y` grid.
+//!
+//! The table is the cumulative distribution over rows, `size.y + 1` values
+//! going from 0 to 1, followed by the cumulative distribution within each
+//! row, `size.x + 1` values each.
+
+use spirv_std::glam::{vec2, UVec2, Vec2};
+
+/// Sample a point, returning it with its probability density.
+pub fn sample(table: &[f32], size: UVec2, random: Vec2) -> (Vec2, f32) {
+    let (width, height) = (size.x as usize, size.y as usize);
+
+    let row = find_interval(table, 0, height, random.y);
+    let row_probability = table[row + 1] - table[row];
+    let v = (row as f32 + (random.y - table[row]) / row_probability) / height as f32;
+
+    let offset = height + 1 + row * (width + 1);
+    let column = find_interval(table, offset, width, random.x);
+    let column_probability = table[offset + column + 1] - table[offset + column];
+    let u =
+        (column as f32 + (random.x - table[offset + column]) / column_probability) / width as f32;
+
+    (
+        vec2(u, v),
+        row_probability * column_probability * (width * height) as f32,
+    )
+}
+
+/// Probability density of `sample` choosing `point`.
+pub fn pdf(table: &[f32], size: UVec2, point: Vec2) -> f32 {
+    let (width, height) = (size.x as usize, size.y as usize);
+    let column = ((point.x * width as f32) as usize).min(width - 1);
+    let row = ((point.y * height as f32) as usize).min(height - 1);
+
+    let offset = height + 1 + row * (width + 1);
+    let row_probability = table[row + 1] - table[row];
+    let column_probability = table[offset + column + 1] - table[offset + column];
+
+    row_probability * column_probability * (width * height) as f32
+}
+
+/// Binary search for the interval of the `count` intervals starting at
+/// `offset` in `table` that contains `value`. Empty intervals are never
+/// chosen.
+fn find_interval(table: &[f32], offset: usize, count: usize, value: f32) -> usize {
+    let mut low = 0;
+    let mut high = count;
+    while high - low > 1 {
+        let middle = (low + high) / 2;
+        if table[offset + middle] <= value {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+
+    low
+}
@@ -0,0 +1,216 @@
+use super::{distribution, sky};
+use crate::lights::LightSample;
+use core::f32::consts::PI;
+use spirv_std::{
+    glam::{vec2, vec3, UVec2, Vec2, Vec3, Vec3A, Vec4, Vec4Swizzles},
+    Image, SampledImage,
+};
+
+// Needed for .sqrt()
+#[allow(unused_imports)]
+use spirv_std::num_traits::Float;
+
+/// Angle between the centre and the edge of the sun's disc, in radians.
+const SUN_ANGULAR_RADIUS: f32 = 0.00465;
+/// Light arriving from the sun onto a surface facing it, relative to the sky's
+/// luminance at the zenith.
+const SUN_IRRADIANCE: Vec3 = vec3(20.0, 19.0, 17.0);
+/// How often the sun is sampled rather than the rest of the sky, while it's
+/// above the horizon.
+const SUN_SAMPLING_PROBABILITY: f32 = 0.5;
+
+/// Where an environment's light comes from. A plain integer rather than an
+/// enum, so it can be read straight out of storage buffers on the GPU.
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct EnvironmentKind(u32);
+
+impl EnvironmentKind {
+    /// The same colour in every direction.
+    pub const CONSTANT: Self = Self(0);
+    /// The Preetham model of a clear sky, plus the sun.
+    pub const SKY: Self = Self(1);
+    /// An equirectangular environment map.
+    pub const MAP: Self = Self(2);
+}
+
+/// The light arriving from infinitely far away, seen by rays that leave the
+/// scene without hitting anything.
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct Environment {
+    pub kind: EnvironmentKind,
+    /// Radiance of constant environments, which the sky and environment maps
+    /// are multiplied by.
+    pub colour: Vec3A,
+    /// Unit vector pointing at the sun.
+    pub sun_direction: Vec3A,
+    /// Haziness of the sky, from about 2 for a very clear sky to 10.
+    pub turbidity: f32,
+    /// Resolution of the table the environment is importance sampled from,
+    /// which lays it out like an environment map.
+    pub distribution_size: UVec2,
+}
+
+/// An equirectangular image of the light arriving from every direction.
+pub trait EnvironmentMap {
+    /// The filtered radiance at `uv`, where U goes once around the horizon and
+    /// V goes from straight up to straight down.
+    fn radiance(&self, uv: Vec2) -> Vec3;
+}
+
+impl EnvironmentMap for SampledImage<Image!(2D, type=f32, sampled)> {
+    fn radiance(&self, uv: Vec2) -> Vec3 {
+        let texel: Vec4 = unsafe { self.sample_by_lod(uv, 0.0) };
+        texel.xyz()
+    }
+}
+
+impl Environment {
+    /// Black in every direction, as if there were nothing outside the scene.
+    pub const BLACK: Self = Self {
+        kind: EnvironmentKind::CONSTANT,
+        colour: Vec3A::ZERO,
+        sun_direction: Vec3A::Y,
+        turbidity: 0.0,
+        distribution_size: UVec2::ONE,
+    };
+
+    /// Light arriving from `direction`. `map` is only read from by
+    /// environment maps.
+    pub fn radiance<M: EnvironmentMap>(&self, direction: Vec3, map: &M) -> Vec3 {
+        let direction = direction.normalize();
+        let mut radiance = self.background(direction, map);
+        if self.has_sun() && self.sun_direction.dot(direction.into()) >= sun_cos_radius() {
+            radiance += Vec3::from(self.colour) * SUN_IRRADIANCE / sun_solid_angle();
+        }
+
+        radiance
+    }
+
+    /// Light arriving from `direction`, ignoring the sun. This is what the
+    /// importance sampling table is built from, as the sun is too small to
+    /// show up in it and gets sampled separately.
+    pub fn background<M: EnvironmentMap>(&self, direction: Vec3, map: &M) -> Vec3 {
+        let colour = Vec3::from(self.colour);
+
+        if self.kind == EnvironmentKind::SKY {
+            colour * sky::radiance(self.sun_direction.into(), self.turbidity, direction)
+        } else if self.kind == EnvironmentKind::MAP {
+            colour * map.radiance(direction_to_uv(direction))
+        } else {
+            colour
+        }
+    }
+
+    /// Sample a direction to light a point from, using the importance sampling
+    /// table `distribution`. The radiance isn't known until a shadow ray
+    /// escapes in that direction and runs the miss shader, so it's left as 1.
+    pub fn sample(&self, distribution: &[f32], random: Vec2) -> LightSample {
+        let sun_probability = self.sun_probability();
+
+        let direction = if random.x < sun_probability {
+            self.sample_sun(vec2(random.x / sun_probability, random.y))
+        } else {
+            let random = vec2(
+                (random.x - sun_probability) / (1.0 - sun_probability),
+                random.y,
+            );
+            let (uv, _) = distribution::sample(distribution, self.distribution_size, random);
+            uv_to_direction(uv)
+        };
+
+        LightSample {
+            direction,
+            distance: 10000.0,
+            radiance: Vec3::ONE,
+            pdf: self.pdf(distribution, direction),
+            delta: false,
+        }
+    }
+
+    /// Probability density per unit solid angle of `sample` choosing
+    /// `direction`.
+    pub fn pdf(&self, distribution: &[f32], direction: Vec3) -> f32 {
+        let sun_probability = self.sun_probability();
+
+        let mut pdf = 0.0;
+        if sun_probability > 0.0 && self.sun_direction.dot(direction.into()) >= sun_cos_radius() {
+            pdf += sun_probability / sun_solid_angle();
+        }
+
+        // Rows of the table cover equal angles, so they're squashed into
+        // smaller solid angles towards the poles.
+        let sin_theta = (1.0 - direction.y * direction.y).max(0.0).sqrt();
+        if sin_theta > 0.0 {
+            let uv = direction_to_uv(direction);
+            pdf += (1.0 - sun_probability)
+                * distribution::pdf(distribution, self.distribution_size, uv)
+                / (2.0 * PI * PI * sin_theta);
+        }
+
+        pdf
+    }
+
+    fn has_sun(&self) -> bool {
+        self.kind == EnvironmentKind::SKY && self.sun_direction.y > 0.0
+    }
+
+    fn sun_probability(&self) -> f32 {
+        if self.has_sun() {
+            SUN_SAMPLING_PROBABILITY
+        } else {
+            0.0
+        }
+    }
+
+    /// Sample a direction uniformly within the sun's disc.
+    fn sample_sun(&self, random: Vec2) -> Vec3 {
+        let half = (SUN_ANGULAR_RADIUS / 2.0).sin();
+        let one_minus_cos = random.x * 2.0 * half * half;
+        let cos_theta = 1.0 - one_minus_cos;
+        let sin_theta = (one_minus_cos * (2.0 - one_minus_cos)).sqrt();
+        let phi = 2.0 * PI * random.y;
+
+        let sun = Vec3::from(self.sun_direction);
+        let axis = if sun.x.abs() > 0.9 { Vec3::Y } else { Vec3::X };
+        let tangent = axis.cross(sun).normalize();
+        let bitangent = sun.cross(tangent);
+
+        (tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + sun * cos_theta)
+            .normalize()
+    }
+}
+
+fn sun_cos_radius() -> f32 {
+    SUN_ANGULAR_RADIUS.cos()
+}
+
+/// Solid angle of the sun's disc. Worked out from the half angle, as `1 - cos`
+/// of such a small angle loses most of its precision.
+fn sun_solid_angle() -> f32 {
+    let half = (SUN_ANGULAR_RADIUS / 2.0).sin();
+    4.0 * PI * half * half
+}
+
+/// Where `direction` is in an equirectangular environment map. -Z is in the
+/// middle, and +Y at the top.
+pub fn direction_to_uv(direction: Vec3) -> Vec2 {
+    vec2(
+        direction.x.atan2(-direction.z) / (2.0 * PI) + 0.5,
+        direction.y.clamp(-1.0, 1.0).acos() / PI,
+    )
+}
+
+pub fn uv_to_direction(uv: Vec2) -> Vec3 {
+    let phi = (uv.x - 0.5) * 2.0 * PI;
+    let theta = uv.y * PI;
+
+    vec3(
+        theta.sin() * phi.sin(),
+        theta.cos(),
+        -theta.sin() * phi.cos(),
+    )
+}
@@ -0,0 +1,58 @@
+mod distribution;
+mod environment;
+mod sky;
+
+use crate::SlotId;
+use core::any::TypeId;
+pub use environment::{
+    direction_to_uv, uv_to_direction, Environment, EnvironmentKind, EnvironmentMap,
+};
+use kea_gpu_shaderlib::slots::{ShaderStages, Slot, SlotType};
+
+pub const SLOT: (SlotId, Slot) = (
+    SlotId::Environment,
+    Slot::new(
+        SlotType::Buffer(TypeId::of::<&Environment>()),
+        ShaderStages {
+            raygen: true,
+            intersection: false,
+            closest_hit: false,
+            miss: true,
+            compute: false,
+            vertex: false,
+            fragment: false,
+        },
+    ),
+);
+
+pub const SLOT_MAP: (SlotId, Slot) = (
+    SlotId::EnvironmentMap,
+    Slot::new(
+        SlotType::CombinedImageSampler,
+        ShaderStages {
+            raygen: false,
+            intersection: false,
+            closest_hit: false,
+            miss: true,
+            compute: false,
+            vertex: false,
+            fragment: false,
+        },
+    ),
+);
+
+pub const SLOT_DISTRIBUTION: (SlotId, Slot) = (
+    SlotId::EnvironmentDistribution,
+    Slot::new(
+        SlotType::Buffer(TypeId::of::<&[f32]>()),
+        ShaderStages {
+            raygen: true,
+            intersection: false,
+            closest_hit: false,
+            miss: false,
+            compute: false,
+            vertex: false,
+            fragment: false,
+        },
+    ),
+);
@@ -0,0 +1,94 @@
+//! The Preetham analytic model of a clear sky, from "A Practical Analytic
+//! Model for Daylight" by Preetham, Shirley and Smits.
+
+use spirv_std::glam::{vec3, Vec3};
+
+// Needed for .acos() and .exp()
+#[allow(unused_imports)]
+use spirv_std::num_traits::Float;
+
+/// The lowest the sun is allowed to get, as the model breaks down when it's
+/// below the horizon.
+const MIN_SUN_ELEVATION_COSINE: f32 = 0.01;
+
+/// Radiance of the sky in `direction`, scaled so the zenith has a luminance
+/// of 1. The ground below the horizon is black, so scenes need a floor of
+/// their own.
+pub fn radiance(sun_direction: Vec3, turbidity: f32, direction: Vec3) -> Vec3 {
+    let cos_theta = direction.y;
+    if cos_theta <= 0.0 {
+        return Vec3::ZERO;
+    }
+
+    let theta_sun = sun_direction.y.clamp(MIN_SUN_ELEVATION_COSINE, 1.0).acos();
+    let gamma = direction.dot(sun_direction).clamp(-1.0, 1.0).acos();
+    let t = turbidity;
+
+    let luminance_coefficients = [
+        0.1787 * t - 1.4630,
+        -0.3554 * t + 0.4275,
+        -0.0227 * t + 5.3251,
+        0.1206 * t - 2.5771,
+        -0.0670 * t + 0.3703,
+    ];
+    let x_coefficients = [
+        -0.0193 * t - 0.2592,
+        -0.0665 * t + 0.0008,
+        -0.0004 * t + 0.2125,
+        -0.0641 * t - 0.8989,
+        -0.0033 * t + 0.0452,
+    ];
+    let y_coefficients = [
+        -0.0167 * t - 0.2608,
+        -0.0950 * t + 0.0092,
+        -0.0079 * t + 0.2102,
+        -0.0441 * t - 1.6537,
+        -0.0109 * t + 0.0529,
+    ];
+
+    // Chromaticity at the zenith.
+    let (s, s2, s3) = (
+        theta_sun,
+        theta_sun * theta_sun,
+        theta_sun * theta_sun * theta_sun,
+    );
+    let zenith_x = t * t * (0.00166 * s3 - 0.00375 * s2 + 0.00209 * s)
+        + t * (-0.02903 * s3 + 0.06377 * s2 - 0.03202 * s + 0.00394)
+        + (0.11693 * s3 - 0.21196 * s2 + 0.06052 * s + 0.25886);
+    let zenith_y = t * t * (0.00275 * s3 - 0.00610 * s2 + 0.00317 * s)
+        + t * (-0.04214 * s3 + 0.08970 * s2 - 0.04153 * s + 0.00516)
+        + (0.15346 * s3 - 0.26756 * s2 + 0.06670 * s + 0.26688);
+
+    let luminance = relative_perez(luminance_coefficients, cos_theta, gamma, theta_sun);
+    let x = zenith_x * relative_perez(x_coefficients, cos_theta, gamma, theta_sun);
+    let y = zenith_y * relative_perez(y_coefficients, cos_theta, gamma, theta_sun);
+
+    xyy_to_linear_srgb(x, y, luminance)
+}
+
+/// The Perez sky luminance distribution, for a direction at `cos_theta` to
+/// the zenith and an angle of `gamma` to the sun.
+fn perez(coefficients: [f32; 5], cos_theta: f32, gamma: f32) -> f32 {
+    let [a, b, c, d, e] = coefficients;
+    let cos_gamma = gamma.cos();
+
+    (1.0 + a * (b / cos_theta).exp()) * (1.0 + c * (d * gamma).exp() + e * cos_gamma * cos_gamma)
+}
+
+/// The Perez distribution relative to its value at the zenith, where the angle
+/// to the sun is the sun's own zenith angle.
+fn relative_perez(coefficients: [f32; 5], cos_theta: f32, gamma: f32, theta_sun: f32) -> f32 {
+    perez(coefficients, cos_theta, gamma) / perez(coefficients, 1.0, theta_sun)
+}
+
+fn xyy_to_linear_srgb(x: f32, y: f32, luminance: f32) -> Vec3 {
+    let big_x = x / y * luminance;
+    let big_z = (1.0 - x - y) / y * luminance;
+
+    vec3(
+        3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z,
+        -0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z,
+        0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z,
+    )
+    .max(Vec3::ZERO)
+}
@@ -4,20 +4,40 @@
 use kea_gpu_shaderlib::{shaders::ShaderGroup, slots::Slot};
 
 pub mod cameras;
+pub mod environments;
 pub mod lights;
 pub mod materials;
 pub mod path_tracer;
-mod payload;
+pub mod payload;
+pub mod preview;
+pub mod primitives;
+pub mod sdfs;
 pub mod spheres;
+pub mod tone_mapping;
 pub mod triangles;
 
 #[derive(Copy, Clone, Hash, PartialEq, Eq)]
 pub enum SlotId {
     Scene = 0,
-    OutputImage,
     Spheres,
     Meshes,
     LightImage,
+    Lights,
+    Environment,
+    EnvironmentMap,
+    EnvironmentDistribution,
+    MeshVertices,
+    MeshIndices,
+    Textures,
+    TextureSampler,
+    Boxes,
+    Planes,
+    Discs,
+    Cylinders,
+    Cones,
+    Tori,
+    Sdfs,
+    SdfNodes,
 }
 
 impl Into<u32> for SlotId {
@@ -26,12 +46,27 @@ impl Into<u32> for SlotId {
     }
 }
 
-pub const SLOTS: [(SlotId, Slot); 5] = [
+pub const SLOTS: [(SlotId, Slot); 20] = [
     path_tracer::SLOT_SCENE,
-    path_tracer::SLOT_OUTPUT_IMAGE,
     spheres::SLOT,
     triangles::SLOT_MESHES,
     path_tracer::SLOT_LIGHT_IMAGE,
+    lights::SLOT,
+    environments::SLOT,
+    environments::SLOT_MAP,
+    environments::SLOT_DISTRIBUTION,
+    triangles::SLOT_VERTICES,
+    triangles::SLOT_INDICES,
+    materials::SLOT_TEXTURES,
+    materials::SLOT_TEXTURE_SAMPLER,
+    primitives::SLOT_BOXES,
+    primitives::SLOT_PLANES,
+    primitives::SLOT_DISCS,
+    primitives::SLOT_CYLINDERS,
+    primitives::SLOT_CONES,
+    primitives::SLOT_TORI,
+    sdfs::SLOT_SDFS,
+    sdfs::SLOT_NODES,
 ];
 
 #[derive(Clone)]
@@ -41,11 +76,26 @@ pub enum ShaderGroupId {
     TriangleHit,
     SphereHit,
     BoxesHit,
+    PlanesHit,
+    DiscsHit,
+    CylindersHit,
+    ConesHit,
+    ToriHit,
+    SdfHit,
 }
 
-pub const SHADERS: [(ShaderGroupId, ShaderGroup); 4] = [
+/// Instances pick their hit group by its position among the hit groups here,
+/// from 0 for triangles.
+pub const SHADERS: [(ShaderGroupId, ShaderGroup); 11] = [
     path_tracer::SHADER_GENERATE_RAY,
     path_tracer::SHADER_RAY_MISS,
     triangles::SHADER,
     spheres::SHADER,
+    primitives::SHADER_BOXES,
+    primitives::SHADER_PLANES,
+    primitives::SHADER_DISCS,
+    primitives::SHADER_CYLINDERS,
+    primitives::SHADER_CONES,
+    primitives::SHADER_TORI,
+    sdfs::SHADER,
 ];
@@ -0,0 +1,228 @@
+use super::PointLight;
+use crate::materials::{Textures, NO_TEXTURE};
+use core::f32::consts::PI;
+use spirv_std::glam::{vec3, Vec2, Vec3, Vec3A};
+
+// Needed for .sqrt()
+#[allow(unused_imports)]
+use spirv_std::num_traits::Float;
+
+/// The shape of a light. A plain integer rather than an enum, so it can be
+/// read straight out of storage buffers on the GPU.
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct LightKind(u32);
+
+impl LightKind {
+    pub const TRIANGLE: Self = Self(0);
+    pub const SPHERE: Self = Self(1);
+    pub const POINT: Self = Self(2);
+    /// The scene's environment, which is sampled with its own table rather
+    /// than anything stored in the light.
+    pub const ENVIRONMENT: Self = Self(3);
+}
+
+/// Something that can be sampled for next event estimation: an emissive
+/// triangle or sphere, a point light, or the environment.
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct Light {
+    pub kind: LightKind,
+    /// Emitted radiance of triangles and spheres, or the intensity of point
+    /// lights.
+    pub emit: Vec3A,
+    /// The vertices of a triangle in world space. Spheres and point lights
+    /// only use the first, as their centre.
+    pub points: [Vec3A; 3],
+    pub radius: f32,
+    /// Texture coordinates of a triangle's vertices, for its emissive texture.
+    pub uvs: [Vec2; 3],
+    /// Texture the emitted radiance of a triangle is multiplied by, or
+    /// `NO_TEXTURE`.
+    pub emissive_texture: u32,
+}
+
+/// A point sampled on a light, as seen from a point being lit.
+pub struct LightSample {
+    /// Direction to the sampled point.
+    pub direction: Vec3,
+    pub distance: f32,
+    /// Light arriving from the sampled point.
+    pub radiance: Vec3,
+    /// Probability density of sampling `direction`, per unit solid angle. Zero
+    /// if the sample should be ignored.
+    pub pdf: f32,
+    /// Whether the light is a single point, so it can't be hit by chance and
+    /// doesn't need weighting against BSDF sampling.
+    pub delta: bool,
+}
+
+impl Light {
+    /// Vulkan doesn't allow empty buffers, so scenes without lights get one of
+    /// these in their light list.
+    pub const NONE: Self = Self {
+        kind: LightKind::POINT,
+        emit: Vec3A::ZERO,
+        points: [Vec3A::ZERO; 3],
+        radius: 0.0,
+        uvs: [Vec2::ZERO; 3],
+        emissive_texture: NO_TEXTURE,
+    };
+
+    pub const ENVIRONMENT: Self = Self {
+        kind: LightKind::ENVIRONMENT,
+        emit: Vec3A::ZERO,
+        points: [Vec3A::ZERO; 3],
+        radius: 0.0,
+        uvs: [Vec2::ZERO; 3],
+        emissive_texture: NO_TEXTURE,
+    };
+
+    pub fn triangle(points: [Vec3A; 3], emit: Vec3A) -> Self {
+        Self {
+            kind: LightKind::TRIANGLE,
+            emit,
+            points,
+            radius: 0.0,
+            uvs: [Vec2::ZERO; 3],
+            emissive_texture: NO_TEXTURE,
+        }
+    }
+
+    /// A triangle whose emission varies across it with `texture`, given the
+    /// texture coordinates of its vertices.
+    pub const fn with_emissive_texture(self, texture: u32, uvs: [Vec2; 3]) -> Self {
+        Self {
+            uvs,
+            emissive_texture: texture,
+            ..self
+        }
+    }
+
+    pub fn sphere(centre: Vec3A, radius: f32, emit: Vec3A) -> Self {
+        Self {
+            kind: LightKind::SPHERE,
+            emit,
+            points: [centre; 3],
+            radius,
+            uvs: [Vec2::ZERO; 3],
+            emissive_texture: NO_TEXTURE,
+        }
+    }
+
+    /// Sample a point on the light to illuminate `position` with, using two
+    /// uniform random numbers.
+    pub fn sample<X: Textures>(&self, position: Vec3, random: Vec2, textures: &X) -> LightSample {
+        let (point, normal, area) = if self.kind == LightKind::TRIANGLE {
+            self.sample_triangle(random)
+        } else if self.kind == LightKind::SPHERE {
+            self.sample_sphere(random)
+        } else {
+            return self.sample_point(position);
+        };
+
+        let offset = point - position;
+        let distance = offset.length();
+        let direction = offset / distance;
+
+        // Triangles emit from both sides. Points on the far side of a sphere
+        // are hidden by the near side, so they can be skipped.
+        let mut cos_light = normal.dot(direction * -1.0);
+        if self.kind == LightKind::TRIANGLE {
+            cos_light = cos_light.abs();
+        }
+
+        let mut radiance = Vec3::from(self.emit);
+        if self.kind == LightKind::TRIANGLE && self.emissive_texture != NO_TEXTURE {
+            let weights = triangle_weights(random);
+            let uv = self.uvs[0] * weights.x + self.uvs[1] * weights.y + self.uvs[2] * weights.z;
+            radiance *= textures.sample_colour(self.emissive_texture, uv);
+        }
+
+        LightSample {
+            direction,
+            distance,
+            radiance,
+            pdf: solid_angle_pdf(area, distance, cos_light),
+            delta: false,
+        }
+    }
+
+    fn sample_triangle(&self, random: Vec2) -> (Vec3, Vec3, f32) {
+        let [a, b, c] = [
+            Vec3::from(self.points[0]),
+            Vec3::from(self.points[1]),
+            Vec3::from(self.points[2]),
+        ];
+
+        let weights = triangle_weights(random);
+        let point = a * weights.x + b * weights.y + c * weights.z;
+
+        let cross = (b - a).cross(c - a);
+        let double_area = cross.length();
+
+        (point, cross / double_area, double_area / 2.0)
+    }
+
+    fn sample_sphere(&self, random: Vec2) -> (Vec3, Vec3, f32) {
+        let z = 1.0 - 2.0 * random.x;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * PI * random.y;
+        let normal = vec3(r * phi.cos(), r * phi.sin(), z);
+
+        (
+            Vec3::from(self.points[0]) + normal * self.radius,
+            normal,
+            4.0 * PI * self.radius * self.radius,
+        )
+    }
+
+    fn sample_point(&self, position: Vec3) -> LightSample {
+        let offset = Vec3::from(self.points[0]) - position;
+        let distance = offset.length();
+
+        LightSample {
+            direction: offset / distance,
+            distance,
+            radiance: Vec3::from(self.emit) / (distance * distance),
+            pdf: 1.0,
+            delta: true,
+        }
+    }
+}
+
+impl From<PointLight> for Light {
+    fn from(light: PointLight) -> Self {
+        Self {
+            kind: LightKind::POINT,
+            emit: light.intensity,
+            points: [light.position; 3],
+            radius: 0.0,
+            uvs: [Vec2::ZERO; 3],
+            emissive_texture: NO_TEXTURE,
+        }
+    }
+}
+
+/// Uniformly distributed barycentric coordinates, from two uniform random
+/// numbers.
+fn triangle_weights(random: Vec2) -> Vec3 {
+    let root = random.x.sqrt();
+    let u = 1.0 - root;
+    let v = random.y * root;
+
+    vec3(u, v, 1.0 - u - v)
+}
+
+/// Probability density per unit solid angle of `Light::sample` choosing a
+/// point on an area light that was found some other way, from `distance` away
+/// along a direction making `cos_light` with the light's surface normal.
+pub fn solid_angle_pdf(area: f32, distance: f32, cos_light: f32) -> f32 {
+    if cos_light > 1e-6 {
+        distance * distance / (cos_light * area)
+    } else {
+        0.0
+    }
+}
@@ -1,3 +1,24 @@
+mod light;
 mod point_light;
 
+use crate::SlotId;
+use core::any::TypeId;
+use kea_gpu_shaderlib::slots::{ShaderStages, Slot, SlotType};
+pub use light::{solid_angle_pdf, Light, LightKind, LightSample};
 pub use point_light::PointLight;
+
+pub const SLOT: (SlotId, Slot) = (
+    SlotId::Lights,
+    Slot::new(
+        SlotType::Buffer(TypeId::of::<&[Light]>()),
+        ShaderStages {
+            raygen: true,
+            intersection: false,
+            closest_hit: false,
+            miss: false,
+            compute: false,
+            vertex: false,
+            fragment: false,
+        },
+    ),
+);
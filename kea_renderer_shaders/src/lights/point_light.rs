@@ -1,8 +1,11 @@
-use spirv_std::glam::Vec3;
+use spirv_std::glam::Vec3A;
 
+/// A light emitting equally in all directions from a single point.
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[derive(Clone, Copy)]
 #[repr(C)]
 pub struct PointLight {
-    pub position: Vec3,
-    pub diffuse: Vec3,
-    pub specular: Vec3,
+    pub position: Vec3A,
+    /// Radiant intensity, in watts per steradian.
+    pub intensity: Vec3A,
 }
@@ -1,9 +1,81 @@
 use spirv_std::glam::Vec3A;
 
+/// How a surface scatters light. A plain integer rather than an enum, so it
+/// can be read straight out of storage buffers on the GPU.
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct MaterialKind(u32);
+
+impl MaterialKind {
+    /// Ideal diffuse reflection.
+    pub const LAMBERTIAN: Self = Self(0);
+    /// Perfectly smooth metal, reflecting in a single direction.
+    pub const MIRROR: Self = Self(1);
+    /// Rough metal, using the GGX microfacet distribution.
+    pub const METAL: Self = Self(2);
+    /// Smooth glass, reflecting or refracting according to its Fresnel
+    /// reflectance.
+    pub const DIELECTRIC: Self = Self(3);
+}
+
 #[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct Material {
-    pub diffuse: Vec3A,
+    /// Albedo of Lambertian surfaces, reflectance at normal incidence of
+    /// metals, and the tint of light transmitted through dielectrics.
+    pub base_colour: Vec3A,
     pub emit: Vec3A,
+    pub kind: MaterialKind,
+    /// GGX roughness of metals, from 0 (smooth) to 1. Ignored otherwise.
+    pub roughness: f32,
+    /// Index of refraction of dielectrics. Ignored otherwise.
+    pub ior: f32,
+}
+
+impl Material {
+    pub const fn lambertian(base_colour: Vec3A) -> Self {
+        Self {
+            base_colour,
+            emit: Vec3A::ZERO,
+            kind: MaterialKind::LAMBERTIAN,
+            roughness: 1.0,
+            ior: 1.0,
+        }
+    }
+
+    pub const fn mirror(base_colour: Vec3A) -> Self {
+        Self {
+            base_colour,
+            emit: Vec3A::ZERO,
+            kind: MaterialKind::MIRROR,
+            roughness: 0.0,
+            ior: 1.0,
+        }
+    }
+
+    pub const fn metal(base_colour: Vec3A, roughness: f32) -> Self {
+        Self {
+            base_colour,
+            emit: Vec3A::ZERO,
+            kind: MaterialKind::METAL,
+            roughness,
+            ior: 1.0,
+        }
+    }
+
+    pub const fn dielectric(base_colour: Vec3A, ior: f32) -> Self {
+        Self {
+            base_colour,
+            emit: Vec3A::ZERO,
+            kind: MaterialKind::DIELECTRIC,
+            roughness: 0.0,
+            ior,
+        }
+    }
+
+    pub const fn with_emit(self, emit: Vec3A) -> Self {
+        Self { emit, ..self }
+    }
 }
@@ -1,3 +1,46 @@
 mod material;
+mod texture;
 
-pub use material::Material;
+use crate::SlotId;
+use kea_gpu_shaderlib::slots::{ShaderStages, Slot, SlotType};
+pub use material::{Material, MaterialKind};
+pub use texture::{BoundTextures, MaterialTextures, Textures, NO_TEXTURE};
+
+/// The most textures a scene can have.
+pub const MAX_TEXTURES: u32 = 1024;
+
+/// Every texture in the scene, indexed by texture ID. Only as many as the
+/// scene has are bound.
+pub const SLOT_TEXTURES: (SlotId, Slot) = (
+    SlotId::Textures,
+    Slot::array(
+        SlotType::SampledImage,
+        ShaderStages {
+            raygen: true,
+            intersection: false,
+            closest_hit: true,
+            miss: false,
+            compute: false,
+            vertex: false,
+            fragment: false,
+        },
+        MAX_TEXTURES,
+    ),
+);
+
+/// The sampler every texture is read through.
+pub const SLOT_TEXTURE_SAMPLER: (SlotId, Slot) = (
+    SlotId::TextureSampler,
+    Slot::new(
+        SlotType::Sampler,
+        ShaderStages {
+            raygen: true,
+            intersection: false,
+            closest_hit: true,
+            miss: false,
+            compute: false,
+            vertex: false,
+            fragment: false,
+        },
+    ),
+);
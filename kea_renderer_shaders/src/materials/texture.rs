@@ -0,0 +1,128 @@
+use super::Material;
+use spirv_std::{
+    glam::{vec4, Vec2, Vec3, Vec3A, Vec4, Vec4Swizzles},
+    Image, RuntimeArray, Sampler,
+};
+
+// Needed for .powf()
+#[allow(unused_imports)]
+use spirv_std::num_traits::Float;
+
+/// Marks a texture slot of a material as unused.
+pub const NO_TEXTURE: u32 = u32::MAX;
+
+/// Every texture in the scene, looked up by index.
+pub trait Textures {
+    /// The raw contents of a texture at `uv`, from 0 to 1, where (0, 0) is
+    /// the top left corner of the image and it repeats outside 0-1.
+    fn sample(&self, texture: u32, uv: Vec2) -> Vec4;
+
+    /// The linear colour of an sRGB encoded texture at `uv`.
+    fn sample_colour(&self, texture: u32, uv: Vec2) -> Vec3 {
+        let colour = self.sample(texture, uv);
+        Vec3::new(
+            srgb_to_linear(colour.x),
+            srgb_to_linear(colour.y),
+            srgb_to_linear(colour.z),
+        )
+    }
+}
+
+/// The scene's textures as the shaders see them, an array of images indexed
+/// by texture ID and read through one repeating sampler.
+#[derive(Clone, Copy)]
+pub struct BoundTextures<'a> {
+    pub images: &'a RuntimeArray<Image!(2D, type=f32, sampled)>,
+    pub sampler: &'a Sampler,
+}
+
+impl Textures for BoundTextures<'_> {
+    fn sample(&self, texture: u32, uv: Vec2) -> Vec4 {
+        // Ray tracing shaders have no derivatives to pick a mip level with.
+        let image = unsafe { self.images.index(texture as usize) };
+        image.sample_by_lod(*self.sampler, uv, 0.0)
+    }
+}
+
+/// The textures that vary a material across a mesh, indexing into the scene's
+/// textures, or `NO_TEXTURE` where the material is uniform.
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct MaterialTextures {
+    /// sRGB colour multiplied into the base colour.
+    pub base_colour: u32,
+    /// sRGB colour multiplied into the emitted light.
+    pub emissive: u32,
+    /// Multiplies the roughness by its green channel, as in glTF's metallic
+    /// roughness textures.
+    pub roughness: u32,
+    /// A tangent space normal map.
+    pub normal: u32,
+}
+
+impl MaterialTextures {
+    pub const NONE: Self = Self {
+        base_colour: NO_TEXTURE,
+        emissive: NO_TEXTURE,
+        roughness: NO_TEXTURE,
+        normal: NO_TEXTURE,
+    };
+
+    /// `material` as it is at `uv`.
+    pub fn apply<X: Textures>(&self, material: Material, textures: &X, uv: Vec2) -> Material {
+        let mut material = material;
+
+        if self.base_colour != NO_TEXTURE {
+            material.base_colour *= Vec3A::from(textures.sample_colour(self.base_colour, uv));
+        }
+
+        if self.emissive != NO_TEXTURE {
+            material.emit *= Vec3A::from(textures.sample_colour(self.emissive, uv));
+        }
+
+        if self.roughness != NO_TEXTURE {
+            material.roughness *= textures.sample(self.roughness, uv).y;
+        }
+
+        material
+    }
+
+    /// Perturb the unit `normal` by the normal map at `uv`. `tangent` points
+    /// along increasing U, and the bitangent along increasing V is their cross
+    /// product times `bitangent_sign`. Without a normal map or a tangent, the
+    /// normal is left as it is.
+    pub fn normal_mapped<X: Textures>(
+        &self,
+        textures: &X,
+        uv: Vec2,
+        normal: Vec3,
+        tangent: Vec3,
+        bitangent_sign: f32,
+    ) -> Vec3 {
+        if self.normal == NO_TEXTURE {
+            return normal;
+        }
+
+        // Make the tangent perpendicular to the interpolated normal.
+        let tangent = tangent - normal * normal.dot(tangent);
+        if tangent.length_squared() <= 0.0 {
+            return normal;
+        }
+        let tangent = tangent.normalize();
+        let bitangent = normal.cross(tangent) * bitangent_sign;
+
+        let mapped = textures.sample(self.normal, uv) * 2.0 - vec4(1.0, 1.0, 1.0, 1.0);
+        let mapped = mapped.xyz();
+
+        (tangent * mapped.x + bitangent * mapped.y + normal * mapped.z).normalize()
+    }
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
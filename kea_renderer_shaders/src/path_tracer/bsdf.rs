@@ -0,0 +1,285 @@
+//! Scattering for each `MaterialKind`: sampling a direction to continue a path
+//! in, and evaluating how much light scatters between two given directions.
+//!
+//! Directions point away from the surface. `outgoing` is the direction light
+//! leaves in, back along the ray that hit the surface, and `incoming` is the
+//! direction it arrives from.
+
+use super::rand::Random;
+use crate::materials::{Material, MaterialKind};
+use core::f32::consts::PI;
+use spirv_std::glam::{vec3, Quat, Vec3};
+
+// Needed for .sqrt()
+#[allow(unused_imports)]
+use spirv_std::num_traits::Float;
+
+/// Metals smoother than this are treated as mirrors, as the GGX distribution
+/// gets too sharp to evaluate accurately.
+const MIN_ROUGHNESS: f32 = 0.01;
+
+pub struct BsdfSample {
+    pub incoming: Vec3,
+    /// The BSDF times the cosine of `incoming` with the normal, divided by the
+    /// probability density of sampling it. This is what light arriving along
+    /// `incoming` is multiplied by. Zero if the path should end here.
+    pub weight: Vec3,
+    /// Probability density of sampling `incoming`. Meaningless for specular
+    /// samples, which could only have gone in one direction.
+    pub pdf: f32,
+    pub specular: bool,
+}
+
+pub struct BsdfEvaluation {
+    /// The BSDF times the cosine of `incoming` with the normal.
+    pub value: Vec3,
+    /// Probability density of `sample` choosing `incoming`.
+    pub pdf: f32,
+}
+
+/// Sample a direction for light to arrive from, for a surface with the given
+/// material and outward facing normal.
+pub fn sample(material: &Material, normal: Vec3, outgoing: Vec3, rand: &mut Random) -> BsdfSample {
+    if material.kind == MaterialKind::DIELECTRIC {
+        return sample_dielectric(material, normal, outgoing, rand);
+    }
+
+    // The rest are opaque, so scatter on whichever side the ray arrived from.
+    let normal = facing(normal, outgoing);
+
+    if is_specular(material) {
+        BsdfSample {
+            incoming: reflect(outgoing, normal),
+            weight: Vec3::from(material.base_colour),
+            pdf: 1.0,
+            specular: true,
+        }
+    } else if material.kind == MaterialKind::METAL {
+        sample_metal(material, normal, outgoing, rand)
+    } else {
+        let incoming = to_world(normal, rand.random_hemisphere_direction());
+
+        BsdfSample {
+            incoming,
+            weight: Vec3::from(material.base_colour),
+            pdf: normal.dot(incoming) / PI,
+            specular: false,
+        }
+    }
+}
+
+/// Whether the material only scatters light in one direction, so there's no
+/// point evaluating it for other directions.
+pub fn is_specular(material: &Material) -> bool {
+    material.kind == MaterialKind::MIRROR
+        || material.kind == MaterialKind::DIELECTRIC
+        || (material.kind == MaterialKind::METAL && material.roughness < MIN_ROUGHNESS)
+}
+
+/// Evaluate the light scattered from `incoming` to `outgoing`. Specular
+/// materials only scatter between exact pairs of directions, so they're
+/// always zero here.
+pub fn evaluate(
+    material: &Material,
+    normal: Vec3,
+    outgoing: Vec3,
+    incoming: Vec3,
+) -> BsdfEvaluation {
+    let normal = facing(normal, outgoing);
+    let cos_incoming = normal.dot(incoming);
+
+    let no_scattering = BsdfEvaluation {
+        value: Vec3::ZERO,
+        pdf: 0.0,
+    };
+
+    if cos_incoming <= 0.0 {
+        no_scattering
+    } else if material.kind == MaterialKind::LAMBERTIAN {
+        BsdfEvaluation {
+            value: Vec3::from(material.base_colour) * cos_incoming / PI,
+            pdf: cos_incoming / PI,
+        }
+    } else if material.kind == MaterialKind::METAL && material.roughness >= MIN_ROUGHNESS {
+        evaluate_metal(material, normal, outgoing, incoming)
+    } else {
+        no_scattering
+    }
+}
+
+/// Sample the GGX distribution of microfacet normals, and reflect off the
+/// microfacet.
+fn sample_metal(
+    material: &Material,
+    normal: Vec3,
+    outgoing: Vec3,
+    rand: &mut Random,
+) -> BsdfSample {
+    let alpha = material.roughness * material.roughness;
+
+    let u = rand.next_float();
+    let phi = 2.0 * PI * rand.next_float();
+    let tan_theta_squared = alpha * alpha * u / (1.0 - u).max(1e-6);
+    let cos_theta = 1.0 / (1.0 + tan_theta_squared).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let microfacet = to_world(
+        normal,
+        vec3(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta),
+    );
+
+    let incoming = reflect(outgoing, microfacet);
+    let cos_outgoing = normal.dot(outgoing);
+    let cos_incoming = normal.dot(incoming);
+    let outgoing_dot_microfacet = outgoing.dot(microfacet);
+
+    if cos_incoming <= 0.0 || outgoing_dot_microfacet <= 0.0 {
+        return BsdfSample {
+            incoming,
+            weight: Vec3::ZERO,
+            pdf: 0.0,
+            specular: false,
+        };
+    }
+
+    // The distribution terms cancel out of the weight.
+    let fresnel = fresnel_schlick(Vec3::from(material.base_colour), outgoing_dot_microfacet);
+    let shadowing = smith_g1(cos_outgoing, alpha) * smith_g1(cos_incoming, alpha);
+
+    BsdfSample {
+        incoming,
+        weight: fresnel * shadowing * outgoing_dot_microfacet / (cos_outgoing * cos_theta),
+        pdf: ggx_d(cos_theta, alpha) * cos_theta / (4.0 * outgoing_dot_microfacet),
+        specular: false,
+    }
+}
+
+fn evaluate_metal(
+    material: &Material,
+    normal: Vec3,
+    outgoing: Vec3,
+    incoming: Vec3,
+) -> BsdfEvaluation {
+    let alpha = material.roughness * material.roughness;
+    let cos_outgoing = normal.dot(outgoing);
+    let cos_incoming = normal.dot(incoming);
+    if cos_outgoing <= 0.0 {
+        return BsdfEvaluation {
+            value: Vec3::ZERO,
+            pdf: 0.0,
+        };
+    }
+
+    let microfacet = (outgoing + incoming).normalize();
+    let cos_theta = normal.dot(microfacet);
+    let outgoing_dot_microfacet = outgoing.dot(microfacet);
+
+    let distribution = ggx_d(cos_theta, alpha);
+    let fresnel = fresnel_schlick(Vec3::from(material.base_colour), outgoing_dot_microfacet);
+    let shadowing = smith_g1(cos_outgoing, alpha) * smith_g1(cos_incoming, alpha);
+
+    BsdfEvaluation {
+        value: fresnel * distribution * shadowing / (4.0 * cos_outgoing),
+        pdf: distribution * cos_theta / (4.0 * outgoing_dot_microfacet),
+    }
+}
+
+/// Reflect or refract, choosing between them by the Fresnel reflectance.
+fn sample_dielectric(
+    material: &Material,
+    normal: Vec3,
+    outgoing: Vec3,
+    rand: &mut Random,
+) -> BsdfSample {
+    let entering = normal.dot(outgoing) > 0.0;
+    let (normal, eta) = if entering {
+        (normal, 1.0 / material.ior)
+    } else {
+        (normal * -1.0, material.ior)
+    };
+
+    let cos_outgoing = normal.dot(outgoing);
+    let sin_transmitted_squared = eta * eta * (1.0 - cos_outgoing * cos_outgoing);
+
+    // Beyond the critical angle all the light is reflected.
+    let (reflectance, cos_transmitted) = if sin_transmitted_squared >= 1.0 {
+        (1.0, 0.0)
+    } else {
+        let cos_transmitted = (1.0 - sin_transmitted_squared).sqrt();
+        (
+            fresnel_dielectric(cos_outgoing, cos_transmitted, eta),
+            cos_transmitted,
+        )
+    };
+
+    if rand.next_float() < reflectance {
+        BsdfSample {
+            incoming: reflect(outgoing, normal),
+            weight: Vec3::ONE,
+            pdf: 1.0,
+            specular: true,
+        }
+    } else {
+        BsdfSample {
+            incoming: (outgoing * -eta + normal * (eta * cos_outgoing - cos_transmitted))
+                .normalize(),
+            weight: Vec3::from(material.base_colour),
+            pdf: 1.0,
+            specular: true,
+        }
+    }
+}
+
+/// Fresnel reflectance of unpolarised light at a boundary between dielectrics,
+/// where `eta` is the ratio of the index of refraction on the incident side
+/// to the one on the transmitted side.
+fn fresnel_dielectric(cos_incident: f32, cos_transmitted: f32, eta: f32) -> f32 {
+    let perpendicular =
+        (eta * cos_incident - cos_transmitted) / (eta * cos_incident + cos_transmitted);
+    let parallel = (cos_incident - eta * cos_transmitted) / (cos_incident + eta * cos_transmitted);
+
+    (perpendicular * perpendicular + parallel * parallel) / 2.0
+}
+
+fn fresnel_schlick(reflectance: Vec3, cos_theta: f32) -> Vec3 {
+    let m = (1.0 - cos_theta).clamp(0.0, 1.0);
+    let m5 = m * m * m * m * m;
+
+    reflectance + (Vec3::ONE - reflectance) * m5
+}
+
+/// The GGX distribution of microfacet normals.
+fn ggx_d(cos_theta: f32, alpha: f32) -> f32 {
+    let alpha_squared = alpha * alpha;
+    let denominator = cos_theta * cos_theta * (alpha_squared - 1.0) + 1.0;
+
+    alpha_squared / (PI * denominator * denominator)
+}
+
+/// Smith's masking function for the GGX distribution.
+fn smith_g1(cos_theta: f32, alpha: f32) -> f32 {
+    let alpha_squared = alpha * alpha;
+
+    2.0 * cos_theta
+        / (cos_theta + (alpha_squared + (1.0 - alpha_squared) * cos_theta * cos_theta).sqrt())
+}
+
+/// Flip `normal` if needed so it's on the same side of the surface as
+/// `direction`.
+fn facing(normal: Vec3, direction: Vec3) -> Vec3 {
+    if normal.dot(direction) < 0.0 {
+        normal * -1.0
+    } else {
+        normal
+    }
+}
+
+fn reflect(direction: Vec3, normal: Vec3) -> Vec3 {
+    normal * (2.0 * normal.dot(direction)) - direction
+}
+
+/// Rotate a direction from a space where `normal` is +Z into world space.
+fn to_world(normal: Vec3, direction: Vec3) -> Vec3 {
+    Quat::from_rotation_arc(Vec3::Z, normal)
+        .mul_vec3(direction)
+        .normalize()
+}
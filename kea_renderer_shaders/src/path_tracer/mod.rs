@@ -3,7 +3,13 @@ use kea_gpu_shaderlib::{
     shaders::{Shader, ShaderGroup},
     slots::{ShaderStages, Slot, SlotType},
 };
+pub mod bsdf;
 pub mod entrypoints;
+mod rand;
+pub mod sampling;
+mod tracer;
+
+pub use tracer::Tracer;
 
 pub const SHADER_GENERATE_RAY: (ShaderGroupId, ShaderGroup) = (
     ShaderGroupId::RayGen,
@@ -23,18 +29,10 @@ pub const SLOT_SCENE: (SlotId, Slot) = (
             raygen: true,
             intersection: false,
             closest_hit: false,
-        },
-    ),
-);
-
-pub const SLOT_OUTPUT_IMAGE: (SlotId, Slot) = (
-    SlotId::OutputImage,
-    Slot::new(
-        SlotType::Image,
-        ShaderStages {
-            raygen: true,
-            intersection: false,
-            closest_hit: false,
+            miss: false,
+            compute: false,
+            vertex: false,
+            fragment: false,
         },
     ),
 );
@@ -47,8 +45,10 @@ pub const SLOT_LIGHT_IMAGE: (SlotId, Slot) = (
             raygen: true,
             intersection: false,
             closest_hit: false,
+            miss: false,
+            compute: false,
+            vertex: false,
+            fragment: false,
         },
     ),
 );
-
-mod rand;
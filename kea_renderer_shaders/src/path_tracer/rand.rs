@@ -24,7 +24,7 @@ impl Random {
     }
 
     fn next_u32(&mut self) -> u32 {
-        let result = self.s0 + self.s3;
+        let result = self.s0.wrapping_add(self.s3);
         let t = self.s1 << 9;
 
         self.s2 ^= self.s0;
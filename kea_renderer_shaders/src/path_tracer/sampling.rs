@@ -0,0 +1,331 @@
+//! The path tracing itself, shared by the ray generation shader and the CPU
+//! reference renderer so that both produce the same image.
+
+use super::{bsdf, rand::Random, Tracer};
+use crate::{
+    cameras::Camera,
+    environments::Environment,
+    lights::{solid_angle_pdf, Light, LightKind},
+    materials::{Material, Textures},
+    payload::RayPayload,
+};
+use kea_gpu_shaderlib::Ray;
+use spirv_std::glam::{vec2, UVec2, Vec2, Vec3};
+
+pub const NUM_SAMPLES: u32 = 5;
+pub const NUM_BOUNCES: u32 = 15;
+pub const WHITE_POINT: f32 = 2.0;
+
+/// Everything next event estimation can sample light from.
+#[derive(Clone, Copy)]
+pub struct Lighting<'a, X: Textures> {
+    /// Every emissive surface and point light in the scene, along with the
+    /// environment unless it's black. Never empty.
+    pub lights: &'a [Light],
+    pub environment: &'a Environment,
+    /// The table the environment is importance sampled from.
+    pub environment_distribution: &'a [f32],
+    /// For the emissive textures of triangle lights.
+    pub textures: &'a X,
+}
+
+/// Sample the light arriving at a pixel for one iteration, averaged over
+/// `NUM_SAMPLES` jittered rays.
+pub fn sample_iteration<T: Tracer, X: Textures>(
+    tracer: &T,
+    lighting: &Lighting<X>,
+    payload: &mut RayPayload,
+    camera: &Camera,
+    pixel: UVec2,
+    size: UVec2,
+    iteration: u64,
+) -> Vec3 {
+    let mut rand = Random::new(
+        pixel.x * size.x,
+        pixel.y * size.y,
+        pixel.y * 42,
+        iteration as u32,
+    );
+
+    multisample_pixel(
+        tracer,
+        lighting,
+        payload,
+        camera,
+        vec2(size.x as f32, size.y as f32),
+        vec2(pixel.x as f32, pixel.y as f32),
+        &mut rand,
+    )
+}
+
+/// Fold one iteration's light into the running average of the previous ones.
+pub fn accumulate(existing_light: Vec3, iteration_light: Vec3, iteration: u64) -> Vec3 {
+    if iteration > 0 {
+        existing_light * (1.0 - 1.0 / iteration as f32) + iteration_light * (1.0 / iteration as f32)
+    } else {
+        iteration_light
+    }
+}
+
+pub fn tone_map(light: Vec3, white_point: f32) -> Vec3 {
+    (light * (1.0 + light / Vec3::splat(white_point * white_point))) / (1.0 + light)
+}
+
+fn multisample_pixel<T: Tracer, X: Textures>(
+    tracer: &T,
+    lighting: &Lighting<X>,
+    payload: &mut RayPayload,
+    camera: &Camera,
+    size: Vec2,
+    pixel_position: Vec2,
+    rand: &mut Random,
+) -> Vec3 {
+    let mut accumulated_light = Vec3::ZERO;
+    for _ in 0..NUM_SAMPLES {
+        accumulated_light += sample_pixel(
+            tracer,
+            lighting,
+            payload,
+            camera,
+            size,
+            jittered_position(pixel_position, rand),
+            rand,
+        )
+    }
+
+    accumulated_light / NUM_SAMPLES as f32
+}
+
+fn jittered_position(position: Vec2, rand: &mut Random) -> Vec2 {
+    vec2(
+        position.x + rand.next_float() - 0.5,
+        position.y + rand.next_float() - 0.5,
+    )
+}
+
+fn sample_pixel<T: Tracer, X: Textures>(
+    tracer: &T,
+    lighting: &Lighting<X>,
+    payload: &mut RayPayload,
+    camera: &Camera,
+    size: Vec2,
+    pixel_position: Vec2,
+    rand: &mut Random,
+) -> Vec3 {
+    let mut light = Vec3::ZERO;
+
+    let camera_ray = camera.ray(
+        pixel_position.x / size.x,
+        (size.y - pixel_position.y) / size.y,
+    );
+    let mut ray = Ray {
+        origin: camera_ray.origin,
+        direction: camera_ray.direction.normalize(),
+    };
+
+    let mut contribution = Vec3::ONE;
+    // Light sampling can't find the emission seen directly by the camera, so
+    // it counts in full, as it does after a specular bounce.
+    let mut scatter = Scatter {
+        pdf: 0.0,
+        specular: true,
+    };
+
+    for _ in 0..NUM_BOUNCES {
+        let BounceSample {
+            hit,
+            light_emitted,
+            next_ray,
+            next_contribution,
+            next_scatter,
+        } = sample_bounce(tracer, lighting, ray, scatter, payload, rand);
+
+        light += light_emitted * contribution;
+        if hit {
+            ray = next_ray;
+            contribution *= next_contribution;
+            scatter = next_scatter;
+        }
+
+        if !hit || contribution.max_element() < 0.001 {
+            break;
+        }
+    }
+
+    light
+}
+
+/// How the ray being traced was scattered from the previous surface.
+#[derive(Clone, Copy)]
+struct Scatter {
+    pdf: f32,
+    specular: bool,
+}
+
+struct BounceSample {
+    hit: bool,
+    /// Light emitted by the surface that was hit, plus the light sampled
+    /// directly from the scene's lights. The environment's light on a miss.
+    light_emitted: Vec3,
+    next_ray: Ray,
+    next_contribution: Vec3,
+    next_scatter: Scatter,
+}
+
+fn sample_bounce<T: Tracer, X: Textures>(
+    tracer: &T,
+    lighting: &Lighting<X>,
+    ray: Ray,
+    scatter: Scatter,
+    payload: &mut RayPayload,
+    rand: &mut Random,
+) -> BounceSample {
+    tracer.trace_ray(ray, 0.001, 10000.0, payload);
+
+    if let Some(distance) = payload.hit {
+        let surface = Surface {
+            position: ray.at(distance),
+            normal: payload.normal,
+            shading_normal: payload.shading_normal,
+            outgoing: ray.direction * -1.0,
+            material: payload.material,
+        };
+
+        // Weight emission against the chance light sampling at the previous
+        // surface would have found it.
+        let mut light_emitted = Vec3::from(surface.material.emit);
+        if !scatter.specular && light_emitted.max_element() > 0.0 {
+            let cos_light = surface.normal.dot(surface.outgoing).abs();
+            let light_pdf =
+                solid_angle_pdf(payload.area, distance, cos_light) / lighting.lights.len() as f32;
+            light_emitted *= power_heuristic(scatter.pdf, light_pdf);
+        }
+
+        if !bsdf::is_specular(&surface.material) {
+            light_emitted += sample_light(tracer, lighting, &surface, payload, rand);
+        }
+
+        let sample = bsdf::sample(
+            &surface.material,
+            surface.shading_normal,
+            surface.outgoing,
+            rand,
+        );
+
+        BounceSample {
+            hit: true,
+            light_emitted,
+            next_ray: Ray {
+                origin: surface.position,
+                direction: sample.incoming,
+            },
+            next_contribution: sample.weight,
+            next_scatter: Scatter {
+                pdf: sample.pdf,
+                specular: sample.specular,
+            },
+        }
+    } else {
+        let mut light_emitted = Vec3::from(payload.material.emit);
+        if !scatter.specular && light_emitted.max_element() > 0.0 {
+            let light_pdf = lighting
+                .environment
+                .pdf(lighting.environment_distribution, ray.direction)
+                / lighting.lights.len() as f32;
+            light_emitted *= power_heuristic(scatter.pdf, light_pdf);
+        }
+
+        BounceSample {
+            hit: false,
+            light_emitted,
+            next_ray: Ray {
+                origin: Vec3::ZERO,
+                direction: Vec3::ZERO,
+            },
+            next_contribution: Vec3::ZERO,
+            next_scatter: scatter,
+        }
+    }
+}
+
+/// A point a ray hit, with the direction back along the ray.
+struct Surface {
+    position: Vec3,
+    normal: Vec3,
+    shading_normal: Vec3,
+    outgoing: Vec3,
+    material: Material,
+}
+
+/// Next event estimation: the light arriving at a surface directly from a
+/// randomly chosen light, weighted against the chance of the BSDF sampling
+/// the same direction.
+fn sample_light<T: Tracer, X: Textures>(
+    tracer: &T,
+    lighting: &Lighting<X>,
+    surface: &Surface,
+    payload: &mut RayPayload,
+    rand: &mut Random,
+) -> Vec3 {
+    let count = lighting.lights.len();
+    let index = ((rand.next_float() * count as f32) as usize).min(count - 1);
+    let light = lighting.lights[index];
+    let random = vec2(rand.next_float(), rand.next_float());
+    let is_environment = light.kind == LightKind::ENVIRONMENT;
+    let sample = if is_environment {
+        lighting
+            .environment
+            .sample(lighting.environment_distribution, random)
+    } else {
+        light.sample(surface.position, random, lighting.textures)
+    };
+    if sample.pdf <= 0.0 || sample.radiance.max_element() <= 0.0 {
+        return Vec3::ZERO;
+    }
+
+    let scattering = bsdf::evaluate(
+        &surface.material,
+        surface.shading_normal,
+        surface.outgoing,
+        sample.direction,
+    );
+    if scattering.value.max_element() <= 0.0 {
+        return Vec3::ZERO;
+    }
+
+    let shadow_ray = Ray {
+        origin: surface.position,
+        direction: sample.direction,
+    };
+    // Stop short of the light so it doesn't shadow itself.
+    if tracer.occluded(shadow_ray, 0.001, sample.distance * 0.999, payload) {
+        return Vec3::ZERO;
+    }
+
+    // The environment's light is only known once the shadow ray escapes.
+    let radiance = if is_environment {
+        Vec3::from(payload.material.emit)
+    } else {
+        sample.radiance
+    };
+
+    let light_pdf = sample.pdf / count as f32;
+    let weight = if sample.delta {
+        1.0
+    } else {
+        power_heuristic(light_pdf, scattering.pdf)
+    };
+
+    radiance * scattering.value * (weight / light_pdf)
+}
+
+/// Multiple importance sampling weight for a sample drawn with probability
+/// density `pdf`, when `other_pdf` could also have drawn it.
+fn power_heuristic(pdf: f32, other_pdf: f32) -> f32 {
+    let (pdf, other_pdf) = (pdf * pdf, other_pdf * other_pdf);
+    if pdf + other_pdf > 0.0 {
+        pdf / (pdf + other_pdf)
+    } else {
+        0.0
+    }
+}
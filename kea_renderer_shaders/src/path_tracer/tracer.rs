@@ -0,0 +1,61 @@
+use crate::payload::RayPayload;
+use kea_gpu_shaderlib::Ray;
+use spirv_std::ray_tracing::{AccelerationStructure, RayFlags};
+
+/// Something rays can be traced against. On the GPU this is the scene's
+/// acceleration structure, on the host it's the CPU reference renderer's BVH.
+pub trait Tracer {
+    /// Find the closest hit along `ray` between `t_min` and `t_max`, filling in
+    /// the payload as the hit shaders would. `payload.hit` is `None` on a miss.
+    fn trace_ray(&self, ray: Ray, t_min: f32, t_max: f32, payload: &mut RayPayload);
+
+    /// Whether anything lies along `ray` between `t_min` and `t_max`. Only
+    /// `payload.hit` is written to, along with the environment's light in
+    /// `payload.material.emit` if nothing does.
+    fn occluded(&self, ray: Ray, t_min: f32, t_max: f32, payload: &mut RayPayload) -> bool;
+}
+
+impl Tracer for AccelerationStructure {
+    fn trace_ray(&self, ray: Ray, t_min: f32, t_max: f32, payload: &mut RayPayload) {
+        unsafe {
+            AccelerationStructure::trace_ray(
+                self,
+                RayFlags::OPAQUE,
+                0xff,
+                0,
+                0,
+                0,
+                ray.origin,
+                t_min,
+                ray.direction,
+                t_max,
+                payload,
+            );
+        }
+    }
+
+    fn occluded(&self, ray: Ray, t_min: f32, t_max: f32, payload: &mut RayPayload) -> bool {
+        // Skipping the hit shaders leaves `hit` as it is unless the miss
+        // shader clears it.
+        payload.hit = Some(t_max);
+        unsafe {
+            AccelerationStructure::trace_ray(
+                self,
+                RayFlags::OPAQUE
+                    | RayFlags::TERMINATE_ON_FIRST_HIT
+                    | RayFlags::SKIP_CLOSEST_HIT_SHADER,
+                0xff,
+                0,
+                0,
+                0,
+                ray.origin,
+                t_min,
+                ray.direction,
+                t_max,
+                payload,
+            );
+        }
+
+        payload.hit.is_some()
+    }
+}
@@ -3,7 +3,18 @@ use spirv_std::glam::Vec3;
 
 #[repr(C)]
 pub struct RayPayload {
+    /// Distance to the hit. On a miss this is `None`, and `material.emit` is
+    /// the light arriving from the environment along the ray.
     pub hit: Option<f32>,
+    /// The outward facing surface normal, whichever side the ray hit.
     pub normal: Vec3,
+    /// The normal to shade with, on the same side as `normal`. Meshes
+    /// interpolate it from their vertex normals and perturb it with normal
+    /// maps; everything else uses `normal`.
+    pub shading_normal: Vec3,
     pub material: Material,
+    /// Surface area of the hit triangle or sphere, for weighting the light it
+    /// emits against the chance of having sampled it as a light. Infinite for
+    /// surfaces that are never sampled as lights.
+    pub area: f32,
 }
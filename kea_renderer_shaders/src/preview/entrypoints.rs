@@ -0,0 +1,41 @@
+use spirv_std::spirv;
+
+use super::shade;
+use spirv_std::glam::{Mat3, Mat4, Vec3, Vec4, Vec4Swizzles};
+
+/// Supplied by the host for each mesh or wireframe drawn.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct PushConstants {
+    /// From object space to clip space.
+    pub clip_from_object: Mat4,
+    /// The columns of the matrix taking normals from object space to view
+    /// space. The last component of each is unused.
+    pub view_normal_from_object: [Vec4; 3],
+    /// Linear RGB, with alpha unused.
+    pub colour: Vec4,
+}
+
+/// Takes each vertex's position and normal in object space, at locations 0
+/// and 1.
+#[spirv(vertex)]
+pub fn preview_vertex(
+    position: Vec3,
+    normal: Vec3,
+    #[spirv(push_constant)] constants: &PushConstants,
+    #[spirv(position)] clip_position: &mut Vec4,
+    view_normal: &mut Vec3,
+) {
+    *clip_position = constants.clip_from_object * position.extend(1.0);
+    let [x, y, z] = constants.view_normal_from_object;
+    *view_normal = Mat3::from_cols(x.xyz(), y.xyz(), z.xyz()) * normal;
+}
+
+#[spirv(fragment)]
+pub fn preview_fragment(
+    view_normal: Vec3,
+    #[spirv(push_constant)] constants: &PushConstants,
+    output: &mut Vec4,
+) {
+    *output = shade(view_normal, constants.colour.xyz()).extend(1.0);
+}
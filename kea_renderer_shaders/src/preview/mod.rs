@@ -0,0 +1,57 @@
+//! A quick rasterized view of the scene, as an alternative to path tracing
+//! it. Meshes are lit by a light at the camera, and anything drawn without
+//! normals, such as wireframes, is drawn in its flat colour.
+
+pub mod entrypoints;
+
+use kea_gpu_shaderlib::shaders::Shader;
+use spirv_std::glam::Vec3;
+
+// Needed for .abs()
+#[allow(unused_imports)]
+use spirv_std::num_traits::Float;
+
+pub const VERTEX_SHADER: Shader = Shader("preview::entrypoints::preview_vertex");
+pub const FRAGMENT_SHADER: Shader = Shader("preview::entrypoints::preview_fragment");
+
+/// The light reaching surfaces facing away from the camera, as a fraction of
+/// the light reaching those facing it.
+const AMBIENT: f32 = 0.2;
+
+/// Light `colour` as seen from the camera, given the surface's normal in view
+/// space. A zero normal leaves the colour unlit.
+pub fn shade(view_normal: Vec3, colour: Vec3) -> Vec3 {
+    if view_normal == Vec3::ZERO {
+        return colour;
+    }
+
+    // Meshes aren't consistently wound, so light both sides.
+    let facing = view_normal.normalize().z.abs();
+    colour * (AMBIENT + (1.0 - AMBIENT) * facing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spirv_std::glam::vec3;
+
+    #[test]
+    fn surfaces_facing_the_camera_are_fully_lit() {
+        let colour = vec3(0.2, 0.4, 0.6);
+        assert!(shade(vec3(0.0, 0.0, 2.0), colour).abs_diff_eq(colour, 1e-6));
+        assert!(shade(vec3(0.0, 0.0, -1.0), colour).abs_diff_eq(colour, 1e-6));
+    }
+
+    #[test]
+    fn edge_on_surfaces_are_lit_by_ambient_light() {
+        let colour = vec3(1.0, 0.5, 0.0);
+        let shaded = shade(vec3(1.0, 0.0, 0.0), colour);
+        assert!(shaded.abs_diff_eq(colour * AMBIENT, 1e-6));
+    }
+
+    #[test]
+    fn missing_normals_leave_colour_unlit() {
+        let colour = vec3(0.3, 0.3, 0.9);
+        assert_eq!(shade(Vec3::ZERO, colour), colour);
+    }
+}
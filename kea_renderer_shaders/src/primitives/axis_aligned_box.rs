@@ -0,0 +1,134 @@
+use super::{closer, hit, Primitive};
+use crate::materials::Material;
+use kea_gpu_shaderlib::{Aabb, Ray};
+use spirv_std::glam::{vec3, Vec3, Vec3A};
+
+/// A box with its faces along the world axes.
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct AxisAlignedBox {
+    min: Vec3A,
+    max: Vec3A,
+    material: Material,
+}
+
+impl AxisAlignedBox {
+    pub fn new(min: Vec3, max: Vec3, material: Material) -> Self {
+        Self {
+            min: Vec3A::from(min.min(max)),
+            max: Vec3A::from(min.max(max)),
+            material,
+        }
+    }
+}
+
+impl Primitive for AxisAlignedBox {
+    fn aabb(&self) -> Aabb {
+        Aabb {
+            min: Vec3::from(self.min),
+            max: Vec3::from(self.max),
+        }
+    }
+
+    fn intersect_ray(&self, ray: Ray, t_min: f32) -> Option<f32> {
+        // The slab method: the ray is inside the box where it's between all
+        // three pairs of planes at once.
+        let inverse_direction = ray.direction.recip();
+        let t0 = (Vec3::from(self.min) - ray.origin) * inverse_direction;
+        let t1 = (Vec3::from(self.max) - ray.origin) * inverse_direction;
+        let near = t0.min(t1).max_element();
+        let far = t0.max(t1).min_element();
+        if near > far {
+            return None;
+        }
+
+        hit(closer(closer(f32::INFINITY, far, t_min), near, t_min))
+    }
+
+    fn normal(&self, point: Vec3) -> Vec3 {
+        // The face the point is on is the one it's relatively furthest
+        // towards from the centre.
+        let centre = Vec3::from(self.min + self.max) / 2.0;
+        let half_size = Vec3::from(self.max - self.min) / 2.0;
+        let offset = (point - centre) / half_size;
+        let distance = offset.abs();
+
+        if distance.x >= distance.y && distance.x >= distance.z {
+            vec3(sign(offset.x), 0.0, 0.0)
+        } else if distance.y >= distance.z {
+            vec3(0.0, sign(offset.y), 0.0)
+        } else {
+            vec3(0.0, 0.0, sign(offset.z))
+        }
+    }
+
+    fn material(&self) -> Material {
+        self.material
+    }
+}
+
+fn sign(value: f32) -> f32 {
+    if value < 0.0 {
+        -1.0
+    } else {
+        1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_box() -> AxisAlignedBox {
+        AxisAlignedBox::new(
+            Vec3::splat(-1.0),
+            Vec3::ONE,
+            Material::lambertian(Vec3A::ONE),
+        )
+    }
+
+    #[test]
+    fn hits_the_near_face() {
+        let ray = Ray {
+            origin: vec3(0.2, 0.3, 5.0),
+            direction: vec3(0.0, 0.0, -1.0),
+        };
+
+        let distance = unit_box().intersect_ray(ray, 0.001).unwrap();
+        assert!((distance - 4.0).abs() < 1e-5);
+        assert_eq!(unit_box().normal(ray.at(distance)), vec3(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn hits_the_far_face_from_inside() {
+        let ray = Ray {
+            origin: Vec3::ZERO,
+            direction: vec3(1.0, 0.0, 0.0),
+        };
+
+        let distance = unit_box().intersect_ray(ray, 0.001).unwrap();
+        assert!((distance - 1.0).abs() < 1e-5);
+        assert_eq!(unit_box().normal(ray.at(distance)), vec3(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn misses_past_the_edge() {
+        let ray = Ray {
+            origin: vec3(1.5, 0.0, 5.0),
+            direction: vec3(0.0, 0.0, -1.0),
+        };
+
+        assert_eq!(unit_box().intersect_ray(ray, 0.001), None);
+    }
+
+    #[test]
+    fn misses_behind_the_ray() {
+        let ray = Ray {
+            origin: vec3(0.0, 0.0, 5.0),
+            direction: vec3(0.0, 0.0, 1.0),
+        };
+
+        assert_eq!(unit_box().intersect_ray(ray, 0.001), None);
+    }
+}
@@ -0,0 +1,204 @@
+use super::{closer, hit, ring_extent, Primitive};
+use crate::materials::Material;
+use kea_gpu_shaderlib::{Aabb, Ray};
+use spirv_std::glam::{Vec3, Vec3A};
+
+// Needed for .sqrt()
+#[allow(unused_imports)]
+use spirv_std::num_traits::Float;
+
+/// A cone closed at its base, narrowing along its axis to a point.
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct Cone {
+    /// The centre of the base.
+    base: Vec3A,
+    axis: Vec3A,
+    height: f32,
+    /// The radius of the base.
+    radius: f32,
+    material: Material,
+}
+
+impl Cone {
+    pub fn new(base: Vec3, axis: Vec3, height: f32, radius: f32, material: Material) -> Self {
+        Self {
+            base: Vec3A::from(base),
+            axis: Vec3A::from(axis.normalize()),
+            height,
+            radius,
+            material,
+        }
+    }
+
+    fn apex(&self) -> Vec3 {
+        Vec3::from(self.base + self.axis * self.height)
+    }
+
+    /// The square of the side's secant to the axis, which relates a point's
+    /// distance from the apex to how far down the axis it is.
+    fn slope(&self) -> f32 {
+        let tangent = self.radius / self.height;
+        1.0 + tangent * tangent
+    }
+
+    /// Whether a depth down the axis from the apex is on the cone.
+    fn spans(&self, depth: f32) -> bool {
+        depth >= 0.0 && depth <= self.height
+    }
+}
+
+impl Primitive for Cone {
+    fn aabb(&self) -> Aabb {
+        let extent = ring_extent(Vec3::from(self.axis), self.radius);
+        let base = Vec3::from(self.base);
+
+        Aabb {
+            min: (base - extent).min(self.apex()),
+            max: (base + extent).max(self.apex()),
+        }
+    }
+
+    fn intersect_ray(&self, ray: Ray, t_min: f32) -> Option<f32> {
+        // Working from the apex, down the axis towards the base. Points on the
+        // side are `slope` times their depth squared from the apex.
+        let down = -Vec3::from(self.axis);
+        let offset = ray.origin - self.apex();
+        let offset_depth = offset.dot(down);
+        let direction_depth = ray.direction.dot(down);
+        let slope = self.slope();
+
+        let mut closest = f32::INFINITY;
+
+        // The side. The same equation also holds on the mirror image of the
+        // cone above the apex, which `spans` rules out.
+        let a = ray.direction.length_squared() - slope * direction_depth * direction_depth;
+        let h = ray.direction.dot(offset) - slope * direction_depth * offset_depth;
+        let c = offset.length_squared() - slope * offset_depth * offset_depth;
+        if a.abs() < 1e-12 {
+            // Parallel to the side, so there's only one hit.
+            if h.abs() > 1e-12 {
+                let distance = -c / (2.0 * h);
+                if self.spans(offset_depth + direction_depth * distance) {
+                    closest = closer(closest, distance, t_min);
+                }
+            }
+        } else {
+            let discriminant = h * h - a * c;
+            if discriminant >= 0.0 {
+                let near = (-h - discriminant.sqrt()) / a;
+                let far = (-h + discriminant.sqrt()) / a;
+                if self.spans(offset_depth + direction_depth * near) {
+                    closest = closer(closest, near, t_min);
+                }
+                if self.spans(offset_depth + direction_depth * far) {
+                    closest = closer(closest, far, t_min);
+                }
+            }
+        }
+
+        // The base.
+        if direction_depth.abs() > 1e-8 {
+            let distance = (self.height - offset_depth) / direction_depth;
+            let across = ray.at(distance) - Vec3::from(self.base);
+            if across.length_squared() <= self.radius * self.radius {
+                closest = closer(closest, distance, t_min);
+            }
+        }
+
+        hit(closest)
+    }
+
+    fn normal(&self, point: Vec3) -> Vec3 {
+        let down = -Vec3::from(self.axis);
+        let offset = point - self.apex();
+        let depth = offset.dot(down);
+        let across = offset - down * depth;
+
+        // Whichever surface the point is nearest to.
+        let slope = self.slope();
+        let to_side = (across.length() - depth * self.radius / self.height).abs() / slope.sqrt();
+        let to_base = (self.height - depth).abs();
+        if to_side <= to_base {
+            (offset - down * (slope * depth)).normalize()
+        } else {
+            down
+        }
+    }
+
+    fn material(&self) -> Material {
+        self.material
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spirv_std::glam::vec3;
+
+    /// Standing on the origin, 2 high with a base of radius 1.
+    fn cone() -> Cone {
+        Cone::new(
+            Vec3::ZERO,
+            vec3(0.0, 1.0, 0.0),
+            2.0,
+            1.0,
+            Material::lambertian(Vec3A::ONE),
+        )
+    }
+
+    #[test]
+    fn hits_the_side() {
+        let ray = Ray {
+            origin: vec3(-3.0, 1.0, 0.0),
+            direction: vec3(1.0, 0.0, 0.0),
+        };
+
+        let distance = cone().intersect_ray(ray, 0.001).unwrap();
+        assert!((distance - 2.5).abs() < 1e-5);
+        let expected = vec3(-2.0, 1.0, 0.0).normalize();
+        assert!((cone().normal(ray.at(distance)) - expected).length() < 1e-5);
+    }
+
+    #[test]
+    fn hits_the_base() {
+        let ray = Ray {
+            origin: vec3(0.2, -3.0, 0.0),
+            direction: vec3(0.0, 1.0, 0.0),
+        };
+
+        let distance = cone().intersect_ray(ray, 0.001).unwrap();
+        assert!((distance - 3.0).abs() < 1e-5);
+        assert!((cone().normal(ray.at(distance)) - vec3(0.0, -1.0, 0.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn hits_the_side_from_inside() {
+        let ray = Ray {
+            origin: vec3(0.0, 0.5, 0.0),
+            direction: vec3(0.0, 0.0, 1.0),
+        };
+
+        let distance = cone().intersect_ray(ray, 0.001).unwrap();
+        assert!((distance - 0.75).abs() < 1e-5);
+    }
+
+    #[test]
+    fn misses_the_mirror_image_above_the_apex() {
+        let ray = Ray {
+            origin: vec3(-3.0, 3.0, 0.0),
+            direction: vec3(1.0, 0.0, 0.0),
+        };
+
+        assert_eq!(cone().intersect_ray(ray, 0.001), None);
+    }
+
+    #[test]
+    fn bounds_its_base_and_apex() {
+        let aabb = cone().aabb();
+
+        assert!((aabb.min - vec3(-1.0, 0.0, -1.0)).abs().max_element() < 1e-5);
+        assert!((aabb.max - vec3(1.0, 2.0, 1.0)).abs().max_element() < 1e-5);
+    }
+}
@@ -0,0 +1,195 @@
+use super::{closer, hit, ring_extent, Primitive};
+use crate::materials::Material;
+use kea_gpu_shaderlib::{Aabb, Ray};
+use spirv_std::glam::{Vec3, Vec3A};
+
+// Needed for .sqrt()
+#[allow(unused_imports)]
+use spirv_std::num_traits::Float;
+
+/// A cylinder closed at both ends, standing on its base along its axis.
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct Cylinder {
+    /// The centre of the bottom cap.
+    base: Vec3A,
+    axis: Vec3A,
+    height: f32,
+    radius: f32,
+    material: Material,
+}
+
+impl Cylinder {
+    pub fn new(base: Vec3, axis: Vec3, height: f32, radius: f32, material: Material) -> Self {
+        Self {
+            base: Vec3A::from(base),
+            axis: Vec3A::from(axis.normalize()),
+            height,
+            radius,
+            material,
+        }
+    }
+
+    fn top(&self) -> Vec3 {
+        Vec3::from(self.base + self.axis * self.height)
+    }
+
+    /// Whether a height along the axis is between the caps.
+    fn spans(&self, height: f32) -> bool {
+        height >= 0.0 && height <= self.height
+    }
+
+    /// Whether an offset across the axis is within the radius.
+    fn covers(&self, across: Vec3) -> bool {
+        across.length_squared() <= self.radius * self.radius
+    }
+}
+
+impl Primitive for Cylinder {
+    fn aabb(&self) -> Aabb {
+        let extent = ring_extent(Vec3::from(self.axis), self.radius);
+        let base = Vec3::from(self.base);
+
+        Aabb {
+            min: base.min(self.top()) - extent,
+            max: base.max(self.top()) + extent,
+        }
+    }
+
+    fn intersect_ray(&self, ray: Ray, t_min: f32) -> Option<f32> {
+        let axis = Vec3::from(self.axis);
+        let offset = ray.origin - Vec3::from(self.base);
+        // Heights along the axis, and the parts across it.
+        let offset_height = offset.dot(axis);
+        let direction_height = ray.direction.dot(axis);
+        let offset_across = offset - axis * offset_height;
+        let direction_across = ray.direction - axis * direction_height;
+
+        let mut closest = f32::INFINITY;
+
+        // The side, where the distance from the axis is the radius.
+        let a = direction_across.length_squared();
+        let h = offset_across.dot(direction_across);
+        let c = offset_across.length_squared() - self.radius * self.radius;
+        let discriminant = h * h - a * c;
+        if a > 1e-12 && discriminant >= 0.0 {
+            let near = (-h - discriminant.sqrt()) / a;
+            let far = (-h + discriminant.sqrt()) / a;
+            if self.spans(offset_height + direction_height * near) {
+                closest = closer(closest, near, t_min);
+            }
+            if self.spans(offset_height + direction_height * far) {
+                closest = closer(closest, far, t_min);
+            }
+        }
+
+        // The caps, where the height is 0 or the cylinder's height.
+        if direction_height.abs() > 1e-8 {
+            let bottom = -offset_height / direction_height;
+            let top = (self.height - offset_height) / direction_height;
+            if self.covers(offset_across + direction_across * bottom) {
+                closest = closer(closest, bottom, t_min);
+            }
+            if self.covers(offset_across + direction_across * top) {
+                closest = closer(closest, top, t_min);
+            }
+        }
+
+        hit(closest)
+    }
+
+    fn normal(&self, point: Vec3) -> Vec3 {
+        let axis = Vec3::from(self.axis);
+        let offset = point - Vec3::from(self.base);
+        let height = offset.dot(axis);
+        let across = offset - axis * height;
+
+        // Whichever surface the point is nearest to.
+        let to_side = (across.length() - self.radius).abs();
+        let to_bottom = height.abs();
+        let to_top = (self.height - height).abs();
+        if to_side <= to_bottom && to_side <= to_top {
+            across.normalize()
+        } else if to_bottom <= to_top {
+            -axis
+        } else {
+            axis
+        }
+    }
+
+    fn material(&self) -> Material {
+        self.material
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spirv_std::glam::vec3;
+
+    /// Upright, from y = -1 to y = 1, with radius 0.5.
+    fn cylinder() -> Cylinder {
+        Cylinder::new(
+            vec3(0.0, -1.0, 0.0),
+            vec3(0.0, 2.0, 0.0),
+            2.0,
+            0.5,
+            Material::lambertian(Vec3A::ONE),
+        )
+    }
+
+    #[test]
+    fn hits_the_side() {
+        let ray = Ray {
+            origin: vec3(-3.0, 0.5, 0.0),
+            direction: vec3(1.0, 0.0, 0.0),
+        };
+
+        let distance = cylinder().intersect_ray(ray, 0.001).unwrap();
+        assert!((distance - 2.5).abs() < 1e-5);
+        assert!((cylinder().normal(ray.at(distance)) - vec3(-1.0, 0.0, 0.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn hits_the_top_cap() {
+        let ray = Ray {
+            origin: vec3(0.2, 4.0, 0.1),
+            direction: vec3(0.0, -1.0, 0.0),
+        };
+
+        let distance = cylinder().intersect_ray(ray, 0.001).unwrap();
+        assert!((distance - 3.0).abs() < 1e-5);
+        assert!((cylinder().normal(ray.at(distance)) - vec3(0.0, 1.0, 0.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn hits_the_bottom_cap_from_inside() {
+        let ray = Ray {
+            origin: Vec3::ZERO,
+            direction: vec3(0.0, -1.0, 0.0),
+        };
+
+        let distance = cylinder().intersect_ray(ray, 0.001).unwrap();
+        assert!((distance - 1.0).abs() < 1e-5);
+        assert!((cylinder().normal(ray.at(distance)) - vec3(0.0, -1.0, 0.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn misses_above_the_top() {
+        let ray = Ray {
+            origin: vec3(-3.0, 1.5, 0.0),
+            direction: vec3(1.0, 0.0, 0.0),
+        };
+
+        assert_eq!(cylinder().intersect_ray(ray, 0.001), None);
+    }
+
+    #[test]
+    fn bounds_its_caps() {
+        let aabb = cylinder().aabb();
+
+        assert!((aabb.min - vec3(-0.5, -1.0, -0.5)).abs().max_element() < 1e-5);
+        assert!((aabb.max - vec3(0.5, 1.0, 0.5)).abs().max_element() < 1e-5);
+    }
+}
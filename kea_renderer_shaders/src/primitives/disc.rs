@@ -0,0 +1,113 @@
+use super::{closer, hit, ring_extent, Primitive};
+use crate::materials::Material;
+use kea_gpu_shaderlib::{Aabb, Ray};
+use spirv_std::glam::{Vec3, Vec3A};
+
+/// A flat circle, facing the way its normal points.
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct Disc {
+    centre: Vec3A,
+    normal: Vec3A,
+    radius: f32,
+    material: Material,
+}
+
+impl Disc {
+    pub fn new(centre: Vec3, normal: Vec3, radius: f32, material: Material) -> Self {
+        Self {
+            centre: Vec3A::from(centre),
+            normal: Vec3A::from(normal.normalize()),
+            radius,
+            material,
+        }
+    }
+}
+
+impl Primitive for Disc {
+    fn aabb(&self) -> Aabb {
+        let extent = ring_extent(Vec3::from(self.normal), self.radius);
+
+        Aabb {
+            min: Vec3::from(self.centre) - extent,
+            max: Vec3::from(self.centre) + extent,
+        }
+    }
+
+    fn intersect_ray(&self, ray: Ray, t_min: f32) -> Option<f32> {
+        let normal = Vec3::from(self.normal);
+        let centre = Vec3::from(self.centre);
+        let speed = normal.dot(ray.direction);
+        if speed.abs() < 1e-8 {
+            return None;
+        }
+
+        let distance = normal.dot(centre - ray.origin) / speed;
+        if ray.at(distance).distance_squared(centre) > self.radius * self.radius {
+            return None;
+        }
+
+        hit(closer(f32::INFINITY, distance, t_min))
+    }
+
+    fn normal(&self, _point: Vec3) -> Vec3 {
+        Vec3::from(self.normal)
+    }
+
+    fn material(&self) -> Material {
+        self.material
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spirv_std::glam::vec3;
+
+    fn disc() -> Disc {
+        Disc::new(
+            vec3(0.0, 0.0, -2.0),
+            vec3(0.0, 0.0, 1.0),
+            1.0,
+            Material::lambertian(Vec3A::ONE),
+        )
+    }
+
+    #[test]
+    fn hits_inside_the_radius() {
+        let ray = Ray {
+            origin: vec3(0.5, 0.5, 0.0),
+            direction: vec3(0.0, 0.0, -1.0),
+        };
+
+        let distance = disc().intersect_ray(ray, 0.001).unwrap();
+        assert!((distance - 2.0).abs() < 1e-5);
+        assert_eq!(disc().normal(ray.at(distance)), vec3(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn misses_outside_the_radius() {
+        let ray = Ray {
+            origin: vec3(0.8, 0.8, 0.0),
+            direction: vec3(0.0, 0.0, -1.0),
+        };
+
+        assert_eq!(disc().intersect_ray(ray, 0.001), None);
+    }
+
+    #[test]
+    fn bounds_a_tilted_disc() {
+        let disc = Disc::new(
+            Vec3::ZERO,
+            vec3(1.0, 1.0, 0.0),
+            1.0,
+            Material::lambertian(Vec3A::ONE),
+        );
+        let aabb = disc.aabb();
+        let half = 0.5f32.sqrt();
+
+        assert!((aabb.max - vec3(half, half, 1.0)).abs().max_element() < 1e-5);
+        assert!((aabb.min + vec3(half, half, 1.0)).abs().max_element() < 1e-5);
+    }
+}
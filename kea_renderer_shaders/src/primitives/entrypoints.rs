@@ -0,0 +1,58 @@
+use spirv_std::spirv;
+
+use super::{shade, AxisAlignedBox, Cone, Cylinder, Disc, Plane, Primitive, Torus};
+use crate::payload::RayPayload;
+use kea_gpu_shaderlib::Ray;
+use spirv_std::{arch::report_intersection, glam::Vec3};
+
+/// The intersection and closest hit shaders for one kind of primitive, read
+/// from the storage buffer at `binding`.
+macro_rules! primitive_shaders {
+    ($primitive:ty, $binding:literal, $intersect:ident, $hit:ident) => {
+        #[spirv(intersection)]
+        pub fn $intersect(
+            #[spirv(world_ray_origin)] ray_origin: Vec3,
+            #[spirv(world_ray_direction)] ray_direction: Vec3,
+            #[spirv(ray_tmin)] t_min: f32,
+            #[spirv(primitive_id)] primitive_id: usize,
+            #[spirv(storage_buffer, descriptor_set = 0, binding = $binding)]
+            primitives: &[$primitive],
+        ) {
+            let ray = Ray {
+                origin: ray_origin,
+                direction: ray_direction,
+            };
+
+            if let Some(hit) = primitives[primitive_id].intersect_ray(ray, t_min) {
+                unsafe {
+                    report_intersection(hit, 0);
+                }
+            }
+        }
+
+        #[spirv(closest_hit)]
+        pub fn $hit(
+            #[spirv(world_ray_origin)] ray_origin: Vec3,
+            #[spirv(world_ray_direction)] ray_direction: Vec3,
+            #[spirv(ray_tmax)] hit_max: f32,
+            #[spirv(incoming_ray_payload)] ray_payload: &mut RayPayload,
+            #[spirv(primitive_id)] primitive_id: usize,
+            #[spirv(storage_buffer, descriptor_set = 0, binding = $binding)]
+            primitives: &[$primitive],
+        ) {
+            let ray = Ray {
+                origin: ray_origin,
+                direction: ray_direction,
+            };
+
+            *ray_payload = shade(&primitives[primitive_id], ray, hit_max);
+        }
+    };
+}
+
+primitive_shaders!(AxisAlignedBox, 12, intersect_box, box_hit);
+primitive_shaders!(Plane, 13, intersect_plane, plane_hit);
+primitive_shaders!(Disc, 14, intersect_disc, disc_hit);
+primitive_shaders!(Cylinder, 15, intersect_cylinder, cylinder_hit);
+primitive_shaders!(Cone, 16, intersect_cone, cone_hit);
+primitive_shaders!(Torus, 17, intersect_torus, torus_hit);
@@ -0,0 +1,194 @@
+//! Analytic shapes traced with intersection shaders, alongside the spheres.
+//! Each kind has its own storage buffer of primitives in world space, and its
+//! own hit group, with an AABB in the acceleration structure for each
+//! primitive.
+//!
+//! Emissive primitives glow when hit, but aren't sampled as lights.
+
+mod axis_aligned_box;
+mod cone;
+mod cylinder;
+mod disc;
+pub mod entrypoints;
+mod plane;
+mod polynomials;
+mod torus;
+
+use crate::{materials::Material, payload::RayPayload, ShaderGroupId, SlotId};
+use core::any::TypeId;
+use kea_gpu_shaderlib::{
+    shaders::{Shader, ShaderGroup},
+    slots::{ShaderStages, Slot, SlotType},
+    Aabb, Ray,
+};
+use spirv_std::glam::{vec3, Vec3};
+
+// Needed for .sqrt()
+#[allow(unused_imports)]
+use spirv_std::num_traits::Float;
+
+pub use axis_aligned_box::AxisAlignedBox;
+pub use cone::Cone;
+pub use cylinder::Cylinder;
+pub use disc::Disc;
+pub use plane::Plane;
+pub use torus::Torus;
+
+/// A shape with an analytic ray intersection.
+pub trait Primitive {
+    fn aabb(&self) -> Aabb;
+
+    /// The distance along `ray` to the closest hit beyond `t_min`. Rays
+    /// starting inside closed shapes hit them on the way out.
+    fn intersect_ray(&self, ray: Ray, t_min: f32) -> Option<f32>;
+
+    /// The outward normal at `point`, which is on the surface.
+    fn normal(&self, point: Vec3) -> Vec3;
+
+    fn material(&self) -> Material;
+}
+
+/// The payload for a ray hitting `primitive` `distance` along it, as the
+/// closest hit shaders fill it in.
+pub fn shade<P: Primitive + ?Sized>(primitive: &P, ray: Ray, distance: f32) -> RayPayload {
+    let normal = primitive.normal(ray.at(distance));
+
+    RayPayload {
+        hit: Some(distance),
+        normal,
+        shading_normal: normal,
+        material: primitive.material(),
+        // They aren't lights, so nothing else could have sampled them.
+        area: f32::INFINITY,
+    }
+}
+
+/// `candidate` if it's beyond `t_min` and closer than `closest`, otherwise
+/// `closest`. Intersections start with `f32::INFINITY` and narrow it down.
+fn closer(closest: f32, candidate: f32, t_min: f32) -> f32 {
+    if candidate > t_min && candidate < closest {
+        candidate
+    } else {
+        closest
+    }
+}
+
+/// `closest` from `closer`, or `None` if nothing was hit.
+fn hit(closest: f32) -> Option<f32> {
+    if closest < f32::INFINITY {
+        Some(closest)
+    } else {
+        None
+    }
+}
+
+/// Two unit vectors perpendicular to the unit vector `n` and each other,
+/// from Duff et al., "Building an Orthonormal Basis, Revisited".
+fn orthonormal_basis(n: Vec3) -> (Vec3, Vec3) {
+    let sign = if n.z >= 0.0 { 1.0 } else { -1.0 };
+    let a = -1.0 / (sign + n.z);
+    let b = n.x * n.y * a;
+
+    (
+        vec3(1.0 + sign * n.x * n.x * a, sign * b, -sign * n.x),
+        vec3(b, sign + n.y * n.y * a, -n.y),
+    )
+}
+
+/// How far a ring of `radius` about the unit vector `axis` reaches along each
+/// world axis.
+fn ring_extent(axis: Vec3, radius: f32) -> Vec3 {
+    vec3(
+        (1.0 - axis.x * axis.x).max(0.0).sqrt(),
+        (1.0 - axis.y * axis.y).max(0.0).sqrt(),
+        (1.0 - axis.z * axis.z).max(0.0).sqrt(),
+    ) * radius
+}
+
+const STAGES: ShaderStages = ShaderStages {
+    raygen: false,
+    intersection: true,
+    closest_hit: true,
+    miss: false,
+    compute: false,
+    vertex: false,
+    fragment: false,
+};
+
+pub const SHADER_BOXES: (ShaderGroupId, ShaderGroup) = (
+    ShaderGroupId::BoxesHit,
+    ShaderGroup::ProceduralHit {
+        intersection: Shader("primitives::entrypoints::intersect_box"),
+        hit: Shader("primitives::entrypoints::box_hit"),
+    },
+);
+
+pub const SHADER_PLANES: (ShaderGroupId, ShaderGroup) = (
+    ShaderGroupId::PlanesHit,
+    ShaderGroup::ProceduralHit {
+        intersection: Shader("primitives::entrypoints::intersect_plane"),
+        hit: Shader("primitives::entrypoints::plane_hit"),
+    },
+);
+
+pub const SHADER_DISCS: (ShaderGroupId, ShaderGroup) = (
+    ShaderGroupId::DiscsHit,
+    ShaderGroup::ProceduralHit {
+        intersection: Shader("primitives::entrypoints::intersect_disc"),
+        hit: Shader("primitives::entrypoints::disc_hit"),
+    },
+);
+
+pub const SHADER_CYLINDERS: (ShaderGroupId, ShaderGroup) = (
+    ShaderGroupId::CylindersHit,
+    ShaderGroup::ProceduralHit {
+        intersection: Shader("primitives::entrypoints::intersect_cylinder"),
+        hit: Shader("primitives::entrypoints::cylinder_hit"),
+    },
+);
+
+pub const SHADER_CONES: (ShaderGroupId, ShaderGroup) = (
+    ShaderGroupId::ConesHit,
+    ShaderGroup::ProceduralHit {
+        intersection: Shader("primitives::entrypoints::intersect_cone"),
+        hit: Shader("primitives::entrypoints::cone_hit"),
+    },
+);
+
+pub const SHADER_TORI: (ShaderGroupId, ShaderGroup) = (
+    ShaderGroupId::ToriHit,
+    ShaderGroup::ProceduralHit {
+        intersection: Shader("primitives::entrypoints::intersect_torus"),
+        hit: Shader("primitives::entrypoints::torus_hit"),
+    },
+);
+
+pub const SLOT_BOXES: (SlotId, Slot) = (
+    SlotId::Boxes,
+    Slot::new(SlotType::Buffer(TypeId::of::<&[AxisAlignedBox]>()), STAGES),
+);
+
+pub const SLOT_PLANES: (SlotId, Slot) = (
+    SlotId::Planes,
+    Slot::new(SlotType::Buffer(TypeId::of::<&[Plane]>()), STAGES),
+);
+
+pub const SLOT_DISCS: (SlotId, Slot) = (
+    SlotId::Discs,
+    Slot::new(SlotType::Buffer(TypeId::of::<&[Disc]>()), STAGES),
+);
+
+pub const SLOT_CYLINDERS: (SlotId, Slot) = (
+    SlotId::Cylinders,
+    Slot::new(SlotType::Buffer(TypeId::of::<&[Cylinder]>()), STAGES),
+);
+
+pub const SLOT_CONES: (SlotId, Slot) = (
+    SlotId::Cones,
+    Slot::new(SlotType::Buffer(TypeId::of::<&[Cone]>()), STAGES),
+);
+
+pub const SLOT_TORI: (SlotId, Slot) = (
+    SlotId::Tori,
+    Slot::new(SlotType::Buffer(TypeId::of::<&[Torus]>()), STAGES),
+);
@@ -0,0 +1,115 @@
+use super::{closer, hit, Primitive};
+use crate::materials::Material;
+use kea_gpu_shaderlib::{Aabb, Ray};
+use spirv_std::glam::{Vec3, Vec3A};
+
+/// How far planes reach from the origin in every direction. Acceleration
+/// structures need finite bounds, so this is as far as rays are traced.
+pub const PLANE_EXTENT: f32 = 10000.0;
+
+/// An infinite plane, facing the way its normal points.
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct Plane {
+    normal: Vec3A,
+    /// Distance from the origin along the normal.
+    offset: f32,
+    material: Material,
+}
+
+impl Plane {
+    pub fn new(point: Vec3, normal: Vec3, material: Material) -> Self {
+        let normal = normal.normalize();
+
+        Self {
+            normal: Vec3A::from(normal),
+            offset: normal.dot(point),
+            material,
+        }
+    }
+}
+
+impl Primitive for Plane {
+    fn aabb(&self) -> Aabb {
+        Aabb {
+            min: Vec3::splat(-PLANE_EXTENT),
+            max: Vec3::splat(PLANE_EXTENT),
+        }
+    }
+
+    fn intersect_ray(&self, ray: Ray, t_min: f32) -> Option<f32> {
+        let normal = Vec3::from(self.normal);
+        let speed = normal.dot(ray.direction);
+        if speed.abs() < 1e-8 {
+            return None;
+        }
+
+        hit(closer(
+            f32::INFINITY,
+            (self.offset - normal.dot(ray.origin)) / speed,
+            t_min,
+        ))
+    }
+
+    fn normal(&self, _point: Vec3) -> Vec3 {
+        Vec3::from(self.normal)
+    }
+
+    fn material(&self) -> Material {
+        self.material
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spirv_std::glam::vec3;
+
+    fn floor() -> Plane {
+        Plane::new(
+            vec3(3.0, -1.0, 2.0),
+            vec3(0.0, 2.0, 0.0),
+            Material::lambertian(Vec3A::ONE),
+        )
+    }
+
+    #[test]
+    fn hits_at_an_angle() {
+        let ray = Ray {
+            origin: vec3(0.0, 1.0, 0.0),
+            direction: vec3(1.0, -1.0, 0.0).normalize(),
+        };
+
+        let distance = floor().intersect_ray(ray, 0.001).unwrap();
+        assert!((distance - 2.0 * 2.0f32.sqrt()).abs() < 1e-5);
+        assert!((ray.at(distance).y + 1.0).abs() < 1e-5);
+        assert_eq!(floor().normal(ray.at(distance)), vec3(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn hits_from_below() {
+        let ray = Ray {
+            origin: vec3(0.0, -3.0, 0.0),
+            direction: vec3(0.0, 1.0, 0.0),
+        };
+
+        let distance = floor().intersect_ray(ray, 0.001).unwrap();
+        assert!((distance - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn misses_parallel_and_receding_rays() {
+        let parallel = Ray {
+            origin: vec3(0.0, 1.0, 0.0),
+            direction: vec3(1.0, 0.0, 0.0),
+        };
+        let receding = Ray {
+            origin: vec3(0.0, 1.0, 0.0),
+            direction: vec3(0.0, 1.0, 0.0),
+        };
+
+        assert_eq!(floor().intersect_ray(parallel, 0.001), None);
+        assert_eq!(floor().intersect_ray(receding, 0.001), None);
+    }
+}
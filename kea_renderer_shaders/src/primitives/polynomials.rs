@@ -0,0 +1,177 @@
+//! Real roots of the polynomials that ray intersections come down to.
+
+// Needed for .sqrt()
+#[allow(unused_imports)]
+use spirv_std::num_traits::Float;
+
+/// Up to four real roots, in no particular order.
+pub struct Roots {
+    pub values: [f32; 4],
+    pub count: usize,
+}
+
+impl Roots {
+    fn new() -> Self {
+        Self {
+            values: [0.0; 4],
+            count: 0,
+        }
+    }
+
+    fn push(&mut self, root: f32) {
+        if self.count < 4 {
+            self.values[self.count] = root;
+            self.count += 1;
+        }
+    }
+
+    /// Add the real roots of `x² + b x + c`.
+    fn push_quadratic(&mut self, b: f32, c: f32) {
+        let discriminant = b * b / 4.0 - c;
+        if discriminant >= 0.0 {
+            let root = discriminant.sqrt();
+            self.push(-b / 2.0 - root);
+            self.push(-b / 2.0 + root);
+        }
+    }
+}
+
+/// The real roots of `x⁴ + b x³ + c x² + d x + e`, by Ferrari's method.
+pub fn solve_quartic(b: f32, c: f32, d: f32, e: f32) -> Roots {
+    // Substituting `x = y - b / 4` leaves `y⁴ + p y² + q y + r`.
+    let b2 = b * b;
+    let p = c - 3.0 * b2 / 8.0;
+    let q = d - b * c / 2.0 + b2 * b / 8.0;
+    let r = e - b * d / 4.0 + b2 * c / 16.0 - 3.0 * b2 * b2 / 256.0;
+
+    // Adding `m` inside `(y² + p / 2 + m)²` makes the rest a perfect square
+    // when `m` solves the resolvent cubic, so the quartic factors into two
+    // quadratics.
+    let m = largest_cubic_root(p, p * p / 4.0 - r, -q * q / 8.0);
+
+    let mut depressed = Roots::new();
+    if m <= 1e-10 {
+        // `q` is zero, so it's a quadratic in `y²`.
+        let mut squares = Roots::new();
+        squares.push_quadratic(p, r);
+        for i in 0..squares.count {
+            if squares.values[i] >= 0.0 {
+                let root = squares.values[i].sqrt();
+                depressed.push(-root);
+                depressed.push(root);
+            }
+        }
+    } else {
+        let s = (2.0 * m).sqrt();
+        depressed.push_quadratic(-s, p / 2.0 + m + q / (2.0 * s));
+        depressed.push_quadratic(s, p / 2.0 + m - q / (2.0 * s));
+    }
+
+    // Polish the roots against the original quartic, as the substitutions
+    // lose precision.
+    let mut roots = Roots::new();
+    for i in 0..depressed.count {
+        let mut x = depressed.values[i] - b / 4.0;
+        for _ in 0..2 {
+            let value = (((x + b) * x + c) * x + d) * x + e;
+            let slope = ((4.0 * x + 3.0 * b) * x + 2.0 * c) * x + d;
+            if slope.abs() > 1e-12 {
+                x -= value / slope;
+            }
+        }
+        roots.push(x);
+    }
+
+    roots
+}
+
+/// The largest real root of `x³ + a x² + b x + c`.
+pub fn largest_cubic_root(a: f32, b: f32, c: f32) -> f32 {
+    // Substituting `x = t - a / 3` leaves `t³ + p t + q`.
+    let p = b - a * a / 3.0;
+    let q = 2.0 * a * a * a / 27.0 - a * b / 3.0 + c;
+    let discriminant = q * q / 4.0 + p * p * p / 27.0;
+
+    let t = if discriminant >= 0.0 {
+        // One real root, by Cardano's formula.
+        let root = discriminant.sqrt();
+        cube_root(-q / 2.0 + root) + cube_root(-q / 2.0 - root)
+    } else {
+        // Three real roots, of which the trigonometric method's first is the
+        // largest.
+        let radius = (-p / 3.0).sqrt();
+        let cos = (-q / (2.0 * radius * radius * radius)).clamp(-1.0, 1.0);
+        2.0 * radius * (cos.acos() / 3.0).cos()
+    };
+
+    let mut x = t - a / 3.0;
+    for _ in 0..2 {
+        let value = ((x + a) * x + b) * x + c;
+        let slope = (3.0 * x + 2.0 * a) * x + b;
+        if slope.abs() > 1e-12 {
+            x -= value / slope;
+        }
+    }
+
+    x
+}
+
+fn cube_root(x: f32) -> f32 {
+    if x < 0.0 {
+        -(-x).powf(1.0 / 3.0)
+    } else {
+        x.powf(1.0 / 3.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(roots: Roots) -> Vec<f32> {
+        let mut values = roots.values[..roots.count].to_vec();
+        values.sort_by(f32::total_cmp);
+        values
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?} != {expected:?}");
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!((actual - expected).abs() < 1e-4, "{actual} != {expected}");
+        }
+    }
+
+    #[test]
+    fn solves_a_quartic_with_four_roots() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        let roots = solve_quartic(-10.0, 35.0, -50.0, 24.0);
+        assert_close(&sorted(roots), &[1.0, 2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn solves_a_quartic_with_two_roots() {
+        // (x - 1)(x + 3)(x² + 1)
+        let roots = solve_quartic(2.0, -2.0, 2.0, -3.0);
+        assert_close(&sorted(roots), &[-3.0, 1.0]);
+    }
+
+    #[test]
+    fn solves_a_biquadratic() {
+        // (x² - 1)(x² - 4)
+        let roots = solve_quartic(0.0, -5.0, 0.0, 4.0);
+        assert_close(&sorted(roots), &[-2.0, -1.0, 1.0, 2.0]);
+    }
+
+    #[test]
+    fn finds_no_roots_of_a_positive_quartic() {
+        assert_eq!(solve_quartic(0.0, 0.0, 0.0, 1.0).count, 0);
+    }
+
+    #[test]
+    fn finds_the_largest_cubic_root() {
+        // (x - 1)(x - 2)(x - 3)
+        assert!((largest_cubic_root(-6.0, 11.0, -6.0) - 3.0).abs() < 1e-4);
+        // (x + 1)(x² - x + 2)
+        assert!((largest_cubic_root(0.0, 1.0, 2.0) + 1.0).abs() < 1e-4);
+    }
+}
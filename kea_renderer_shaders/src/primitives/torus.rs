@@ -0,0 +1,195 @@
+use super::{closer, hit, orthonormal_basis, polynomials::solve_quartic, ring_extent, Primitive};
+use crate::materials::Material;
+use kea_gpu_shaderlib::{Aabb, Ray};
+use spirv_std::glam::{vec3, Vec3, Vec3A};
+
+// Needed for .sqrt()
+#[allow(unused_imports)]
+use spirv_std::num_traits::Float;
+
+/// A ring-shaped tube around an axis.
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct Torus {
+    centre: Vec3A,
+    axis: Vec3A,
+    /// The radius of the ring running down the middle of the tube.
+    major_radius: f32,
+    /// The radius of the tube.
+    minor_radius: f32,
+    material: Material,
+}
+
+impl Torus {
+    pub fn new(
+        centre: Vec3,
+        axis: Vec3,
+        major_radius: f32,
+        minor_radius: f32,
+        material: Material,
+    ) -> Self {
+        Self {
+            centre: Vec3A::from(centre),
+            axis: Vec3A::from(axis.normalize()),
+            major_radius,
+            minor_radius,
+            material,
+        }
+    }
+}
+
+impl Primitive for Torus {
+    fn aabb(&self) -> Aabb {
+        let extent =
+            ring_extent(Vec3::from(self.axis), self.major_radius) + Vec3::splat(self.minor_radius);
+
+        Aabb {
+            min: Vec3::from(self.centre) - extent,
+            max: Vec3::from(self.centre) + extent,
+        }
+    }
+
+    fn intersect_ray(&self, ray: Ray, t_min: f32) -> Option<f32> {
+        // Work in the torus's own space, with its axis along z and a unit
+        // direction.
+        let axis = Vec3::from(self.axis);
+        let (u, v) = orthonormal_basis(axis);
+        let offset = ray.origin - Vec3::from(self.centre);
+        let scale = ray.direction.length();
+        let direction = vec3(
+            ray.direction.dot(u),
+            ray.direction.dot(v),
+            ray.direction.dot(axis),
+        ) / scale;
+        let origin = vec3(offset.dot(u), offset.dot(v), offset.dot(axis));
+
+        // Starting from where the ray enters the bounding sphere keeps the
+        // quartic's coefficients small, which it needs for precision.
+        let bound = self.major_radius + self.minor_radius;
+        let n = origin.dot(direction);
+        let h = n * n - origin.length_squared() + bound * bound;
+        if h < 0.0 {
+            return None;
+        }
+        let start = (-n - h.sqrt()).max(0.0);
+        let origin = origin + direction * start;
+
+        // Points on the torus satisfy
+        // `(|p|² + R² - r²)² = 4 R² (p.x² + p.y²)`,
+        // which along the ray is a quartic in the distance.
+        let major_2 = self.major_radius * self.major_radius;
+        let minor_2 = self.minor_radius * self.minor_radius;
+        let n = origin.dot(direction);
+        let k = origin.length_squared() + major_2 - minor_2;
+        let roots = solve_quartic(
+            4.0 * n,
+            4.0 * n * n + 2.0 * k
+                - 4.0 * major_2 * (direction.x * direction.x + direction.y * direction.y),
+            4.0 * n * k - 8.0 * major_2 * (origin.x * direction.x + origin.y * direction.y),
+            k * k - 4.0 * major_2 * (origin.x * origin.x + origin.y * origin.y),
+        );
+
+        let mut closest = f32::INFINITY;
+        for i in 0..roots.count {
+            closest = closer(closest, (start + roots.values[i]) / scale, t_min);
+        }
+
+        hit(closest)
+    }
+
+    fn normal(&self, point: Vec3) -> Vec3 {
+        // Away from the nearest point on the ring.
+        let axis = Vec3::from(self.axis);
+        let offset = point - Vec3::from(self.centre);
+        let across = offset - axis * offset.dot(axis);
+        let ring = across.normalize_or_zero() * self.major_radius;
+
+        (offset - ring).normalize()
+    }
+
+    fn material(&self) -> Material {
+        self.material
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lying flat around the z axis, with a ring of radius 2 and a tube of
+    /// radius 0.5.
+    fn torus() -> Torus {
+        Torus::new(
+            Vec3::ZERO,
+            vec3(0.0, 0.0, 1.0),
+            2.0,
+            0.5,
+            Material::lambertian(Vec3A::ONE),
+        )
+    }
+
+    #[test]
+    fn hits_the_outside_edge() {
+        let ray = Ray {
+            origin: vec3(-5.0, 0.0, 0.0),
+            direction: vec3(1.0, 0.0, 0.0),
+        };
+
+        let distance = torus().intersect_ray(ray, 0.001).unwrap();
+        assert!((distance - 2.5).abs() < 1e-3);
+        assert!((torus().normal(ray.at(distance)) - vec3(-1.0, 0.0, 0.0)).length() < 1e-3);
+    }
+
+    #[test]
+    fn hits_the_top_of_the_tube() {
+        let ray = Ray {
+            origin: vec3(2.0, 0.0, 5.0),
+            direction: vec3(0.0, 0.0, -2.0),
+        };
+
+        let distance = torus().intersect_ray(ray, 0.001).unwrap();
+        assert!((distance - 2.25).abs() < 1e-3);
+        assert!((torus().normal(ray.at(distance)) - vec3(0.0, 0.0, 1.0)).length() < 1e-3);
+    }
+
+    #[test]
+    fn hits_the_inside_edge_beyond_t_min() {
+        let ray = Ray {
+            origin: vec3(-5.0, 0.0, 0.0),
+            direction: vec3(1.0, 0.0, 0.0),
+        };
+
+        let distance = torus().intersect_ray(ray, 3.0).unwrap();
+        assert!((distance - 3.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn misses_through_the_hole() {
+        let ray = Ray {
+            origin: vec3(0.0, 0.0, 5.0),
+            direction: vec3(0.0, 0.0, -1.0),
+        };
+
+        assert_eq!(torus().intersect_ray(ray, 0.001), None);
+    }
+
+    #[test]
+    fn hits_around_a_tilted_axis() {
+        let torus = Torus::new(
+            vec3(1.0, 1.0, 1.0),
+            vec3(0.0, 1.0, 0.0),
+            2.0,
+            0.5,
+            Material::lambertian(Vec3A::ONE),
+        );
+        let ray = Ray {
+            origin: vec3(1.0, 1.0, 6.0),
+            direction: vec3(0.0, 0.0, -1.0),
+        };
+
+        let distance = torus.intersect_ray(ray, 0.001).unwrap();
+        assert!((distance - 2.5).abs() < 1e-3);
+        assert!((torus.normal(ray.at(distance)) - vec3(0.0, 0.0, 1.0)).length() < 1e-3);
+    }
+}
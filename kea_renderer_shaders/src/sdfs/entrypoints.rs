@@ -0,0 +1,45 @@
+use spirv_std::spirv;
+
+use super::{Sdf, SdfNode};
+use crate::payload::RayPayload;
+use kea_gpu_shaderlib::Ray;
+use spirv_std::{arch::report_intersection, glam::Vec3};
+
+#[spirv(intersection)]
+pub fn intersect_sdf(
+    #[spirv(world_ray_origin)] ray_origin: Vec3,
+    #[spirv(world_ray_direction)] ray_direction: Vec3,
+    #[spirv(ray_tmin)] t_min: f32,
+    #[spirv(primitive_id)] sdf_id: usize,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 18)] sdfs: &[Sdf],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 19)] nodes: &[SdfNode],
+) {
+    let ray = Ray {
+        origin: ray_origin,
+        direction: ray_direction,
+    };
+
+    if let Some(hit) = sdfs[sdf_id].intersect_ray(nodes, ray, t_min) {
+        unsafe {
+            report_intersection(hit, 0);
+        }
+    }
+}
+
+#[spirv(closest_hit)]
+pub fn sdf_hit(
+    #[spirv(world_ray_origin)] ray_origin: Vec3,
+    #[spirv(world_ray_direction)] ray_direction: Vec3,
+    #[spirv(ray_tmax)] hit_max: f32,
+    #[spirv(incoming_ray_payload)] ray_payload: &mut RayPayload,
+    #[spirv(primitive_id)] sdf_id: usize,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 18)] sdfs: &[Sdf],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 19)] nodes: &[SdfNode],
+) {
+    let ray = Ray {
+        origin: ray_origin,
+        direction: ray_direction,
+    };
+
+    *ray_payload = sdfs[sdf_id].shade(nodes, ray, hit_max);
+}
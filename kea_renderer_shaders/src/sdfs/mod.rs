@@ -0,0 +1,47 @@
+//! Shapes defined by signed distance functions, sphere traced by their
+//! intersection shader. Each SDF is an expression tree of spheres, boxes and
+//! capsules, combined by unions, intersections, subtractions and smooth
+//! unions, with an AABB in the acceleration structure bounding it.
+//!
+//! Like the analytic primitives, emissive SDFs glow when hit, but aren't
+//! sampled as lights.
+
+pub mod entrypoints;
+mod sdf;
+
+use crate::{ShaderGroupId, SlotId};
+use core::any::TypeId;
+use kea_gpu_shaderlib::{
+    shaders::{Shader, ShaderGroup},
+    slots::{ShaderStages, Slot, SlotType},
+};
+pub use sdf::{Sdf, SdfNode, SdfNodeKind, MAX_SDF_STACK};
+
+const STAGES: ShaderStages = ShaderStages {
+    raygen: false,
+    intersection: true,
+    closest_hit: true,
+    miss: false,
+    compute: false,
+    vertex: false,
+    fragment: false,
+};
+
+pub const SHADER: (ShaderGroupId, ShaderGroup) = (
+    ShaderGroupId::SdfHit,
+    ShaderGroup::ProceduralHit {
+        intersection: Shader("sdfs::entrypoints::intersect_sdf"),
+        hit: Shader("sdfs::entrypoints::sdf_hit"),
+    },
+);
+
+pub const SLOT_SDFS: (SlotId, Slot) = (
+    SlotId::Sdfs,
+    Slot::new(SlotType::Buffer(TypeId::of::<&[Sdf]>()), STAGES),
+);
+
+/// The nodes of every SDF's expression tree, one range after another.
+pub const SLOT_NODES: (SlotId, Slot) = (
+    SlotId::SdfNodes,
+    Slot::new(SlotType::Buffer(TypeId::of::<&[SdfNode]>()), STAGES),
+);
@@ -0,0 +1,376 @@
+use crate::{materials::Material, payload::RayPayload};
+use kea_gpu_shaderlib::{Aabb, Ray};
+use spirv_std::glam::{vec3, Vec3, Vec3A};
+
+// Needed for .sqrt()
+#[allow(unused_imports)]
+use spirv_std::num_traits::Float;
+
+/// The deepest an SDF's expression tree can need its evaluation stack to be.
+pub const MAX_SDF_STACK: usize = 16;
+
+/// The most steps a ray takes towards the surface before giving up.
+const MAX_STEPS: u32 = 256;
+
+/// Rays hit the surface once they're this close to it, relative to how far
+/// they've gone, so distant surfaces converge as quickly as near ones.
+const SURFACE_TOLERANCE: f32 = 1e-4;
+
+/// The closest rays need to get to the surface to hit it, however close it
+/// is, so they don't run out of steps creeping up on it.
+const MIN_SURFACE_DISTANCE: f32 = 1e-5;
+
+/// The offset central differences take the gradient over.
+const NORMAL_EPSILON: f32 = 1e-4;
+
+/// What an SDF node is. A plain integer rather than an enum, so it can be read
+/// straight out of storage buffers on the GPU.
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct SdfNodeKind(u32);
+
+impl SdfNodeKind {
+    /// A sphere at `a`, of `radius`.
+    pub const SPHERE: Self = Self(0);
+    /// A box centred on `a`, with half its size in `b`.
+    pub const BOX: Self = Self(1);
+    /// A capsule from `a` to `b`, of `radius`.
+    pub const CAPSULE: Self = Self(2);
+    /// Everything in either of the last two shapes.
+    pub const UNION: Self = Self(3);
+    /// Everything in both of the last two shapes.
+    pub const INTERSECTION: Self = Self(4);
+    /// The second to last shape with the last one cut out of it.
+    pub const SUBTRACTION: Self = Self(5);
+    /// A union blending the last two shapes together over `radius`.
+    pub const SMOOTH_UNION: Self = Self(6);
+}
+
+/// A node of an SDF's expression tree: either a shape, or an operation
+/// combining the two before it. Trees are stored in postfix order, so they can
+/// be evaluated with a stack rather than recursion.
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct SdfNode {
+    pub kind: SdfNodeKind,
+    pub radius: f32,
+    pub a: Vec3A,
+    pub b: Vec3A,
+}
+
+impl SdfNode {
+    pub fn sphere(centre: Vec3, radius: f32) -> Self {
+        Self::shape(SdfNodeKind::SPHERE, centre, Vec3::ZERO, radius)
+    }
+
+    pub fn cuboid(centre: Vec3, half_size: Vec3) -> Self {
+        Self::shape(SdfNodeKind::BOX, centre, half_size, 0.0)
+    }
+
+    pub fn capsule(start: Vec3, end: Vec3, radius: f32) -> Self {
+        Self::shape(SdfNodeKind::CAPSULE, start, end, radius)
+    }
+
+    pub fn operation(kind: SdfNodeKind) -> Self {
+        Self::shape(kind, Vec3::ZERO, Vec3::ZERO, 0.0)
+    }
+
+    pub fn smooth_union(radius: f32) -> Self {
+        Self::shape(SdfNodeKind::SMOOTH_UNION, Vec3::ZERO, Vec3::ZERO, radius)
+    }
+
+    fn shape(kind: SdfNodeKind, a: Vec3, b: Vec3, radius: f32) -> Self {
+        Self {
+            kind,
+            radius,
+            a: Vec3A::from(a),
+            b: Vec3A::from(b),
+        }
+    }
+
+    /// Whether the node is a shape, rather than an operation.
+    pub fn is_shape(&self) -> bool {
+        self.kind == SdfNodeKind::SPHERE
+            || self.kind == SdfNodeKind::BOX
+            || self.kind == SdfNodeKind::CAPSULE
+    }
+
+    /// The signed distance from `point` to a shape node.
+    fn distance(&self, point: Vec3) -> f32 {
+        let a = Vec3::from(self.a);
+        let b = Vec3::from(self.b);
+
+        if self.kind == SdfNodeKind::SPHERE {
+            (point - a).length() - self.radius
+        } else if self.kind == SdfNodeKind::BOX {
+            let q = (point - a).abs() - b;
+            q.max(Vec3::ZERO).length() + q.max_element().min(0.0)
+        } else {
+            let along = point - a;
+            let axis = b - a;
+            let h =
+                (along.dot(axis) / axis.length_squared().max(f32::MIN_POSITIVE)).clamp(0.0, 1.0);
+            (along - axis * h).length() - self.radius
+        }
+    }
+
+    /// The distance to the combination of two shapes `a` and `b` apart, by an
+    /// operation node.
+    fn combine(&self, a: f32, b: f32) -> f32 {
+        if self.kind == SdfNodeKind::UNION {
+            a.min(b)
+        } else if self.kind == SdfNodeKind::INTERSECTION {
+            a.max(b)
+        } else if self.kind == SdfNodeKind::SUBTRACTION {
+            a.max(-b)
+        } else {
+            smooth_min(a, b, self.radius)
+        }
+    }
+}
+
+/// The polynomial smooth minimum, from Inigo Quilez, which blends `a` and `b`
+/// where they're within `k` of each other.
+fn smooth_min(a: f32, b: f32, k: f32) -> f32 {
+    if k <= 0.0 {
+        return a.min(b);
+    }
+
+    let h = (k - (a - b).abs()).max(0.0) / k;
+    a.min(b) - h * h * k / 4.0
+}
+
+/// A shape defined by a signed distance function, sphere traced by its
+/// intersection shader. Its expression tree is a range of the SDF node buffer.
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct Sdf {
+    first_node: u32,
+    node_count: u32,
+    /// The bounds of the shape, which rays are only traced through.
+    min: Vec3A,
+    max: Vec3A,
+    material: Material,
+}
+
+impl Sdf {
+    pub fn new(first_node: u32, node_count: u32, aabb: Aabb, material: Material) -> Self {
+        Self {
+            first_node,
+            node_count,
+            min: Vec3A::from(aabb.min),
+            max: Vec3A::from(aabb.max),
+            material,
+        }
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        Aabb {
+            min: Vec3::from(self.min),
+            max: Vec3::from(self.max),
+        }
+    }
+
+    pub fn material(&self) -> Material {
+        self.material
+    }
+
+    /// The signed distance from `point` to the surface, negative inside.
+    pub fn distance(&self, nodes: &[SdfNode], point: Vec3) -> f32 {
+        let mut stack = [0.0; MAX_SDF_STACK];
+        let mut depth = 0;
+
+        for index in self.first_node..self.first_node + self.node_count {
+            let node = nodes[index as usize];
+            if node.is_shape() {
+                stack[depth] = node.distance(point);
+                depth += 1;
+            } else {
+                depth -= 1;
+                stack[depth - 1] = node.combine(stack[depth - 1], stack[depth]);
+            }
+        }
+
+        stack[0]
+    }
+
+    /// The distance along `ray` to the first hit beyond `t_min`. Rays
+    /// starting inside the shape hit it on the way out.
+    pub fn intersect_ray(&self, nodes: &[SdfNode], ray: Ray, t_min: f32) -> Option<f32> {
+        let inverse_direction = ray.direction.recip();
+        let t0 = (Vec3::from(self.min) - ray.origin) * inverse_direction;
+        let t1 = (Vec3::from(self.max) - ray.origin) * inverse_direction;
+        let far = t0.max(t1).min_element();
+        let mut t = t0.min(t1).max_element().max(t_min);
+        if t > far {
+            return None;
+        }
+
+        // Distances are in world space, but t is in units of the direction.
+        let scale = ray.direction.length();
+        let side = if self.distance(nodes, ray.at(t)) < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+
+        for _ in 0..MAX_STEPS {
+            let distance = self.distance(nodes, ray.at(t)) * side;
+            if distance < (SURFACE_TOLERANCE * t * scale).max(MIN_SURFACE_DISTANCE) {
+                return Some(t);
+            }
+
+            t += distance / scale;
+            if t > far {
+                return None;
+            }
+        }
+
+        None
+    }
+
+    /// The outward normal at `point`, from the gradient of the distance
+    /// function by central differences.
+    pub fn normal(&self, nodes: &[SdfNode], point: Vec3) -> Vec3 {
+        let x = vec3(NORMAL_EPSILON, 0.0, 0.0);
+        let y = vec3(0.0, NORMAL_EPSILON, 0.0);
+        let z = vec3(0.0, 0.0, NORMAL_EPSILON);
+
+        vec3(
+            self.distance(nodes, point + x) - self.distance(nodes, point - x),
+            self.distance(nodes, point + y) - self.distance(nodes, point - y),
+            self.distance(nodes, point + z) - self.distance(nodes, point - z),
+        )
+        .normalize_or_zero()
+    }
+
+    /// The payload for a ray hitting the shape `distance` along it, as the
+    /// closest hit shader fills it in.
+    pub fn shade(&self, nodes: &[SdfNode], ray: Ray, distance: f32) -> RayPayload {
+        let normal = self.normal(nodes, ray.at(distance));
+
+        RayPayload {
+            hit: Some(distance),
+            normal,
+            shading_normal: normal,
+            material: self.material,
+            // They aren't lights, so nothing else could have sampled them.
+            area: f32::INFINITY,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spirv_std::glam::Vec3A;
+
+    const MATERIAL: Material = Material::lambertian(Vec3A::ONE);
+
+    fn sdf(nodes: &[SdfNode], min: Vec3, max: Vec3) -> Sdf {
+        Sdf::new(0, nodes.len() as u32, Aabb { min, max }, MATERIAL)
+    }
+
+    fn towards_z(origin: Vec3) -> Ray {
+        Ray {
+            origin,
+            direction: vec3(0.0, 0.0, -1.0),
+        }
+    }
+
+    #[test]
+    fn sphere_traces_a_sphere() {
+        let nodes = [SdfNode::sphere(vec3(0.0, 0.0, -5.0), 1.0)];
+        let sdf = sdf(&nodes, Vec3::splat(-10.0), Vec3::splat(10.0));
+
+        let hit = sdf
+            .intersect_ray(&nodes, towards_z(Vec3::ZERO), 0.001)
+            .unwrap();
+        assert!((hit - 4.0).abs() < 1e-3);
+
+        let normal = sdf.normal(&nodes, vec3(0.0, 0.0, -4.0));
+        assert!((normal - vec3(0.0, 0.0, 1.0)).length() < 1e-2);
+    }
+
+    #[test]
+    fn rays_inside_hit_on_the_way_out() {
+        let nodes = [SdfNode::cuboid(vec3(0.0, 0.0, -5.0), Vec3::ONE)];
+        let sdf = sdf(&nodes, Vec3::splat(-10.0), Vec3::splat(10.0));
+
+        let hit = sdf
+            .intersect_ray(&nodes, towards_z(vec3(0.0, 0.0, -5.0)), 0.001)
+            .unwrap();
+        assert!((hit - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn misses_outside_the_bounds() {
+        let nodes = [SdfNode::sphere(vec3(0.0, 0.0, -5.0), 1.0)];
+        let sdf = sdf(&nodes, vec3(-1.0, -1.0, -6.0), vec3(1.0, 1.0, -4.0));
+
+        assert!(sdf
+            .intersect_ray(&nodes, towards_z(vec3(2.0, 0.0, 0.0)), 0.001)
+            .is_none());
+    }
+
+    #[test]
+    fn subtraction_cuts_out_the_second_shape() {
+        // A box with a hole through it along z.
+        let nodes = [
+            SdfNode::cuboid(vec3(0.0, 0.0, -5.0), Vec3::ONE),
+            SdfNode::capsule(vec3(0.0, 0.0, -7.0), vec3(0.0, 0.0, -3.0), 0.5),
+            SdfNode::operation(SdfNodeKind::SUBTRACTION),
+        ];
+        let sdf = sdf(&nodes, Vec3::splat(-10.0), Vec3::splat(10.0));
+
+        assert!(sdf.distance(&nodes, vec3(0.0, 0.0, -5.0)) > 0.0);
+        assert!(sdf.distance(&nodes, vec3(0.75, 0.0, -5.0)) < 0.0);
+        assert!(sdf
+            .intersect_ray(&nodes, towards_z(Vec3::ZERO), 0.001)
+            .is_none());
+    }
+
+    #[test]
+    fn operations_combine_the_last_two_values() {
+        let nodes = [
+            SdfNode::sphere(vec3(-0.5, 0.0, 0.0), 1.0),
+            SdfNode::sphere(vec3(0.5, 0.0, 0.0), 1.0),
+            SdfNode::operation(SdfNodeKind::INTERSECTION),
+            SdfNode::sphere(vec3(0.0, 3.0, 0.0), 0.5),
+            SdfNode::operation(SdfNodeKind::UNION),
+        ];
+        let sdf = sdf(&nodes, Vec3::splat(-10.0), Vec3::splat(10.0));
+
+        // Only the lens where the first two overlap, and the third sphere.
+        assert!(sdf.distance(&nodes, Vec3::ZERO) < 0.0);
+        assert!(sdf.distance(&nodes, vec3(-1.0, 0.0, 0.0)) > 0.0);
+        assert!(sdf.distance(&nodes, vec3(0.0, 3.0, 0.0)) < 0.0);
+    }
+
+    #[test]
+    fn smooth_union_fills_in_between() {
+        let union = [
+            SdfNode::sphere(vec3(-1.0, 0.0, 0.0), 0.9),
+            SdfNode::sphere(vec3(1.0, 0.0, 0.0), 0.9),
+            SdfNode::operation(SdfNodeKind::UNION),
+        ];
+        let mut smooth = union;
+        smooth[2] = SdfNode::smooth_union(0.5);
+
+        let bounds = (Vec3::splat(-10.0), Vec3::splat(10.0));
+        let union_sdf = sdf(&union, bounds.0, bounds.1);
+        let smooth_sdf = sdf(&smooth, bounds.0, bounds.1);
+
+        assert!(union_sdf.distance(&union, Vec3::ZERO) > 0.0);
+        assert!(smooth_sdf.distance(&smooth, Vec3::ZERO) < 0.0);
+        // Far from the join, it's the same as the union.
+        let far = vec3(-2.5, 0.0, 0.0);
+        assert_eq!(
+            union_sdf.distance(&union, far),
+            smooth_sdf.distance(&smooth, far)
+        );
+    }
+}
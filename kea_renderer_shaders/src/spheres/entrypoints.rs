@@ -11,7 +11,7 @@ pub fn sphere_hit(
     #[spirv(ray_tmax)] hit_max: f32,
     #[spirv(incoming_ray_payload)] ray_payload: &mut RayPayload,
     #[spirv(primitive_id)] sphere_id: usize,
-    #[spirv(storage_buffer, descriptor_set = 0, binding = 2)] spheres: &mut [Sphere],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] spheres: &mut [Sphere],
 ) {
     let sphere = spheres[sphere_id];
     ray_payload.hit = Some(hit_max);
@@ -19,8 +19,10 @@ pub fn sphere_hit(
         origin: ray_origin,
         direction: ray_direction,
     });
+    ray_payload.shading_normal = ray_payload.normal;
 
     ray_payload.material = sphere.material();
+    ray_payload.area = sphere.area();
 }
 
 #[spirv(intersection)]
@@ -28,7 +30,7 @@ pub fn intersect_sphere(
     #[spirv(world_ray_origin)] ray_origin: Vec3,
     #[spirv(world_ray_direction)] ray_direction: Vec3,
     #[spirv(primitive_id)] sphere_id: usize,
-    #[spirv(storage_buffer, descriptor_set = 0, binding = 2)] spheres: &mut [Sphere],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 1)] spheres: &mut [Sphere],
 ) {
     let sphere = spheres[sphere_id];
 
@@ -25,6 +25,10 @@ pub const SLOT: (SlotId, Slot) = (
             raygen: false,
             intersection: true,
             closest_hit: true,
+            miss: false,
+            compute: false,
+            vertex: false,
+            fragment: false,
         },
     ),
 );
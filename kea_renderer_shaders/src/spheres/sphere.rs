@@ -40,6 +40,10 @@ impl Sphere {
         self.radius
     }
 
+    pub fn area(&self) -> f32 {
+        4.0 * core::f32::consts::PI * self.radius * self.radius
+    }
+
     pub fn intersect_ray(&self, ray: Ray) -> Option<f32> {
         // A sphere's implicit formula is:
         // `||x - c||^2 = r^2`
@@ -0,0 +1,34 @@
+use spirv_std::spirv;
+
+use crate::path_tracer::sampling::{tone_map as tone_map_light, WHITE_POINT};
+use spirv_std::{
+    glam::{UVec2, UVec3, Vec4, Vec4Swizzles},
+    Image,
+};
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct PushConstants {
+    /// The size of the images, which the workgroups may overhang.
+    pub size: UVec2,
+}
+
+/// Tone map each pixel of the light image into the output image. The thread
+/// counts match `WORKGROUP_SIZE`.
+#[spirv(compute(threads(8, 8)))]
+pub fn tone_map(
+    #[spirv(global_invocation_id)] id: UVec3,
+    #[spirv(descriptor_set = 0, binding = 0)] light_image: &Image!(2D, format=rgba32f, sampled=false),
+    #[spirv(descriptor_set = 0, binding = 1)] output_image: &Image!(2D, format=rgba32f, sampled=false),
+    #[spirv(push_constant)] constants: &PushConstants,
+) {
+    let pixel = UVec2::new(id.x, id.y);
+    if pixel.x >= constants.size.x || pixel.y >= constants.size.y {
+        return;
+    }
+
+    let light: Vec4 = light_image.read(pixel);
+    unsafe {
+        output_image.write(pixel, tone_map_light(light.xyz(), WHITE_POINT).extend(1.0));
+    }
+}
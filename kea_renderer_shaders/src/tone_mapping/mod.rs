@@ -0,0 +1,48 @@
+//! A compute pass turning the light accumulated by the path tracer into the
+//! colours shown, run after each iteration.
+
+pub mod entrypoints;
+
+use kea_gpu_shaderlib::{
+    shaders::Shader,
+    slots::{ShaderStages, Slot, SlotType},
+};
+
+/// The slots of the tone mapping pipeline, which has a layout of its own.
+#[derive(Copy, Clone, Hash, PartialEq, Eq)]
+pub enum ToneMappingSlotId {
+    LightImage = 0,
+    OutputImage,
+}
+
+impl From<ToneMappingSlotId> for u32 {
+    fn from(slot_id: ToneMappingSlotId) -> u32 {
+        slot_id as u32
+    }
+}
+
+const STAGES: ShaderStages = ShaderStages {
+    raygen: false,
+    intersection: false,
+    closest_hit: false,
+    miss: false,
+    compute: true,
+    vertex: false,
+    fragment: false,
+};
+
+pub const SLOTS: [(ToneMappingSlotId, Slot); 2] = [
+    (
+        ToneMappingSlotId::LightImage,
+        Slot::new(SlotType::Image, STAGES),
+    ),
+    (
+        ToneMappingSlotId::OutputImage,
+        Slot::new(SlotType::Image, STAGES),
+    ),
+];
+
+pub const SHADER: Shader = Shader("tone_mapping::entrypoints::tone_map");
+
+/// The pixels each workgroup of the shader covers, across and down.
+pub const WORKGROUP_SIZE: (u32, u32) = (8, 8);
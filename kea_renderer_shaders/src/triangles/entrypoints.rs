@@ -1,33 +1,94 @@
 use spirv_std::spirv;
 
-use super::Mesh;
-use crate::payload::RayPayload;
-use spirv_std::glam::{vec3, Vec3};
+use super::{shade_triangle, Mesh, Vertex};
+use crate::{materials::BoundTextures, payload::RayPayload};
+use spirv_std::{
+    glam::{vec3, Vec2, Vec3, Vec3A},
+    matrix::Matrix4x3,
+    Image, RuntimeArray, Sampler, TypedBuffer,
+};
 
 #[spirv(closest_hit)]
+#[allow(clippy::too_many_arguments)]
 pub fn triangle_hit(
     #[spirv(ray_tmax)] hit_max: f32,
-    #[spirv(world_ray_direction)] ray_direction: Vec3,
+    #[spirv(object_to_world)] object_to_world: Matrix4x3,
+    #[spirv(world_to_object)] world_to_object: Matrix4x3,
     #[spirv(incoming_ray_payload)] ray_payload: &mut RayPayload,
-    #[spirv(storage_buffer, descriptor_set = 0, binding = 3)] meshes: &[Mesh],
+    #[spirv(hit_attribute)] barycentrics: &mut Vec2,
     #[spirv(hit_triangle_vertex_positions)] points: [Vec3; 3],
     #[spirv(instance_custom_index)] mesh_id: usize,
+    #[spirv(primitive_id)] triangle_id: usize,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 2)] meshes: &[Mesh],
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 8)] vertices: &RuntimeArray<
+        TypedBuffer<[Vertex]>,
+    >,
+    #[spirv(storage_buffer, descriptor_set = 0, binding = 9)] indices: &RuntimeArray<
+        TypedBuffer<[u32]>,
+    >,
+    #[spirv(descriptor_set = 0, binding = 10)] textures: &RuntimeArray<
+        Image!(2D, type=f32, sampled),
+    >,
+    #[spirv(descriptor_set = 0, binding = 11)] sampler: &Sampler,
 ) {
-    let u = points[1] - points[0];
-    let v = points[2] - points[0];
-    let mut normal = vec3(
-        u.y * v.z - u.z * v.y,
-        u.z * v.x - u.x * v.z,
-        u.x * v.y - u.y * v.x,
+    let mesh = meshes[mesh_id];
+    let vertices = unsafe { vertices.index(mesh.asset as usize) };
+    let indices = unsafe { indices.index(mesh.asset as usize) };
+    let first_index = triangle_id * 3;
+    let corners = [
+        vertices[indices[first_index] as usize],
+        vertices[indices[first_index + 1] as usize],
+        vertices[indices[first_index + 2] as usize],
+    ];
+
+    // The fetched positions and vertex attributes are in object space.
+    *ray_payload = shade_triangle(
+        &mesh,
+        [
+            transform_point(&object_to_world, points[0]),
+            transform_point(&object_to_world, points[1]),
+            transform_point(&object_to_world, points[2]),
+        ],
+        [
+            vertex_to_world(&object_to_world, &world_to_object, corners[0]),
+            vertex_to_world(&object_to_world, &world_to_object, corners[1]),
+            vertex_to_world(&object_to_world, &world_to_object, corners[2]),
+        ],
+        *barycentrics,
+        hit_max,
+        &BoundTextures {
+            images: textures,
+            sampler,
+        },
     );
+}
 
-    if ray_direction.dot(normal) > 0.0 {
-        normal = normal * -1.0;
-    }
+fn transform_point(matrix: &Matrix4x3, point: Vec3) -> Vec3 {
+    Vec3::from(
+        matrix.x_axis * point.x + matrix.y_axis * point.y + matrix.z_axis * point.z + matrix.w_axis,
+    )
+}
+
+/// Move a vertex's normal and tangent from object to world space. Normals are
+/// transformed by the inverse transpose, so they stay perpendicular to the
+/// surface under non-uniform scaling.
+fn vertex_to_world(
+    object_to_world: &Matrix4x3,
+    world_to_object: &Matrix4x3,
+    vertex: Vertex,
+) -> Vertex {
+    let normal = vertex.normal;
+    let tangent = vertex.tangent;
 
-    *ray_payload = RayPayload {
-        hit: Some(hit_max),
-        material: meshes[mesh_id].material,
-        normal,
-    };
+    Vertex {
+        normal: Vec3A::from(vec3(
+            world_to_object.x_axis.dot(normal),
+            world_to_object.y_axis.dot(normal),
+            world_to_object.z_axis.dot(normal),
+        )),
+        tangent: object_to_world.x_axis * tangent.x
+            + object_to_world.y_axis * tangent.y
+            + object_to_world.z_axis * tangent.z,
+        ..vertex
+    }
 }
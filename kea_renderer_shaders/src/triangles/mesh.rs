@@ -1,7 +1,12 @@
-use crate::materials::Material;
+use crate::materials::{Material, MaterialTextures};
 
+/// An instance of a mesh asset, with the material it's drawn in. Any number
+/// of instances can share an asset's vertices and acceleration structure.
 #[derive(Clone, Copy)]
 #[repr(C)]
 pub struct Mesh {
     pub material: Material,
+    pub textures: MaterialTextures,
+    /// The asset ID of the vertex and index buffers it uses.
+    pub asset: u32,
 }
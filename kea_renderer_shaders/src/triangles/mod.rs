@@ -7,8 +7,12 @@ use kea_gpu_shaderlib::{
 
 pub mod entrypoints;
 mod mesh;
+mod triangle;
+mod vertex;
 
 pub use mesh::Mesh;
+pub use triangle::{face_normal, shade_triangle};
+pub use vertex::Vertex;
 
 pub const SHADER: (ShaderGroupId, ShaderGroup) = (
     ShaderGroupId::TriangleHit,
@@ -23,6 +27,52 @@ pub const SLOT_MESHES: (SlotId, Slot) = (
             raygen: false,
             intersection: false,
             closest_hit: true,
+            miss: false,
+            compute: false,
+            vertex: false,
+            fragment: false,
         },
     ),
 );
+
+/// The most mesh assets a scene can have. Each can have any number of
+/// instances.
+pub const MAX_MESHES: u32 = 4096;
+
+/// A buffer of shading attributes for each mesh asset's vertices, indexed by
+/// asset ID.
+pub const SLOT_VERTICES: (SlotId, Slot) = (
+    SlotId::MeshVertices,
+    Slot::array(
+        SlotType::Buffer(TypeId::of::<&[Vertex]>()),
+        ShaderStages {
+            raygen: false,
+            intersection: false,
+            closest_hit: true,
+            miss: false,
+            compute: false,
+            vertex: false,
+            fragment: false,
+        },
+        MAX_MESHES,
+    ),
+);
+
+/// A buffer of vertex indices for each mesh asset's triangles, indexed by
+/// asset ID.
+pub const SLOT_INDICES: (SlotId, Slot) = (
+    SlotId::MeshIndices,
+    Slot::array(
+        SlotType::Buffer(TypeId::of::<&[u32]>()),
+        ShaderStages {
+            raygen: false,
+            intersection: false,
+            closest_hit: true,
+            miss: false,
+            compute: false,
+            vertex: false,
+            fragment: false,
+        },
+        MAX_MESHES,
+    ),
+);
@@ -0,0 +1,77 @@
+use super::{Mesh, Vertex};
+use crate::{materials::Textures, payload::RayPayload};
+use spirv_std::glam::{vec3, Vec2, Vec3};
+
+/// The unnormalised normal of a triangle, following the winding of its
+/// vertices.
+pub fn face_normal(points: [Vec3; 3]) -> Vec3 {
+    let u = points[1] - points[0];
+    let v = points[2] - points[0];
+
+    vec3(
+        u.y * v.z - u.z * v.y,
+        u.z * v.x - u.x * v.z,
+        u.x * v.y - u.y * v.x,
+    )
+}
+
+/// The payload for a ray hitting a triangle of `mesh` `distance` along it.
+/// `barycentrics` are the weights of the second and third vertices at the
+/// hit, and the vertex positions and attributes must be in world space.
+pub fn shade_triangle<X: Textures>(
+    mesh: &Mesh,
+    points: [Vec3; 3],
+    vertices: [Vertex; 3],
+    barycentrics: Vec2,
+    distance: f32,
+    textures: &X,
+) -> RayPayload {
+    let face_normal = face_normal(points);
+    let area = face_normal.length() / 2.0;
+    let normal = face_normal.normalize();
+
+    let weights = vec3(
+        1.0 - barycentrics.x - barycentrics.y,
+        barycentrics.x,
+        barycentrics.y,
+    );
+    let uv = vertices[0].uv * weights.x + vertices[1].uv * weights.y + vertices[2].uv * weights.z;
+    let material = mesh.textures.apply(mesh.material, textures, uv);
+
+    let interpolated_normal = Vec3::from(
+        vertices[0].normal * weights.x
+            + vertices[1].normal * weights.y
+            + vertices[2].normal * weights.z,
+    );
+    let mut shading_normal = if interpolated_normal.length_squared() > 0.0 {
+        interpolated_normal.normalize()
+    } else {
+        normal
+    };
+
+    let tangent = Vec3::from(
+        vertices[0].tangent * weights.x
+            + vertices[1].tangent * weights.y
+            + vertices[2].tangent * weights.z,
+    );
+    shading_normal = mesh.textures.normal_mapped(
+        textures,
+        uv,
+        shading_normal,
+        tangent,
+        vertices[0].bitangent_sign,
+    );
+
+    // Vertex normals can disagree with the winding of the triangle.
+    if shading_normal.dot(normal) < 0.0 {
+        shading_normal *= -1.0;
+    }
+
+    RayPayload {
+        hit: Some(distance),
+        normal,
+        shading_normal,
+        material,
+        area,
+    }
+}
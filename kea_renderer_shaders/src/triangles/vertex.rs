@@ -0,0 +1,32 @@
+use spirv_std::glam::{Vec2, Vec3A};
+
+/// How a mesh is shaded around a vertex. Vertex positions live in the buffer
+/// the acceleration structure was built from, so aren't repeated here.
+#[cfg_attr(not(target_arch = "spirv"), derive(Debug))]
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct Vertex {
+    /// Unit normal to interpolate across the triangles using the vertex, or
+    /// zero to shade them flat.
+    pub normal: Vec3A,
+    /// Unit tangent pointing along increasing U, for normal mapping. Zero if
+    /// unknown.
+    pub tangent: Vec3A,
+    /// Texture coordinates, with (0, 0) at the top left of textures.
+    pub uv: Vec2,
+    /// 1 or -1, multiplying the cross product of the normal and tangent to
+    /// give the bitangent, which the green channel of normal maps points
+    /// along. As with glTF tangents, this is 1 when textures are mapped the
+    /// right way round, with the bitangent pointing up them.
+    pub bitangent_sign: f32,
+}
+
+impl Vertex {
+    /// A vertex without shading attributes, for flat shaded meshes.
+    pub const FLAT: Self = Self {
+        normal: Vec3A::ZERO,
+        tangent: Vec3A::ZERO,
+        uv: Vec2::ZERO,
+        bitangent_sign: 1.0,
+    };
+}